@@ -1,37 +1,434 @@
-use std::cell::RefCell;
+use std::{
+    cell::RefCell,
+    io,
+    path::PathBuf,
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
 
 use anyhow::{anyhow, Result};
 use gag::Gag;
 
 use promkit::{
     crossterm::{
-        event::Event,
+        event::{self, Event},
         style::{Attribute, Attributes, Color},
     },
-    json::{self, JsonBundle, JsonNode, JsonPathSegment},
+    json::{self, JsonNode},
     keymap::KeymapManager,
     listbox, serde_json,
     snapshot::Snapshot,
     style::StyleBuilder,
-    suggest::Suggest,
-    text, text_editor, Prompt, PromptSignal, Renderer,
+    text, text_editor, Evaluator, Prompt, PromptSignal, Renderer,
 };
+use unicode_width::UnicodeWidthChar;
 
+mod analysis;
+mod bench;
+mod budget;
+mod builtins;
+mod changes;
+#[cfg(unix)]
+mod control;
+mod damage;
+mod diff;
+mod edit;
+mod export;
+mod formats;
+mod grapheme;
+mod history;
+mod hooks;
 mod keymap;
+mod loading;
+mod macros;
+mod marks;
+mod metrics;
+mod navigate;
+mod pager;
+mod paths;
+mod pipe;
+mod ranking;
+mod refresh;
 mod render;
+mod replay;
+mod report;
+mod settings;
+mod store;
+mod theme;
 mod trie;
+mod wake;
+mod wizard;
+mod worker;
+mod yank;
+pub use bench::bench;
+use damage::{PaneIndex, FRAME_INTERVAL};
+pub use edit::Edits;
+pub use export::ansi;
+pub use formats::{escape_non_ascii, is_yaml, yaml_to_json, OutputFormat};
+pub use hooks::check as check_hooks;
+use hooks::Hooks;
+use loading::Loading;
+pub use loading::PLACEHOLDER;
+use navigate::key_segment;
+pub use pager::use_alt_screen;
+use paths::{Kind, PathIndex};
+use ranking::Ranking;
+use refresh::Refresh;
+pub use render::{CaseSensitivity, ColorChoice, Layout, Verbosity};
+pub use replay::parse_events;
+pub use report::{include_data_in_reports, log_message, write_report};
+pub use store::set_history_size;
+use store::Store;
+pub use theme::Theme;
 use trie::QueryTrie;
+pub use wake::SUPPORTED as WAKE_SUPPORTED;
+pub use worker::{serve as serve_jq_worker, ENV as JQ_WORKER_ENV};
+pub use yank::paste_from_clipboard;
+
+/// The file keeping the last accepted query for each input.
+pub(crate) const QUERIES_FILE: &str = "queries.json";
+
+/// Splits the input into the JSON documents it consists of.
+///
+/// Like jq, the input may contain multiple JSON values back-to-back
+/// (e.g. `{"a": 1} {"a": 2}` or JSON Lines). Each document keeps its
+/// original text so that the order of keys is preserved for the viewer.
+fn split_documents(input: &str) -> Result<Vec<String>> {
+    let mut documents = Vec::new();
+    let mut stream = serde_json::Deserializer::from_str(input).into_iter::<serde_json::Value>();
+    let mut start = 0;
+    while let Some(value) = stream.next() {
+        value?;
+        let end = stream.byte_offset();
+        documents.push(input[start..end].trim().to_string());
+        start = end;
+    }
+    Ok(documents)
+}
+
+/// Collects the paths in the documents to be suggested,
+/// along with the number of documents containing each path.
+fn index(documents: &[String]) -> Result<PathIndex> {
+    let mut index = PathIndex::default();
+    extend_index(&mut index, documents)?;
+    Ok(index)
+}
+
+/// Adds the paths in the documents to the index.
+fn extend_index(index: &mut PathIndex, documents: &[String]) -> Result<()> {
+    for document in documents {
+        let mut paths = Vec::new();
+        collect_paths(
+            &JsonNode::try_new(document, None)?,
+            &mut Vec::new(),
+            &mut paths,
+        );
+        // Documents in a stream often share the same structure,
+        // so the same path is kept only once.
+        index.add_document(paths);
+    }
+    Ok(())
+}
+
+/// Collects the path of every value in the node, as jq segments,
+/// with the kind of the value.
+fn collect_paths(node: &JsonNode, path: &mut Vec<String>, paths: &mut Vec<(Vec<String>, Kind)>) {
+    match node {
+        JsonNode::Object { children, .. } => {
+            paths.push((path.clone(), Kind::Object));
+            for (key, child) in children {
+                path.push(key_segment(key));
+                collect_paths(child, path, paths);
+                path.pop();
+            }
+        }
+        JsonNode::Array { children, .. } => {
+            paths.push((path.clone(), Kind::Array));
+            for (index, child) in children.iter().enumerate() {
+                path.push(format!("[{}]", index));
+                collect_paths(child, path, paths);
+                path.pop();
+            }
+        }
+        JsonNode::Leaf(_) => paths.push((path.clone(), Kind::Scalar)),
+    }
+}
+
+/// Describes the value at the path for the hint pane
+/// (e.g. `.users[0].email → string "a@b.c"`),
+/// if the query is a plain path like `.users[0].email`,
+/// listing the first keys of an object to tell what can be typed next.
+fn describe_path(query: &str, result: &str) -> Option<String> {
+    /// The number of chars previewed at most.
+    const PREVIEW_LENGTH: usize = 40;
+    /// The number of keys of an object listed at most.
+    const LISTED_KEYS: usize = 8;
+
+    let mut rest = query.strip_prefix('.')?;
+    while !rest.is_empty() {
+        let end = if let Some(index) = rest.strip_prefix('[') {
+            let digits = index.find(']')?;
+            if digits == 0 || !index[..digits].chars().all(|ch| ch.is_ascii_digit()) {
+                return None;
+            }
+            digits + 2
+        } else {
+            let key = rest.strip_prefix('.').unwrap_or(rest);
+            let quoted = key
+                .strip_prefix('"')
+                .and_then(|key| key.find('"').map(|end| end + 2));
+            let len = match quoted {
+                Some(len) => len,
+                None => key
+                    .find(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+                    .unwrap_or(key.len()),
+            };
+            if len == 0 {
+                return None;
+            }
+            len + rest.len() - key.len()
+        };
+        rest = &rest[end..];
+    }
+
+    let value = serde_json::from_str::<serde_json::Value>(result).ok()?;
+    let description = match &value {
+        serde_json::Value::Object(map) => format!("object with {} keys", map.len()),
+        serde_json::Value::Array(array) => format!("array of {} values", array.len()),
+        serde_json::Value::String(_) => format!("string {}", value),
+        serde_json::Value::Number(_) => format!("number {}", value),
+        serde_json::Value::Bool(_) => format!("boolean {}", value),
+        serde_json::Value::Null => "null".to_string(),
+    };
+    let mut preview = description.chars().take(PREVIEW_LENGTH).collect::<String>();
+    if preview.len() < description.len() {
+        preview.push('…');
+    }
+    let mut ret = format!("{} → {}", query, preview);
+    if let serde_json::Value::Object(map) = &value {
+        let keys = map
+            .keys()
+            .take(LISTED_KEYS)
+            .map(|key| key_segment(key))
+            .collect::<Vec<_>>();
+        if !keys.is_empty() {
+            ret.push_str(&format!(": {}", keys.join(" ")));
+        }
+        if map.len() > LISTED_KEYS {
+            ret.push_str(" …");
+        }
+    }
+    Some(ret)
+}
+
+/// How long an evaluation may take before the next ones wait for a pause in typing.
+const SLOW_EVALUATION: Duration = Duration::from_millis(20);
+/// The longest pause waited for before evaluating.
+const MAX_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Returns how long to wait for the next key before evaluating the query,
+/// given how long the last evaluation took: nothing for a quick one,
+/// and half as long for a slow one, so that the keys typed meanwhile
+/// don't each wait for an evaluation whose results are replaced right away.
+fn debounce(eval_time: Duration) -> Duration {
+    if eval_time < SLOW_EVALUATION {
+        Duration::ZERO
+    } else {
+        (eval_time / 2).min(MAX_DEBOUNCE)
+    }
+}
+
+/// The jq function definitions available in every query (e.g. from plugins).
+static DEFINITIONS: OnceLock<String> = OnceLock::new();
+
+/// Makes the jq function definitions (e.g. `def celsius: (. - 32) * 5 / 9;`)
+/// available in every query, once for the process.
+pub fn define_jq_functions(definitions: String) {
+    let _ = DEFINITIONS.set(definitions);
+}
+
+/// Checks that the jq function definitions compile, without the ones defined already.
+pub fn check_jq_functions(definitions: &str) -> Result<()> {
+    let ignore_err = Gag::stderr().ok();
+    let ret = j9::run(&format!("{}\nnull", definitions), "null");
+    drop(ignore_err);
+    ret.map(drop)
+        .map_err(|_| anyhow!("The jq function definitions fail to compile"))
+}
+
+/// How long a query may run before it is abandoned, if limited.
+static EVAL_TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+/// Limits how long every query may run, once for the process.
+pub fn set_eval_timeout(timeout: Duration) {
+    let _ = EVAL_TIMEOUT.set(timeout);
+}
+
+/// The error of a query that ran past the timeout.
+#[derive(Debug)]
+struct Timeout(Duration);
+
+impl std::fmt::Display for Timeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The query timed out after {:?}", self.0)
+    }
+}
+
+impl std::error::Error for Timeout {}
+
+/// Runs the jq query against the JSON, giving up after the timeout if set.
+///
+/// libjq can't be interrupted, so given a timeout the query runs
+/// in a worker process, killed if the query runs past it.
+fn run_jq(query: &str, json: &str) -> Result<Vec<String>> {
+    let Some(timeout) = EVAL_TIMEOUT.get().copied() else {
+        return run_jq_blocking(query, json);
+    };
+    match worker::run(&program(query), json, timeout)? {
+        worker::Reply::Results(results) => Ok(results),
+        worker::Reply::Failed => Err(anyhow!("Failed to execute jq query '{}'", query)),
+        worker::Reply::TimedOut => Err(Timeout(timeout).into()),
+    }
+}
+
+/// Prepends the jq function definitions to the query.
+fn program(query: &str) -> String {
+    let definitions = DEFINITIONS.get().map_or("", |definitions| definitions);
+    format!("{}{}", definitions, query)
+}
+
+/// Runs the jq query against the JSON like `run_jq`, but has jq stop evaluating it
+/// once the results exceed the total size in bytes, rather than building all of them,
+/// returning the results up to the size with whether any were left out.
+fn run_jq_capped(query: &str, json: &str, max: usize) -> Result<(Vec<String>, bool)> {
+    if max == usize::MAX {
+        return run_jq(query, json).map(|results| (results, false));
+    }
+    // The first result past the size is passed on, to tell that there are more.
+    let capped = format!(
+        "label $cap | foreach ({}) as $result (0; . + ($result | tojson | length); \
+         if . > {} then ($result, break $cap) else $result end)",
+        query, max
+    );
+    let results = run_jq(&capped, json).map_err(|e| match e.downcast::<Timeout>() {
+        Ok(timeout) => timeout.into(),
+        Err(_) => anyhow!("Failed to execute jq query '{}'", query),
+    })?;
+    let (results, dropped) = limit_output(results, max);
+    Ok((results, dropped > 0))
+}
+
+/// Keeps the results up to the total size in bytes,
+/// returning them with the number of results left out.
+fn limit_output(mut results: Vec<String>, max: usize) -> (Vec<String>, usize) {
+    let mut size = 0;
+    let kept = results
+        .iter()
+        .take_while(|result| {
+            size += result.len();
+            size <= max
+        })
+        .count();
+    let dropped = results.len() - kept;
+    results.truncate(kept);
+    (results, dropped)
+}
+
+/// Runs the jq query against the JSON in this process.
+fn run_jq_blocking(query: &str, json: &str) -> Result<Vec<String>> {
+    // libjq writes to the console when an internal error occurs.
+    //
+    // e.g.
+    // ```
+    // let _ = j9::run(". | select(.number == invalid_no_quote)", "{}");
+    // jq: error: invalid_no_quote/0 is not defined at <top-level>, line 1:
+    //     . | select(.number == invalid_no_quote)
+    // ```
+    //
+    // While errors themselves are not an issue,
+    // they interfere with the console output handling mechanism
+    // in promkit and qjq (e.g., causing line numbers to shift).
+    // Therefore, we'll ignore console output produced inside j9::run.
+    //
+    // It's possible that this could be handled
+    // within github.com/ynqa/j9, but for now,
+    // we'll proceed with this workaround.
+    //
+    // For reference, the functionality of a quiet mode in libjq is
+    // also being discussed at https://github.com/jqlang/jq/issues/1225.
+    let ignore_err = Gag::stderr().ok();
+    let ret = j9::run(&program(query), json);
+    drop(ignore_err);
+    ret.map_err(|_| anyhow!("Failed to execute jq query '{}'", query))
+}
+
+/// The outcome of an interactive session.
+pub enum Outcome {
+    /// The query was accepted, along with the results of evaluating it
+    /// against every document.
+    Accepted {
+        query: String,
+        results: Result<Vec<String>>,
+        /// The edited input, if any value was edited.
+        edits: Option<Edits>,
+        /// Whether strings are output without quotes.
+        raw_output: bool,
+        /// Whether each result is output on one line.
+        compact_output: bool,
+        /// Whether the characters beyond ASCII are escaped.
+        ascii_output: bool,
+        output_format: OutputFormat,
+        /// The colors of the JSON viewer, if the results are output colored
+        /// regardless of where they go.
+        colors: Option<Box<json::Theme>>,
+    },
+    /// The session was aborted.
+    Aborted,
+    /// The input failed to be loaded after the session started.
+    Failed(anyhow::Error),
+}
 
 pub struct Jnv {
-    input_json: String,
+    documents: Vec<String>,
     expand_depth: Option<usize>,
-    no_hint: bool,
+    verbosity: Verbosity,
 
     query_editor_renderer: text_editor::Renderer,
     hint_message_renderer: text::Renderer,
-    suggest: Suggest,
+    status_renderer: text::Renderer,
+    suggest: PathIndex,
+    index_time: Duration,
+    suggest_ranking: Ranking,
+    suggest_case: CaseSensitivity,
     suggest_renderer: listbox::Renderer,
+    suggest_preview_renderer: listbox::Renderer,
+    analysis_renderer: listbox::Renderer,
+    diff_renderer: listbox::Renderer,
     json_bundle_renderer: json::bundle::Renderer,
+    layout: Layout,
+    yank_file: Option<PathBuf>,
+    source: Option<PathBuf>,
+    query_store: Store,
+    last_query: Option<String>,
+    workspace: Vec<PathBuf>,
+    fingerprint: String,
+    refresh: Option<Refresh>,
+    loading: Option<Loading>,
+    hooks: Hooks,
+    listen: Option<PathBuf>,
+    theme: Theme,
+    highlight_changes: bool,
+    hint_visible: bool,
+    hint_lines: Option<usize>,
+    hide_empty: bool,
+    sample_arrays: bool,
+    debug_hud: bool,
+    max_depth: Option<usize>,
+    max_output: usize,
+    output_format: OutputFormat,
+    ascii_output: bool,
+    color: ColorChoice,
+    picker_renderer: listbox::Renderer,
     keymap: KeymapManager<self::render::Renderer>,
 }
 
@@ -39,36 +436,37 @@ impl Jnv {
     pub fn try_new(
         input_json: String,
         expand_depth: Option<usize>,
-        no_hint: bool,
         edit_mode: text_editor::Mode,
         indent: usize,
         suggestion_list_length: usize,
     ) -> Result<Self> {
-        let kinds = JsonNode::try_new(input_json.clone(), None)?.flatten_visibles();
-        let full = kinds.iter().filter_map(|kind| kind.path()).map(|segments| {
-            if segments.is_empty() {
-                ".".to_string()
-            } else {
-                segments
-                    .iter()
-                    .map(|segment| match segment {
-                        JsonPathSegment::Key(key) => {
-                            if key.contains('.') || key.contains('-') || key.contains('@') {
-                                format!(".\"{}\"", key)
-                            } else {
-                                format!(".{}", key)
-                            }
-                        }
-                        JsonPathSegment::Index(index) => format!("[{}]", index),
-                    })
-                    .collect::<String>()
-            }
-        });
+        let documents = split_documents(&input_json)?;
+        let start = Instant::now();
+        let suggest = index(&documents)?;
+        let index_time = start.elapsed();
+        report::record_input(&input_json, &documents, suggest.len());
+        let fingerprint = store::fingerprint(&input_json);
+        let ranking = Ranking::load(&fingerprint);
+        let query_store = Store::open(QUERIES_FILE, &fingerprint);
+        let last_query = query_store
+            .load()
+            .and_then(|query| query.as_str().map(|query| query.to_string()));
+
+        let first = documents.first().ok_or_else(|| anyhow!("No data found"))?;
+        let initial = j9::run(".", first)
+            .map_err(|_| anyhow!(format!("jq error with program: '.', input: {}", first)))?
+            .first()
+            .cloned()
+            .ok_or_else(|| anyhow!("No data found"))?;
 
         Ok(Self {
-            input_json: input_json.clone(),
+            status_renderer: text::Renderer {
+                text: Default::default(),
+                style: StyleBuilder::new().fgc(Color::DarkGrey).build(),
+            },
+            documents,
             expand_depth,
-            no_hint,
+            verbosity: Verbosity::Normal,
             query_editor_renderer: text_editor::Renderer {
                 texteditor: Default::default(),
                 history: Default::default(),
@@ -87,7 +485,10 @@ impl Jnv {
                     .attrs(Attributes::from(Attribute::Bold))
                     .build(),
             },
-            suggest: Suggest::from_iter(full),
+            suggest,
+            index_time,
+            suggest_ranking: ranking,
+            suggest_case: CaseSensitivity::Smart,
             suggest_renderer: listbox::Renderer {
                 listbox: listbox::Listbox::from_iter(Vec::<String>::new()),
                 cursor: String::from("❯ "),
@@ -98,22 +499,80 @@ impl Jnv {
                 inactive_item_style: StyleBuilder::new().fgc(Color::Grey).build(),
                 lines: Some(suggestion_list_length),
             },
+            suggest_preview_renderer: listbox::Renderer {
+                listbox: listbox::Listbox::from_iter(Vec::<String>::new()),
+                cursor: String::new(),
+                active_item_style: StyleBuilder::new().fgc(Color::DarkGrey).build(),
+                inactive_item_style: StyleBuilder::new().fgc(Color::DarkGrey).build(),
+                lines: Default::default(),
+            },
+            analysis_renderer: listbox::Renderer {
+                listbox: listbox::Listbox::from_iter(Vec::<String>::new()),
+                cursor: String::new(),
+                active_item_style: StyleBuilder::new().fgc(Color::Magenta).build(),
+                inactive_item_style: StyleBuilder::new().fgc(Color::Magenta).build(),
+                lines: Default::default(),
+            },
+            diff_renderer: listbox::Renderer {
+                listbox: listbox::Listbox::from_iter(Vec::<String>::new()),
+                cursor: String::new(),
+                active_item_style: StyleBuilder::new().fgc(Color::Yellow).build(),
+                inactive_item_style: StyleBuilder::new().fgc(Color::Yellow).build(),
+                lines: Default::default(),
+            },
+            layout: Layout::Top,
+            yank_file: None,
+            source: None,
+            query_store,
+            last_query,
+            workspace: Vec::new(),
+            fingerprint,
+            refresh: None,
+            loading: None,
+            hooks: Hooks::default(),
+            listen: None,
+            theme: Theme::default(),
+            highlight_changes: false,
+            hint_visible: true,
+            hint_lines: None,
+            hide_empty: false,
+            sample_arrays: false,
+            debug_hud: false,
+            max_depth: None,
+            output_format: OutputFormat::Json,
+            ascii_output: false,
+            color: ColorChoice::Auto,
+            max_output: usize::MAX,
+            picker_renderer: listbox::Renderer {
+                listbox: listbox::Listbox::from_iter(Vec::<String>::new()),
+                cursor: String::from("❯ "),
+                active_item_style: StyleBuilder::new()
+                    .fgc(Color::Grey)
+                    .bgc(Color::Yellow)
+                    .build(),
+                inactive_item_style: StyleBuilder::new().fgc(Color::Grey).build(),
+                lines: Some(suggestion_list_length),
+            },
             keymap: KeymapManager::new("default", self::keymap::default)
-                .register("on_suggest", self::keymap::on_suggest),
+                .register("on_suggest", self::keymap::on_suggest)
+                .register("on_edit", self::keymap::on_edit)
+                .register("on_wizard", self::keymap::on_wizard)
+                .register("on_navigate", self::keymap::on_navigate)
+                .register("on_visual", self::keymap::on_visual)
+                .register("on_mark", self::keymap::on_mark)
+                .register("on_pick", self::keymap::on_pick)
+                .register("on_help", self::keymap::on_help)
+                .register("on_chord", self::keymap::on_chord)
+                .register("on_wrap", self::keymap::on_wrap)
+                .register("on_macro", self::keymap::on_macro)
+                .register("on_settings", self::keymap::on_settings)
+                .register("on_theme", self::keymap::on_theme)
+                .register("on_key_filter", self::keymap::on_key_filter)
+                .register("on_jump", self::keymap::on_jump)
+                .register("on_pipe", self::keymap::on_pipe)
+                .register("on_export", self::keymap::on_export),
             json_bundle_renderer: json::bundle::Renderer {
-                bundle: json::JsonBundle::new([JsonNode::try_new(
-                    j9::run(".", &input_json)
-                        .map_err(|_| {
-                            anyhow!(format!(
-                                "jq error with program: '.', input: {}",
-                                &input_json
-                            ))
-                        })?
-                        .first()
-                        .map(|s| s.as_str())
-                        .ok_or_else(|| anyhow!("No data found"))?,
-                    expand_depth,
-                )?]),
+                bundle: json::JsonBundle::new([JsonNode::try_new(initial, expand_depth)?]),
                 theme: json::Theme {
                     curly_brackets_style: StyleBuilder::new()
                         .attrs(Attributes::from(Attribute::Bold))
@@ -135,159 +594,814 @@ impl Jnv {
         })
     }
 
-    pub fn prompt(self) -> Result<Prompt<String>> {
+    /// Sets the maximum number of lines for the query editor.
+    pub fn editor_lines(mut self, lines: Option<usize>) -> Self {
+        self.query_editor_renderer.lines = lines;
+        self
+    }
+
+    /// Sets the maximum number of lines for the JSON viewer,
+    /// which otherwise takes up the rest of the terminal.
+    pub fn result_lines(mut self, lines: Option<usize>) -> Self {
+        self.json_bundle_renderer.theme.lines = lines;
+        self
+    }
+
+    /// Sets the maximum number of lines for the hint pane.
+    pub fn hint_lines(mut self, lines: Option<usize>) -> Self {
+        self.hint_lines = lines;
+        self
+    }
+
+    /// Sets whether the hint pane is shown at start, which Alt+I toggles.
+    pub fn hint_visible(mut self, visible: bool) -> Self {
+        self.hint_visible = visible;
+        self
+    }
+
+    /// Sets how the letter case of the path being completed is matched.
+    pub fn suggestion_case(mut self, case: CaseSensitivity) -> Self {
+        self.suggest_case = case;
+        self
+    }
+
+    /// Sets the file to append yanked values to, instead of the clipboard.
+    pub fn yank_file(mut self, path: Option<PathBuf>) -> Self {
+        self.yank_file = path;
+        self
+    }
+
+    /// Reads the input again with the function on the interval.
+    pub fn refresh(
+        mut self,
+        interval: Duration,
+        reload: Box<dyn Fn() -> Result<String> + Send>,
+    ) -> Self {
+        self.refresh = Some(Refresh::new(interval, reload, &self.fingerprint));
+        self
+    }
+
+    /// Reads and parses the input with the function in a thread,
+    /// showing the placeholder it was created with until the input is ready.
+    pub fn loading(mut self, read: Box<dyn FnOnce() -> Result<String> + Send>) -> Self {
+        self.loading = Some(Loading::new(read));
+        self
+    }
+
+    /// Sets the file the input is read from, if a single one,
+    /// which the accepted query is kept for rather than the content of the input,
+    /// to be restored after the file changes.
+    pub fn source(mut self, path: Option<PathBuf>) -> Self {
+        if let Some(key) = path.as_deref().and_then(store::path_key) {
+            self.query_store = Store::open(QUERIES_FILE, &key);
+            self.last_query = self
+                .query_store
+                .load()
+                .and_then(|query| query.as_str().map(|query| query.to_string()));
+        }
+        self.source = path;
+        self
+    }
+
+    /// Sets the script called on events of the session.
+    pub fn hooks(mut self, script: Option<PathBuf>) -> Self {
+        self.hooks = Hooks::new(script);
+        self
+    }
+
+    /// Sets the colors of the JSON viewer, and the file to save them to.
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Sets whether the rows changed by an edit of the query are highlighted.
+    pub fn highlight_changes(mut self, highlight_changes: bool) -> Self {
+        self.highlight_changes = highlight_changes;
+        self
+    }
+
+    /// Sets whether the entries with null or empty values are hidden from the viewer.
+    pub fn hide_empty(mut self, hide_empty: bool) -> Self {
+        self.hide_empty = hide_empty;
+        self
+    }
+
+    /// Sets whether only a sample of the items of long arrays is shown in the viewer.
+    pub fn sample_arrays(mut self, sample_arrays: bool) -> Self {
+        self.sample_arrays = sample_arrays;
+        self
+    }
+
+    /// Sets whether the debug overlay on performance is shown.
+    pub fn debug_hud(mut self, debug_hud: bool) -> Self {
+        self.debug_hud = debug_hud;
+        self
+    }
+
+    /// Sets the format of the output on accept.
+    pub fn output_format(mut self, output_format: OutputFormat) -> Self {
+        self.output_format = output_format;
+        self
+    }
+
+    /// Sets whether the characters beyond ASCII are escaped on accept and in exports.
+    pub fn ascii_output(mut self, ascii_output: bool) -> Self {
+        self.ascii_output = ascii_output;
+        self
+    }
+
+    /// Sets when the output outside the interface is colored.
+    pub fn color(mut self, color: ColorChoice) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Sets the number of levels shown in the viewer,
+    /// with the objects and arrays below noted by their size.
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the total size in bytes of the results shown in the viewer,
+    /// beyond which the rest of the results are left out.
+    pub fn max_output(mut self, max_output: usize) -> Self {
+        self.max_output = max_output;
+        self
+    }
+
+    /// Sets the Unix socket to serve control requests on.
+    pub fn listen(mut self, path: Option<PathBuf>) -> Self {
+        self.listen = path;
+        self
+    }
+
+    /// Sets the files to pick the input from.
+    pub fn workspace(mut self, files: Vec<PathBuf>) -> Self {
+        self.workspace = files;
+        self
+    }
+
+    /// Sets how much the hint pane tells about the evaluation of the query.
+    pub fn verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// Sets where the query editor is placed relative to the JSON viewer.
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Builds the interface and the evaluator of its events,
+    /// for a terminal of the size if given, or else the actual one.
+    fn build(self, size: Option<(u16, u16)>) -> Result<(self::render::Renderer, Box<Evaluator>)> {
         let trie = RefCell::new(QueryTrie::default());
-        Ok(Prompt::try_new(
-            Box::new(self::render::Renderer {
-                keymap: self.keymap,
-                query_editor_snapshot: Snapshot::<text_editor::Renderer>::new(
-                    self.query_editor_renderer,
-                ),
-                hint_message_snapshot: Snapshot::<text::Renderer>::new(self.hint_message_renderer),
-                suggest: self.suggest,
-                suggest_snapshot: Snapshot::<listbox::Renderer>::new(self.suggest_renderer),
-                json_bundle_snapshot: Snapshot::<json::bundle::Renderer>::new(
-                    self.json_bundle_renderer,
-                ),
+        let result_lines = self.json_bundle_renderer.theme.lines;
+        let mut json_bundle_renderer = self.json_bundle_renderer;
+        self.theme.apply(&mut json_bundle_renderer.theme);
+        #[cfg(unix)]
+        let control = self
+            .listen
+            .as_deref()
+            .map(control::Control::listen)
+            .transpose()?;
+        #[cfg(not(unix))]
+        if self.listen.is_some() {
+            return Err(anyhow!("The control socket requires Unix sockets"));
+        }
+        let mut renderer = self::render::Renderer {
+            keymap: self.keymap,
+            query_editor_snapshot: Snapshot::<text_editor::Renderer>::new(
+                self.query_editor_renderer,
+            ),
+            hint_message_snapshot: Snapshot::<text::Renderer>::new(self.hint_message_renderer),
+            status_snapshot: Snapshot::<text::Renderer>::new(self.status_renderer),
+            documents: self.documents,
+            document_position: 0,
+            accepted: false,
+            editor_mode: None,
+            edits: Vec::new(),
+            discard_pending: None,
+            navigator: None,
+            hint_visible: self.hint_visible,
+            hint_lines: self.hint_lines,
+            verbosity: self.verbosity,
+            suggest_visible: true,
+            fullscreen: false,
+            hud_visible: self.debug_hud,
+            metrics: Default::default(),
+            damage: Default::default(),
+            wrap: true,
+            line_numbers: false,
+            column: 0,
+            json_row: None,
+            json_rows: None,
+            json_nodes: Vec::new(),
+            result_summary: None,
+            source_values: Default::default(),
+            remaining: None,
+            key_filter: None,
+            help_snapshot: Snapshot::<listbox::Renderer>::new(listbox::Renderer {
+                lines: Some(builtins::LINES),
+                ..self.picker_renderer.clone()
+            }),
+            visual_anchor: None,
+            marks: Default::default(),
+            mark_action: None,
+            chord: None,
+            macros: Default::default(),
+            macro_action: None,
+            pending_location: None,
+            origin_query: None,
+            stack: Vec::new(),
+            tabs: vec![self::render::Tab {
+                query: String::new(),
+                row: None,
+            }],
+            tab: 0,
+            pending_row: None,
+            source: self.source,
+            query_store: self.query_store,
+            history: RefCell::new(history::History::load()),
+            last_query: self.last_query,
+            workspace: self.workspace,
+            refresh: self.refresh,
+            loading: self.loading,
+            failed: RefCell::new(None),
+            switching: None,
+            hooks: self.hooks,
+            #[cfg(unix)]
+            control,
+            settings_snapshot: Snapshot::<listbox::Renderer>::new(listbox::Renderer {
+                lines: Some(settings::SETTINGS.len()),
+                ..self.picker_renderer.clone()
+            }),
+            settings_visible: false,
+            theme_snapshot: Snapshot::<listbox::Renderer>::new(listbox::Renderer {
+                lines: Some(theme::ELEMENTS.len()),
+                ..self.picker_renderer.clone()
             }),
-            Box::new(
-                move |event: &Event,
-                      renderer: &mut Box<dyn Renderer + 'static>|
-                      -> promkit::Result<PromptSignal> {
-                    let renderer = self::render::Renderer::cast_mut(renderer.as_mut())?;
-                    let signal = match renderer.keymap.get() {
-                        Some(f) => f(event, renderer),
-                        None => Ok(PromptSignal::Quit),
-                    }?;
-                    let completed = renderer
+            theme_visible: false,
+            theme: self.theme,
+            sort_keys: false,
+            hide_empty: self.hide_empty,
+            sample_arrays: self.sample_arrays,
+            max_depth: self.max_depth,
+            expand_depth: self.expand_depth,
+            highlight_changes: self.highlight_changes,
+            changes: None,
+            result_rows: Default::default(),
+            raw_output: false,
+            compact_output: false,
+            ascii_output: self.ascii_output,
+            color: self.color,
+            output_format: self.output_format,
+            stale: false,
+            picker_snapshot: Snapshot::<listbox::Renderer>::new(self.picker_renderer),
+            loads: 0,
+            yank_file: self.yank_file,
+            suggest: self.suggest,
+            index_time: self.index_time,
+            eval_time: Duration::ZERO,
+            suggest_ranking: self.suggest_ranking,
+            suggest_case: self.suggest_case,
+            suggest_snapshot: Snapshot::<listbox::Renderer>::new(self.suggest_renderer),
+            suggest_marked: Vec::new(),
+            completion: Default::default(),
+            suggest_token: String::new(),
+            suggest_preview_snapshot: Snapshot::<listbox::Renderer>::new(
+                self.suggest_preview_renderer,
+            ),
+            suggest_note: None,
+            suggest_kind: None,
+            analysis: None,
+            scratch_snapshot: Snapshot::<listbox::Renderer>::new(listbox::Renderer {
+                active_item_style: StyleBuilder::new().build(),
+                inactive_item_style: StyleBuilder::new().build(),
+                ..self.analysis_renderer.clone()
+            }),
+            analysis_snapshot: Snapshot::<listbox::Renderer>::new(self.analysis_renderer),
+            pinned: None,
+            diff_snapshot: Snapshot::<listbox::Renderer>::new(self.diff_renderer),
+            json_bundle_snapshot: Snapshot::<json::bundle::Renderer>::new(json_bundle_renderer),
+            layout: self.layout,
+            size,
+            headless: size.is_some(),
+            result_lines,
+        };
+        renderer.show_json(vec![serde_json::from_str(&renderer.documents[0])?]);
+        // Offered once the input is loaded instead.
+        if let Some(query) = renderer
+            .last_query
+            .as_ref()
+            .filter(|_| renderer.verbosity >= Verbosity::Normal && renderer.loading.is_none())
+        {
+            let message = format!("Alt + U restores the last filter: '{}'", query);
+            renderer.show_message(message, Color::Blue);
+        }
+        // Notified once the input is loaded instead.
+        if renderer.loading.is_none() {
+            renderer
+                .hooks
+                .notify("on_start", &[], renderer.documents[0].clone());
+        }
+        renderer.update_status();
+        renderer.fit_layout()?;
+        let step = move |event: &Event,
+                         renderer: &mut Box<dyn Renderer + 'static>|
+              -> promkit::Result<PromptSignal> {
+            let renderer = self::render::Renderer::cast_mut(renderer.as_mut())?;
+            let event = match self::keymap::normalize(event) {
+                Some(event) => event,
+                None => return Ok(PromptSignal::Continue),
+            };
+            let document_position = renderer.document_position;
+            let edits = renderer.edits.len();
+            let loads = renderer.loads;
+            // A resize, or a wake-up arriving as one, is not a key: the layout is fitted
+            // to the terminal and what other threads queued is handled below.
+            let signal = if let Event::Resize(width, height) = event {
+                wake::handled();
+                renderer.damage.resized(width, height);
+                // A key sequence waiting for a longer one may have waited long enough.
+                let chord = renderer.chord.is_some();
+                let signal = self::keymap::on_chord_timeout(renderer)?;
+                if chord != renderer.chord.is_some() {
+                    renderer.damage.mark_all();
+                }
+                signal
+            } else {
+                // Any part of the screen may change with a key.
+                renderer.damage.mark_all();
+                renderer.macros.record(&event);
+                let discard_pending = renderer.discard_pending.clone();
+                let signal = match renderer.keymap.get() {
+                    Some(f) => f(&event, renderer),
+                    None => Ok(PromptSignal::Quit),
+                }?;
+                // A warning about discarding the edits only holds for the next key.
+                if discard_pending.is_some() && renderer.discard_pending == discard_pending {
+                    renderer.discard_pending = None;
+                }
+                signal
+            };
+            #[cfg(unix)]
+            renderer.handle_requests();
+            renderer.finish_layout();
+            let indexed = renderer.loads;
+            if let Err(e) = renderer.finish_loading() {
+                *renderer.failed.borrow_mut() = Some(e);
+                return Ok(PromptSignal::Quit);
+            }
+            renderer.finish_switch();
+            if renderer.loads != indexed {
+                self::keymap::finish_indexing(renderer);
+            }
+            if renderer
+                .changes
+                .as_ref()
+                .is_some_and(|changes| !changes.is_active())
+            {
+                renderer.changes = None;
+                renderer.damage.mark(PaneIndex::Result);
+            }
+            // The frame rate is shown as it changes.
+            if renderer.hud_visible {
+                renderer.damage.mark(PaneIndex::Prompt);
+            }
+            let completed = renderer
+                .query_editor_snapshot
+                .after()
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+
+            // The editor holds the value being edited or the answer
+            // to a question, not a query.
+            if renderer.editor_mode.is_some()
+                // Suggestions being cycled through are evaluated once accepted.
+                || !renderer.suggest_snapshot.after().listbox.items().is_empty()
+            {
+                renderer.update_status();
+                renderer.fit_layout()?;
+                return Ok(signal);
+            }
+            report::record_query(&completed);
+
+            if let Err(e) = renderer.refresh(false) {
+                renderer.show_message(format!("Failed to refresh: {}", e), Color::Red);
+            }
+
+            trie.borrow_mut().trim();
+
+            let document_changed = document_position != renderer.document_position
+                || edits != renderer.edits.len()
+                || loads != renderer.loads;
+            if document_changed {
+                // Cached results belong to the previous document,
+                // so start over with the new document as the fallback view.
+                *trie.borrow_mut() = QueryTrie::default();
+                let document =
+                    serde_json::from_str(&renderer.documents[renderer.document_position])
+                        .map_err(io::Error::from)?;
+                renderer.show_json(vec![document]);
+            }
+
+            // The results must be shown again after changing how they are shown.
+            let stale = std::mem::take(&mut renderer.stale);
+            if document_changed
+                || stale
+                || completed
+                    != renderer
                         .query_editor_snapshot
-                        .after()
+                        .borrow_before()
                         .texteditor
                         .text_without_cursor()
-                        .to_string();
-
-                    if completed
-                        != renderer
-                            .query_editor_snapshot
-                            .borrow_before()
-                            .texteditor
-                            .text_without_cursor()
-                            .to_string()
-                    {
-                        renderer.hint_message_snapshot.reset_after_to_init();
-
-                        // libjq writes to the console when an internal error occurs.
-                        //
-                        // e.g.
-                        // ```
-                        // let _ = j9::run(". | select(.number == invalid_no_quote)", "{}");
-                        // jq: error: invalid_no_quote/0 is not defined at <top-level>, line 1:
-                        //     . | select(.number == invalid_no_quote)
-                        // ```
-                        //
-                        // While errors themselves are not an issue,
-                        // they interfere with the console output handling mechanism
-                        // in promkit and qjq (e.g., causing line numbers to shift).
-                        // Therefore, we'll ignore console output produced inside j9::run.
-                        //
-                        // It's possible that this could be handled
-                        // within github.com/ynqa/j9, but for now,
-                        // we'll proceed with this workaround.
-                        //
-                        // For reference, the functionality of a quiet mode in libjq is
-                        // also being discussed at https://github.com/jqlang/jq/issues/1225.
-                        let ignore_err = Gag::stderr().unwrap();
-                        let ret = j9::run(&completed, &self.input_json);
-                        drop(ignore_err);
-
-                        ret
-                        .map(|ret| {
-                            if ret.is_empty() {
-                                if !self.no_hint {
-                                    renderer.hint_message_snapshot.after_mut().replace(text::Renderer {
-                                        text: format!("JSON query ('{}') was executed, but no results were returned.", &completed),
+                        .to_string()
+            {
+                // Keys come first: a key already queued, or typed during the pause,
+                // is handled before the evaluation, which happens with the last key
+                // of the burst. Replayed events come without a terminal to poll.
+                let delay = debounce(renderer.eval_time);
+                if !renderer.headless && event::poll(delay)? {
+                    renderer.stale = true;
+                    renderer.update_status();
+                    renderer.fit_layout()?;
+                    return Ok(signal);
+                }
+
+                renderer.hint_message_snapshot.reset_after_to_init();
+                renderer.analysis = None;
+                renderer.analysis_snapshot.reset_after_to_init();
+                renderer.scratch_snapshot.reset_after_to_init();
+
+                let start = Instant::now();
+                // Viewing the results takes far more memory than the text,
+                // so only the results up to the size are shown.
+                let ret = run_jq_capped(
+                    &completed,
+                    &renderer.documents[renderer.document_position],
+                    self.max_output,
+                );
+                let elapsed = start.elapsed();
+                let (ret, capped) = match ret {
+                    Ok((ret, capped)) => (Ok(ret), capped),
+                    Err(e) => (Err(e), false),
+                };
+                renderer.update_diff(&ret);
+                renderer.eval_time = elapsed;
+                renderer.metrics.evaluated(elapsed);
+                let shown = ret.as_ref().map_or(0, Vec::len);
+
+                renderer.result_summary = None;
+                match ret {
+                    Ok(ret) => {
+                        // Tell empty results apart from a blank viewer.
+                        renderer.result_summary = Some(match ret.as_slice() {
+                            [] => format!("no results in {:.2?}", elapsed),
+                            [result] if result.trim() == "[]" => {
+                                format!("empty array in {:.2?}", elapsed)
+                            }
+                            [_] => "1 result".to_string(),
+                            _ => format!("{} results", ret.len()),
+                        });
+                        if ret.is_empty() {
+                            if renderer.verbosity >= Verbosity::Normal {
+                                renderer.hint_message_snapshot.after_mut().replace(
+                                    text::Renderer {
+                                        text: format!(
+                                            "JSON query ('{}') was executed in {:.2?}, \
+                                             but no results were returned.",
+                                            &completed, elapsed,
+                                        ),
                                         style: StyleBuilder::new()
                                             .fgc(Color::Red)
                                             .attrs(Attributes::from(Attribute::Bold))
                                             .build(),
-                                    });
-                                }
-                                if let Some(searched) = trie.borrow().prefix_search_value(&completed) {
-                                    renderer.json_bundle_snapshot.after_mut().bundle = JsonBundle::new(searched.clone());
-                                }
-                            } else {
-                                ret.iter().map(|string| {
-                                    JsonNode::try_new(string.as_str(), self.expand_depth)
-                                }).collect::<Result<Vec<JsonNode>, _>>()
-                                .map(|nodes| {
-                                    if nodes.len() == 1 && nodes.first().unwrap() == &JsonNode::Leaf(serde_json::Value::Null) {
-                                        if !self.no_hint {
-                                            renderer.hint_message_snapshot.after_mut().replace(text::Renderer {
+                                    },
+                                );
+                            }
+                            if let Some(searched) = trie.borrow().prefix_search_value(&completed) {
+                                renderer.show_json(searched.clone());
+                            }
+                        } else {
+                            let values = ret
+                                .iter()
+                                .map(|string| serde_json::from_str::<serde_json::Value>(string))
+                                .collect::<Result<Vec<_>, _>>();
+                            match values {
+                                Ok(values) if values == [serde_json::Value::Null] => {
+                                    if renderer.verbosity >= Verbosity::Normal {
+                                        renderer.hint_message_snapshot.after_mut().replace(
+                                            text::Renderer {
                                                 text: format!(
-                                                    "JSON query resulted in 'null', which may indicate a typo or incorrect query: '{}'",
+                                                    "JSON query resulted in 'null', which may \
+                                                     indicate a typo or incorrect query: '{}'",
                                                     &completed,
                                                 ),
                                                 style: StyleBuilder::new()
                                                     .fgc(Color::Yellow)
                                                     .attrs(Attributes::from(Attribute::Bold))
                                                     .build(),
-                                            });
-                                        }
-                                        if let Some(searched) = trie.borrow().prefix_search_value(&completed) {
-                                            renderer.json_bundle_snapshot.after_mut().bundle = JsonBundle::new(searched.clone());
+                                            },
+                                        );
+                                    }
+                                    if let Some(searched) =
+                                        trie.borrow().prefix_search_value(&completed)
+                                    {
+                                        renderer.show_json(searched.clone());
+                                    }
+                                }
+                                Ok(values) => {
+                                    // SUCCESS!
+                                    renderer.hooks.settle(
+                                        "on_query_result",
+                                        &[&completed],
+                                        ret.join("\n"),
+                                    );
+                                    trie.borrow_mut().insert(&completed, values.clone());
+                                    renderer.show_results(values);
+                                    let description = (renderer.verbosity >= Verbosity::Normal
+                                        && ret.len() == 1)
+                                        .then(|| describe_path(&completed, &ret[0]))
+                                        .flatten();
+                                    let stats =
+                                        (renderer.verbosity == Verbosity::Verbose).then(|| {
+                                            format!(
+                                                "{} results in {:.2?}, {} paths indexed in {:.2?}",
+                                                ret.len(),
+                                                elapsed,
+                                                renderer.suggest.len(),
+                                                renderer.index_time,
+                                            )
+                                        });
+                                    match (description, stats) {
+                                        (Some(description), Some(stats)) => renderer.show_message(
+                                            format!("{} ({})", description, stats),
+                                            Color::DarkGrey,
+                                        ),
+                                        (Some(message), None) | (None, Some(message)) => {
+                                            renderer.show_message(message, Color::DarkGrey)
                                         }
-                                    } else {
-                                        // SUCCESS!
-                                        trie.borrow_mut().insert(&completed, nodes.clone());
-                                        renderer.json_bundle_snapshot.after_mut().bundle = JsonBundle::new(nodes);
+                                        (None, None) => {}
                                     }
-                                })
-                                .unwrap_or_else(|e| {
-                                    if !self.no_hint {
-                                        renderer.hint_message_snapshot.after_mut().replace(text::Renderer{
-                                            text: format!(
-                                                "Failed to parse query result for viewing: {}",
-                                                e
-                                            ),
-                                            style: StyleBuilder::new()
-                                                .fgc(Color::Red)
-                                                .attrs(Attributes::from(Attribute::Bold))
-                                                .build(),
-                                        })
+                                }
+                                Err(e) => {
+                                    if renderer.verbosity >= Verbosity::Errors {
+                                        renderer.hint_message_snapshot.after_mut().replace(
+                                            text::Renderer {
+                                                text: format!(
+                                                    "Failed to parse query result for viewing: {}",
+                                                    e
+                                                ),
+                                                style: StyleBuilder::new()
+                                                    .fgc(Color::Red)
+                                                    .attrs(Attributes::from(Attribute::Bold))
+                                                    .build(),
+                                            },
+                                        )
                                     }
-                                    if let Some(searched) = trie.borrow().prefix_search_value(&completed) {
-                                        renderer.json_bundle_snapshot.after_mut().bundle = JsonBundle::new(searched.clone());
+                                    if let Some(searched) =
+                                        trie.borrow().prefix_search_value(&completed)
+                                    {
+                                        renderer.show_json(searched.clone());
                                     }
-                                });
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        // Keep the previous results of a runaway query on screen.
+                        if let Some(timeout) = e.downcast_ref::<Timeout>() {
+                            if renderer.verbosity >= Verbosity::Errors {
+                                let message = format!("{}: '{}'", timeout, &completed);
+                                renderer.show_message(message, Color::Yellow);
                             }
-                        })
-                        .unwrap_or_else(|_| {
-                            if !self.no_hint {
-                                renderer.hint_message_snapshot.after_mut().replace(text::Renderer {
-                                    text: format!("Failed to execute jq query '{}'", &completed),
-                                    style: StyleBuilder::new()
-                                        .fgc(Color::Red)
-                                        .attrs(Attributes::from(Attribute::Bold))
-                                        .build(),
+                        } else {
+                            if renderer.verbosity >= Verbosity::Errors {
+                                renderer.hint_message_snapshot.after_mut().replace(
+                                    text::Renderer {
+                                        text: format!(
+                                            "Failed to execute jq query '{}'",
+                                            &completed
+                                        ),
+                                        style: StyleBuilder::new()
+                                            .fgc(Color::Red)
+                                            .attrs(Attributes::from(Attribute::Bold))
+                                            .build(),
                                     },
                                 );
                             }
                             if let Some(searched) = trie.borrow().prefix_search_value(&completed) {
-                                renderer.json_bundle_snapshot.after_mut().bundle = JsonBundle::new(searched.clone());
+                                renderer.show_json(searched.clone());
                             }
-                        });
+                        }
                     }
-                    Ok(signal)
-                },
-            ),
-            |renderer: &(dyn Renderer + '_)| -> promkit::Result<String> {
-                Ok(self::render::Renderer::cast(renderer)?
-                    .query_editor_snapshot
-                    .after()
-                    .texteditor
-                    .text_without_cursor()
-                    .to_string())
+                }
+                if capped {
+                    renderer.result_summary = Some(format!("first {} results", shown));
+                    renderer.show_message(
+                        format!(
+                            "The output exceeds {:.1} MB: showing the first {} results",
+                            self.max_output as f64 / (1 << 20) as f64,
+                            shown,
+                        ),
+                        Color::Yellow,
+                    );
+                }
+            }
+            if let Some(path) = renderer.pending_location.take() {
+                renderer.move_json_cursor_to(&path);
+            }
+            if let Some(row) = renderer.pending_row.take() {
+                if renderer.json_rows.is_some_and(|rows| row < rows) {
+                    renderer.move_json_cursor(row);
+                }
+            }
+            renderer.update_status();
+            renderer.fit_layout()?;
+            Ok(signal)
+        };
+        // The events of a macro are handled one by one after the key playing it,
+        // each followed by the evaluation of the query as if typed.
+        // The events queued meanwhile (e.g. a held key, the text committed by an IME
+        // or a burst of scrolling over a slow connection) are handled too,
+        // through the active keymap and the macro being recorded, for a frame at most,
+        // so that the screen is redrawn once for all of them.
+        // Redraws are no more frequent than `FRAME_INTERVAL`, the events arriving
+        // until the next frame is due being handled too, and are skipped while
+        // nothing on the screen changed (e.g. when woken up with nothing to show).
+        let evaluator: Box<Evaluator> = Box::new(
+            move |event: &Event, renderer: &mut Box<dyn Renderer + 'static>| {
+                let started = Instant::now();
+                self::render::Renderer::cast_mut(renderer.as_mut())?
+                    .macros
+                    .typed();
+                let mut signal = step(event, renderer)?;
+                while signal == PromptSignal::Continue {
+                    let render = self::render::Renderer::cast_mut(renderer.as_mut())?;
+                    if let Some(next) = render.macros.next() {
+                        signal = step(&next, renderer)?;
+                        continue;
+                    }
+                    // Replayed events come without a terminal to poll.
+                    if render.headless {
+                        break;
+                    }
+                    if !render.damage.is_clean() {
+                        let wait = render.damage.until_next_frame();
+                        if started.elapsed() > FRAME_INTERVAL || !event::poll(wait)? {
+                            break;
+                        }
+                    }
+                    render.macros.typed();
+                    signal = step(&event::read()?, renderer)?;
+                }
+                Ok(signal)
             },
-        )?)
+        );
+        Ok((renderer, evaluator))
+    }
+
+    pub fn prompt(self) -> Result<Prompt<Outcome>> {
+        let (renderer, evaluator) = self.build(None)?;
+        Ok(Prompt::try_new(Box::new(renderer), evaluator, output)?)
+    }
+
+    /// Feeds the events to the interface without a terminal, as if typed by the user,
+    /// and returns the rows a terminal of the size would show at the end,
+    /// with their styles as ANSI escape sequences if styled.
+    pub fn replay(
+        self,
+        events: &[Event],
+        width: u16,
+        height: u16,
+        styled: bool,
+    ) -> Result<Vec<String>> {
+        let (renderer, evaluator) = self.build(Some((width, height)))?;
+        let mut renderer: Box<dyn Renderer> = Box::new(renderer);
+        let (mut width, mut height) = (width, height);
+        for event in events {
+            if let Event::Resize(w, h) = event {
+                (width, height) = (*w, *h);
+                self::render::Renderer::cast_mut(renderer.as_mut())?.size = Some((width, height));
+            }
+            if evaluator(event, &mut renderer)? == PromptSignal::Quit {
+                break;
+            }
+        }
+        let mut rows = Vec::new();
+        for pane in renderer.create_panes(width) {
+            let remaining = (height as usize).saturating_sub(rows.len());
+            if remaining == 0 {
+                break;
+            }
+            if pane.is_empty() {
+                continue;
+            }
+            rows.extend(pane.extract(remaining).iter().map(|row| {
+                if styled {
+                    truncate_styled(&row.styled_display().to_string(), width as usize)
+                } else {
+                    row.to_string().chars().take(width as usize).collect()
+                }
+            }));
+        }
+        Ok(rows)
+    }
+}
+
+/// Cuts the row off at the width, as the terminal would,
+/// keeping the escape sequences intact.
+fn truncate_styled(row: &str, width: usize) -> String {
+    let mut ret = String::new();
+    let mut visible = 0;
+    let mut chars = row.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            ret.push(ch);
+            // Copy the sequence up to its final byte (e.g. `m` for styles).
+            for ch in chars.by_ref() {
+                ret.push(ch);
+                if ch.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            // Wide characters (e.g. CJK) take two columns.
+            let columns = ch.width().unwrap_or(0);
+            if visible + columns <= width {
+                ret.push(ch);
+                visible += columns;
+            } else {
+                // Not to show the characters after one that doesn't fit.
+                visible = width;
+            }
+        }
+    }
+    ret
+}
+
+/// Returns the outcome of the session.
+fn output(renderer: &dyn Renderer) -> promkit::Result<Outcome> {
+    let renderer = self::render::Renderer::cast(renderer)?;
+    if let Some(e) = renderer.failed.borrow_mut().take() {
+        return Ok(Outcome::Failed(e));
+    }
+    if !renderer.accepted {
+        return Ok(Outcome::Aborted);
+    }
+    let query = renderer
+        .query_editor_snapshot
+        .after()
+        .texteditor
+        .text_without_cursor()
+        .to_string();
+    // Every document of the stream is filtered, as jq does, not only the one shown.
+    let results = renderer
+        .documents
+        .iter()
+        .map(|document| run_jq(&query, document))
+        .collect::<Result<Vec<_>>>()
+        .map(|results| results.concat());
+    // The results being refined come from the pushed queries.
+    let query = renderer.pipeline(&query);
+    // Autosaving is best effort, and must not fail accepting the query.
+    let _ = renderer
+        .query_store
+        .save(serde_json::Value::String(query.clone()));
+    let _ = renderer.history.borrow_mut().push(&query);
+    let edits = (!renderer.edits.is_empty()).then(|| Edits {
+        documents: renderer.documents.clone(),
+        patches: edit::patches(&renderer.edits, renderer.documents.len()),
+    });
+    Ok(Outcome::Accepted {
+        query,
+        results,
+        edits,
+        raw_output: renderer.raw_output,
+        compact_output: renderer.compact_output,
+        ascii_output: renderer.ascii_output,
+        output_format: renderer.output_format,
+        colors: (renderer.color == ColorChoice::Always)
+            .then(|| Box::new(renderer.json_bundle_snapshot.after().theme.clone())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_styled_counts_columns() {
+        assert_eq!(truncate_styled("abcdef", 4), "abcd");
+        assert_eq!(
+            truncate_styled("\x1b[1m日本語\x1b[0m", 5),
+            "\x1b[1m日本\x1b[0m"
+        );
+        assert_eq!(truncate_styled("a日b", 2), "a");
     }
 }
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use promkit::serde_json::{self, Value};
+
+/// The number of distinct values shown in a histogram.
+const HISTOGRAM_TOP: usize = 10;
+/// The width of the longest bar in a histogram.
+const HISTOGRAM_BAR_WIDTH: usize = 30;
+
+/// The kinds of analysis shown in the analysis pane.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Analysis {
+    Histogram,
+    Statistics,
+}
+
+impl Analysis {
+    /// Analyzes the values produced by the query, rendered as lines.
+    pub fn run(&self, query: &str, values: &[Value]) -> Result<Vec<String>> {
+        match self {
+            Analysis::Histogram => Ok(histogram(query, values)),
+            Analysis::Statistics => statistics(query, values),
+        }
+    }
+}
+
+/// Collects the values to analyze from the results of a query.
+///
+/// A query producing a single array (e.g. `.items | map(.status)`)
+/// is analyzed over its elements, and any other query over its results
+/// (e.g. `.items[].status`).
+pub fn values(results: &[String]) -> Result<Vec<Value>> {
+    let mut values = results
+        .iter()
+        .map(|result| serde_json::from_str::<Value>(result))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let [Value::Array(elements)] = values.as_mut_slice() {
+        return Ok(std::mem::take(elements));
+    }
+    Ok(values)
+}
+
+/// Computes the frequency of each value,
+/// rendered as the most common values with counts and bars.
+fn histogram(query: &str, values: &[Value]) -> Vec<String> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for value in values {
+        let value = value.to_string();
+        match positions.get(&value) {
+            Some(&position) => counts[position].1 += 1,
+            None => {
+                positions.insert(value.clone(), counts.len());
+                counts.push((value, 1));
+            }
+        }
+    }
+    // Stable, so values with the same count keep the order of appearance.
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+    let mut ret = vec![format!(
+        "Histogram of '{}': {} distinct values in {}",
+        query,
+        counts.len(),
+        values.len()
+    )];
+    let max = counts.first().map(|(_, count)| *count).unwrap_or(0);
+    let label_width = counts
+        .iter()
+        .take(HISTOGRAM_TOP)
+        .map(|(value, _)| value.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (value, count) in counts.iter().take(HISTOGRAM_TOP) {
+        let bar = "█".repeat((count * HISTOGRAM_BAR_WIDTH).div_ceil(max));
+        ret.push(format!(
+            "{:<width$} {} {}",
+            value,
+            bar,
+            count,
+            width = label_width
+        ));
+    }
+    if counts.len() > HISTOGRAM_TOP {
+        ret.push(format!("… {} more", counts.len() - HISTOGRAM_TOP));
+    }
+    ret
+}
+
+/// Computes the summary statistics of the numbers among the values.
+fn statistics(query: &str, values: &[Value]) -> Result<Vec<String>> {
+    let mut numbers = values.iter().filter_map(Value::as_f64).collect::<Vec<_>>();
+    if numbers.is_empty() {
+        bail!("The query '{}' produced no numbers", query);
+    }
+    numbers.sort_by(f64::total_cmp);
+
+    // Nearest-rank method.
+    let percentile = |p: f64| numbers[((p * numbers.len() as f64).ceil() as usize).max(1) - 1];
+    let mean = numbers.iter().sum::<f64>() / numbers.len() as f64;
+
+    let mut ret = vec![format!("Statistics of '{}'", query)];
+    if numbers.len() < values.len() {
+        ret[0].push_str(&format!(
+            " ({} non-numeric values ignored)",
+            values.len() - numbers.len()
+        ));
+    }
+    ret.extend([
+        format!("count  {}", numbers.len()),
+        format!("min    {}", numbers[0]),
+        format!("max    {}", numbers[numbers.len() - 1]),
+        format!("mean   {}", mean),
+        format!("median {}", percentile(0.5)),
+        format!("p95    {}", percentile(0.95)),
+    ]);
+    Ok(ret)
+}
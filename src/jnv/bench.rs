@@ -0,0 +1,57 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+
+/// Measures the stages of loading the input and evaluating the filter,
+/// returning the report to print.
+///
+/// The filter is evaluated over the first document, as in the interface,
+/// the given number of times.
+pub fn bench(input: &str, filter: &str, runs: usize) -> Result<Vec<String>> {
+    let start = Instant::now();
+    let documents = super::split_documents(input)?;
+    let parse = start.elapsed();
+    let first = documents
+        .first()
+        .ok_or_else(|| anyhow!("No data found in the input"))?;
+
+    let start = Instant::now();
+    let paths = super::index(&documents)?;
+    let index = start.elapsed();
+
+    let mut evaluations = Vec::with_capacity(runs);
+    for _ in 0..runs.max(1) {
+        let start = Instant::now();
+        super::run_jq(filter, first)?;
+        evaluations.push(start.elapsed());
+    }
+    evaluations.sort();
+
+    Ok(vec![
+        format!(
+            "parse     {:>10.2?}  ({} documents, {} bytes)",
+            parse,
+            documents.len(),
+            input.len()
+        ),
+        format!("index     {:>10.2?}  ({} paths)", index, paths.len()),
+        format!(
+            "evaluate  {:>10.2?}  p50, {:.2?} p90, {:.2?} p99, {:.2?} max ({} runs of '{}')",
+            percentile(&evaluations, 50),
+            percentile(&evaluations, 90),
+            percentile(&evaluations, 99),
+            evaluations.last().copied().unwrap_or_default(),
+            evaluations.len(),
+            filter
+        ),
+    ])
+}
+
+/// Returns the percentile of the sorted durations, by the nearest rank.
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
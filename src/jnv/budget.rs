@@ -0,0 +1,93 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use promkit::{json::JsonNode, serde_json::Value};
+
+use super::{
+    settings::{self, View},
+    wake,
+};
+
+/// The time the results may take to be laid out in the JSON viewer before
+/// they are shown, so that typing stays responsive when a filter explodes
+/// into millions of rows.
+pub const FRAME_BUDGET: Duration = Duration::from_millis(30);
+
+/// The results left over after the frame budget, laid out by a thread
+/// which wakes the interface up when they are ready.
+///
+/// Dropping it (e.g. for the results of the next query) stops the thread
+/// before the next value.
+pub struct Remaining {
+    nodes: Receiver<Vec<JsonNode>>,
+    stopped: Arc<AtomicBool>,
+    /// Whether the changed rows are highlighted once every result is laid out.
+    pub compare: bool,
+}
+
+impl Remaining {
+    /// Lays out the values from the position on.
+    pub fn start(values: Arc<Vec<Value>>, from: usize, view: View) -> Self {
+        let (tx, nodes) = mpsc::channel();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread_stopped = Arc::clone(&stopped);
+        thread::spawn(move || {
+            let values = &values[from..];
+            let (nodes, done) =
+                settings::nodes_until(values, &view, || thread_stopped.load(Ordering::SeqCst));
+            if done == values.len() && tx.send(nodes).is_ok() {
+                wake::wake();
+            }
+        });
+        Self {
+            nodes,
+            stopped,
+            compare: false,
+        }
+    }
+
+    /// Returns the nodes of the remaining values once laid out.
+    pub fn take(&self) -> Option<Vec<JsonNode>> {
+        self.nodes.try_recv().ok()
+    }
+}
+
+impl Drop for Remaining {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use promkit::serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn remaining_values_are_laid_out() {
+        let values = Arc::new(vec![json!(1), json!(2), json!(3)]);
+        let remaining = Remaining::start(values, 1, View::default());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let nodes = loop {
+            if let Some(nodes) = remaining.take() {
+                break nodes;
+            }
+            assert!(Instant::now() < deadline, "the values are not laid out");
+            thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(
+            nodes,
+            vec![JsonNode::Leaf(json!(2)), JsonNode::Leaf(json!(3))]
+        );
+    }
+}
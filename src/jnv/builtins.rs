@@ -0,0 +1,570 @@
+/// A jq builtin function, documented in short for the help pane.
+pub struct Builtin {
+    pub name: &'static str,
+    /// The arguments, as written in the jq manual (e.g. `(f)` or `(path; value)`).
+    pub args: &'static str,
+    pub description: &'static str,
+    pub example: &'static str,
+}
+
+impl Builtin {
+    const fn new(
+        name: &'static str,
+        args: &'static str,
+        description: &'static str,
+        example: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            args,
+            description,
+            example,
+        }
+    }
+
+    /// Formats the builtin as an item of the help pane.
+    pub fn line(&self) -> String {
+        format!(
+            "{}{}  {}  e.g. {}",
+            self.name, self.args, self.description, self.example
+        )
+    }
+}
+
+pub const BUILTINS: &[Builtin] = &[
+    Builtin::new(
+        "length",
+        "",
+        "Length of a string, array or object, or the absolute value of a number",
+        ".items | length",
+    ),
+    Builtin::new(
+        "utf8bytelength",
+        "",
+        "Number of bytes of a string in UTF-8",
+        ".name | utf8bytelength",
+    ),
+    Builtin::new("not", "", "Negates a boolean", ".active | not"),
+    Builtin::new(
+        "keys",
+        "",
+        "Sorted keys of an object, or indices of an array",
+        ".user | keys",
+    ),
+    Builtin::new(
+        "keys_unsorted",
+        "",
+        "Keys of an object in their order",
+        ".user | keys_unsorted",
+    ),
+    Builtin::new(
+        "has",
+        "(key)",
+        "Whether the object has the key, or the array the index",
+        "has(\"email\")",
+    ),
+    Builtin::new(
+        "in",
+        "(object)",
+        "Whether the input key is in the object",
+        "\"a\" | in({\"a\": 1})",
+    ),
+    Builtin::new(
+        "map",
+        "(f)",
+        "Applies f to each item of an array",
+        "map(.name)",
+    ),
+    Builtin::new(
+        "map_values",
+        "(f)",
+        "Applies f to each value of an object or array",
+        "map_values(. * 2)",
+    ),
+    Builtin::new(
+        "path",
+        "(f)",
+        "Path to the values produced by f, as an array",
+        "path(.a[0].b)",
+    ),
+    Builtin::new("paths", "", "Paths to every value of the input", "[paths]"),
+    Builtin::new(
+        "leaf_paths",
+        "",
+        "Paths to every scalar of the input",
+        "[leaf_paths]",
+    ),
+    Builtin::new(
+        "getpath",
+        "(path)",
+        "Value at the path",
+        "getpath([\"a\", \"b\"])",
+    ),
+    Builtin::new(
+        "setpath",
+        "(path; value)",
+        "Sets the value at the path",
+        "setpath([\"a\"]; 1)",
+    ),
+    Builtin::new(
+        "delpaths",
+        "(paths)",
+        "Deletes the values at the paths",
+        "delpaths([[\"a\"]])",
+    ),
+    Builtin::new(
+        "del",
+        "(path)",
+        "Deletes the value at the path",
+        "del(.password)",
+    ),
+    Builtin::new(
+        "to_entries",
+        "",
+        "Object as an array of {key, value}",
+        "to_entries",
+    ),
+    Builtin::new(
+        "from_entries",
+        "",
+        "Object from an array of {key, value}",
+        "from_entries",
+    ),
+    Builtin::new(
+        "with_entries",
+        "(f)",
+        "Applies f to the {key, value} entries of an object",
+        "with_entries(.value |= tostring)",
+    ),
+    Builtin::new(
+        "select",
+        "(f)",
+        "Keeps the input if f is true",
+        ".[] | select(.age > 30)",
+    ),
+    Builtin::new(
+        "empty",
+        "",
+        "Produces no result",
+        "if .ok then . else empty end",
+    ),
+    Builtin::new(
+        "error",
+        "(message)",
+        "Raises an error",
+        "error(\"missing id\")",
+    ),
+    Builtin::new(
+        "add",
+        "",
+        "Sums, concatenates or merges the items of an array",
+        "[.[].price] | add",
+    ),
+    Builtin::new(
+        "any",
+        "(f)",
+        "Whether f is true for any item",
+        "any(.[]; . > 10)",
+    ),
+    Builtin::new(
+        "all",
+        "(f)",
+        "Whether f is true for every item",
+        "all(.[]; . > 0)",
+    ),
+    Builtin::new(
+        "flatten",
+        "(depth)",
+        "Flattens nested arrays, up to the depth if given",
+        "flatten(1)",
+    ),
+    Builtin::new(
+        "range",
+        "(from; upto)",
+        "Numbers from from up to but excluding upto",
+        "[range(0; 5)]",
+    ),
+    Builtin::new("floor", "", "Rounds a number down", ".price | floor"),
+    Builtin::new("sqrt", "", "Square root of a number", "16 | sqrt"),
+    Builtin::new(
+        "tonumber",
+        "",
+        "Parses a string as a number",
+        ".count | tonumber",
+    ),
+    Builtin::new(
+        "tostring",
+        "",
+        "Converts to a string, as JSON unless already a string",
+        ".id | tostring",
+    ),
+    Builtin::new("type", "", "Type of the value, as a string", "map(type)"),
+    Builtin::new("infinite", "", "Positive infinity", "infinite"),
+    Builtin::new("nan", "", "Not a number", "nan | isnan"),
+    Builtin::new("sort", "", "Sorts an array", ".tags | sort"),
+    Builtin::new("sort_by", "(f)", "Sorts an array by f", "sort_by(.date)"),
+    Builtin::new(
+        "group_by",
+        "(f)",
+        "Groups the items of an array by f",
+        "group_by(.status)",
+    ),
+    Builtin::new("min", "", "Smallest item of an array", "[.[].age] | min"),
+    Builtin::new("max", "", "Largest item of an array", "[.[].age] | max"),
+    Builtin::new(
+        "min_by",
+        "(f)",
+        "Item of an array with the smallest f",
+        "min_by(.price)",
+    ),
+    Builtin::new(
+        "max_by",
+        "(f)",
+        "Item of an array with the largest f",
+        "max_by(.price)",
+    ),
+    Builtin::new(
+        "unique",
+        "",
+        "Sorted array without duplicates",
+        ".tags | unique",
+    ),
+    Builtin::new(
+        "unique_by",
+        "(f)",
+        "Items of an array with distinct f",
+        "unique_by(.id)",
+    ),
+    Builtin::new(
+        "reverse",
+        "",
+        "Reverses an array or a string",
+        ".items | reverse",
+    ),
+    Builtin::new(
+        "contains",
+        "(element)",
+        "Whether the input contains the element, recursively",
+        "contains({\"tags\": [\"a\"]})",
+    ),
+    Builtin::new(
+        "inside",
+        "(container)",
+        "Whether the input is contained in the container",
+        "[\"a\"] | inside([\"a\", \"b\"])",
+    ),
+    Builtin::new(
+        "index",
+        "(s)",
+        "Index of the first occurrence of s",
+        "index(\",\")",
+    ),
+    Builtin::new(
+        "indices",
+        "(s)",
+        "Indices of every occurrence of s",
+        "indices(\", \")",
+    ),
+    Builtin::new(
+        "startswith",
+        "(s)",
+        "Whether the string starts with s",
+        "select(.name | startswith(\"a\"))",
+    ),
+    Builtin::new(
+        "endswith",
+        "(s)",
+        "Whether the string ends with s",
+        "select(.file | endswith(\".json\"))",
+    ),
+    Builtin::new(
+        "ltrimstr",
+        "(s)",
+        "Removes the prefix s from the string",
+        "ltrimstr(\"v\")",
+    ),
+    Builtin::new(
+        "rtrimstr",
+        "(s)",
+        "Removes the suffix s from the string",
+        "rtrimstr(\".json\")",
+    ),
+    Builtin::new(
+        "explode",
+        "",
+        "Code points of a string",
+        "\"abc\" | explode",
+    ),
+    Builtin::new(
+        "implode",
+        "",
+        "String from code points",
+        "[65, 66] | implode",
+    ),
+    Builtin::new(
+        "split",
+        "(separator)",
+        "Splits a string by the separator",
+        "split(\",\")",
+    ),
+    Builtin::new(
+        "join",
+        "(separator)",
+        "Joins an array of strings with the separator",
+        ".tags | join(\", \")",
+    ),
+    Builtin::new(
+        "ascii_downcase",
+        "",
+        "Lowercases the ASCII letters of a string",
+        ".name | ascii_downcase",
+    ),
+    Builtin::new(
+        "ascii_upcase",
+        "",
+        "Uppercases the ASCII letters of a string",
+        ".name | ascii_upcase",
+    ),
+    Builtin::new(
+        "recurse",
+        "(f)",
+        "Every value reached by applying f repeatedly, .. without f",
+        "recurse(.children[])",
+    ),
+    Builtin::new("env", "", "Object of the environment variables", "env.HOME"),
+    Builtin::new(
+        "transpose",
+        "",
+        "Transposes an array of arrays",
+        "[[1, 2], [3, 4]] | transpose",
+    ),
+    Builtin::new(
+        "first",
+        "(f)",
+        "First result of f, or first item without f",
+        "first(.[] | select(.ok))",
+    ),
+    Builtin::new(
+        "last",
+        "(f)",
+        "Last result of f, or last item without f",
+        ".items | last",
+    ),
+    Builtin::new("limit", "(n; f)", "First n results of f", "[limit(3; .[])]"),
+    Builtin::new(
+        "until",
+        "(cond; next)",
+        "Applies next until cond is true",
+        "until(. > 100; . * 2)",
+    ),
+    Builtin::new(
+        "while",
+        "(cond; update)",
+        "Results of update while cond is true",
+        "[while(. < 100; . * 2)]",
+    ),
+    Builtin::new(
+        "repeat",
+        "(f)",
+        "Applies f repeatedly, forever",
+        "[limit(3; repeat(. * 2))]",
+    ),
+    Builtin::new(
+        "reduce",
+        "",
+        "Folds the results of a filter into one value",
+        "reduce .[] as $x (0; . + $x)",
+    ),
+    Builtin::new(
+        "foreach",
+        "",
+        "Folds the results of a filter, producing each step",
+        "foreach .[] as $x (0; . + $x)",
+    ),
+    Builtin::new("input", "", "Next input document", "[., input]"),
+    Builtin::new("inputs", "", "Remaining input documents", "[., inputs]"),
+    Builtin::new(
+        "debug",
+        "",
+        "Prints the input to standard error, unchanged",
+        ".user | debug | .name",
+    ),
+    Builtin::new(
+        "test",
+        "(regex; flags)",
+        "Whether the string matches the regex",
+        "select(.email | test(\"@example\\\\.com$\"))",
+    ),
+    Builtin::new(
+        "match",
+        "(regex; flags)",
+        "Match objects of the regex in the string",
+        "match(\"[0-9]+\")",
+    ),
+    Builtin::new(
+        "capture",
+        "(regex; flags)",
+        "Object of the named captures of the regex",
+        "capture(\"(?<year>[0-9]{4})\")",
+    ),
+    Builtin::new(
+        "scan",
+        "(regex)",
+        "Substrings matching the regex",
+        "[scan(\"[a-z]+\")]",
+    ),
+    Builtin::new(
+        "splits",
+        "(regex)",
+        "Splits a string by the regex",
+        "[splits(\", *\")]",
+    ),
+    Builtin::new(
+        "sub",
+        "(regex; replacement)",
+        "Replaces the first match of the regex",
+        "sub(\"^v\"; \"\")",
+    ),
+    Builtin::new(
+        "gsub",
+        "(regex; replacement)",
+        "Replaces every match of the regex",
+        "gsub(\"-\"; \"_\")",
+    ),
+    Builtin::new("tojson", "", "Value as a JSON string", ".payload | tojson"),
+    Builtin::new(
+        "fromjson",
+        "",
+        "Parses a JSON string",
+        ".payload | fromjson",
+    ),
+    Builtin::new(
+        "todate",
+        "",
+        "Unix time as an ISO 8601 date",
+        ".created | todate",
+    ),
+    Builtin::new(
+        "fromdate",
+        "",
+        "ISO 8601 date as Unix time",
+        ".created | fromdate",
+    ),
+    Builtin::new("now", "", "Current Unix time", "now | todate"),
+    Builtin::new(
+        "strftime",
+        "(format)",
+        "Formats broken down or Unix time",
+        "strftime(\"%Y-%m-%d\")",
+    ),
+    Builtin::new(
+        "strptime",
+        "(format)",
+        "Parses a date into broken down time",
+        "strptime(\"%Y-%m-%d\") | mktime",
+    ),
+    Builtin::new(
+        "ascii",
+        "",
+        "Character of an ASCII code point",
+        "65 | ascii",
+    ),
+    Builtin::new(
+        "tostream",
+        "",
+        "Stream of [path, leaf] events of the input",
+        "[tostream]",
+    ),
+    Builtin::new(
+        "fromstream",
+        "(f)",
+        "Value from the [path, leaf] events of f",
+        "fromstream(tostream)",
+    ),
+    Builtin::new(
+        "objects",
+        "",
+        "Keeps the input if it is an object",
+        ".[] | objects",
+    ),
+    Builtin::new(
+        "arrays",
+        "",
+        "Keeps the input if it is an array",
+        ".[] | arrays",
+    ),
+    Builtin::new(
+        "strings",
+        "",
+        "Keeps the input if it is a string",
+        ".. | strings",
+    ),
+    Builtin::new(
+        "numbers",
+        "",
+        "Keeps the input if it is a number",
+        ".. | numbers",
+    ),
+    Builtin::new(
+        "booleans",
+        "",
+        "Keeps the input if it is a boolean",
+        ".. | booleans",
+    ),
+    Builtin::new("nulls", "", "Keeps the input if it is null", ".[] | nulls"),
+    Builtin::new(
+        "scalars",
+        "",
+        "Keeps the input if it is not an array or object",
+        ".. | scalars",
+    ),
+    Builtin::new(
+        "values",
+        "",
+        "Keeps the input if it is not null",
+        ".[] | values",
+    ),
+];
+
+/// The number of builtins listed in the help pane at once.
+pub const LINES: usize = 6;
+
+/// Returns the builtins matching the pattern, ignoring case:
+/// those whose names start with it first, then those whose names contain it,
+/// then those whose descriptions contain it.
+pub fn search(pattern: &str) -> Vec<&'static Builtin> {
+    let pattern = pattern.to_lowercase();
+    let mut ret = BUILTINS
+        .iter()
+        .filter(|builtin| builtin.name.starts_with(&pattern))
+        .collect::<Vec<_>>();
+    for builtin in BUILTINS {
+        if !builtin.name.starts_with(&pattern) && builtin.name.contains(&pattern) {
+            ret.push(builtin);
+        }
+    }
+    for builtin in BUILTINS {
+        if !builtin.name.contains(&pattern) && builtin.description.to_lowercase().contains(&pattern)
+        {
+            ret.push(builtin);
+        }
+    }
+    ret
+}
+
+/// Returns the range of characters of the function name under the cursor,
+/// empty at the cursor if there is none.
+pub fn name_at(query: &str, cursor: usize) -> (usize, usize) {
+    let chars = query.chars().collect::<Vec<_>>();
+    let cursor = cursor.min(chars.len());
+    let is_name = |ch: &char| ch.is_ascii_alphanumeric() || *ch == '_';
+    let start = chars[..cursor]
+        .iter()
+        .rposition(|ch| !is_name(ch))
+        .map_or(0, |position| position + 1);
+    let end = chars[cursor..]
+        .iter()
+        .position(|ch| !is_name(ch))
+        .map_or(chars.len(), |position| cursor + position);
+    (start, end)
+}
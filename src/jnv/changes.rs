@@ -0,0 +1,64 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use promkit::{crossterm::style::Color, pane::Pane, style::StyleBuilder};
+
+/// How long the changed rows stay highlighted.
+const HIGHLIGHT_DURATION: Duration = Duration::from_secs(2);
+
+/// The rows of the JSON viewer that were not among the results of the previous query,
+/// highlighted for a while to show the effect of the edit.
+pub struct Changes {
+    rows: HashSet<String>,
+    since: Instant,
+}
+
+impl Changes {
+    /// Compares the rows of the results, returning `None` if nothing changed,
+    /// or if most rows changed, which makes the highlighting noise.
+    pub fn between(previous: &HashSet<String>, rows: Vec<String>) -> Option<Self> {
+        let total = rows.len();
+        let rows = rows
+            .into_iter()
+            .filter(|row| !previous.contains(row))
+            .collect::<HashSet<_>>();
+        if rows.is_empty() || rows.len() * 2 > total {
+            return None;
+        }
+        Some(Self {
+            rows,
+            since: Instant::now(),
+        })
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.since.elapsed() < HIGHLIGHT_DURATION
+    }
+
+    /// Highlights the changed rows of the panes of the JSON viewer.
+    pub fn highlight(&self, panes: Vec<Pane>) -> Vec<Pane> {
+        let style = StyleBuilder::new()
+            .fgc(Color::Black)
+            .bgc(Color::Yellow)
+            .build();
+        panes
+            .into_iter()
+            .map(|pane| {
+                let rows = pane
+                    .extract(usize::MAX)
+                    .into_iter()
+                    .map(|row| {
+                        if self.rows.contains(&row.to_string()) {
+                            row.apply_style(style)
+                        } else {
+                            row
+                        }
+                    })
+                    .collect();
+                Pane::new(rows, 0, None)
+            })
+            .collect()
+    }
+}
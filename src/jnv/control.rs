@@ -0,0 +1,150 @@
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use anyhow::{bail, Result};
+use promkit::serde_json::{self, json, Value};
+
+use super::wake;
+
+/// A request from a client, with the channel to reply on.
+pub struct Request {
+    pub method: String,
+    pub params: Value,
+    pub reply: Sender<Result<Value, String>>,
+}
+
+/// Serves JSON-RPC 2.0 requests on a Unix socket, one per line,
+/// to drive the session from other programs (e.g. editor plugins or tests).
+///
+/// The interface waits for terminal events, so the listener wakes it up
+/// for each request to handle the pending requests.
+pub struct Control {
+    path: PathBuf,
+    requests: Receiver<Request>,
+}
+
+impl Control {
+    pub fn listen(path: &Path) -> Result<Self> {
+        // Take over the socket left by a previous session,
+        // but neither a socket still served nor any other file.
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => {
+                if UnixStream::connect(path).is_ok() {
+                    bail!("Another session serves on {}", path.display());
+                }
+                fs::remove_file(path)?;
+            }
+            Ok(_) => bail!("{} exists and is not a socket", path.display()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        let listener = UnixListener::bind(path)?;
+        let (sender, requests) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let sender = sender.clone();
+                thread::spawn(move || serve(stream, sender));
+            }
+        });
+        Ok(Self {
+            path: path.to_path_buf(),
+            requests,
+        })
+    }
+
+    /// Returns the requests received since the last call.
+    pub fn pending(&self) -> Vec<Request> {
+        self.requests.try_iter().collect()
+    }
+}
+
+impl Drop for Control {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn serve(stream: UnixStream, sender: Sender<Request>) -> Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(request) => {
+                let id = request.get("id").cloned().unwrap_or(Value::Null);
+                match handle(request, &sender) {
+                    Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+                    Err(message) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {"code": -32000, "message": message},
+                    }),
+                }
+            }
+            Err(e) => json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": {"code": -32700, "message": e.to_string()},
+            }),
+        };
+        writeln!(writer, "{}", response)?;
+    }
+    Ok(())
+}
+
+fn handle(request: Value, sender: &Sender<Request>) -> Result<Value, String> {
+    let method = request
+        .get("method")
+        .and_then(Value::as_str)
+        .ok_or("The method is missing")?
+        .to_string();
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+    let (reply, replied) = mpsc::channel();
+    sender
+        .send(Request {
+            method,
+            params,
+            reply,
+        })
+        .map_err(|_| "The session has ended")?;
+    wake::wake();
+    replied.recv().map_err(|_| "The session has ended")?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listen_takes_over_only_sockets_left_behind() {
+        let dir = std::env::temp_dir().join(format!("jnv-control-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let file = dir.join("file");
+        fs::write(&file, "").unwrap();
+        assert!(Control::listen(&file).is_err());
+        assert!(file.exists());
+
+        let socket = dir.join("socket");
+        let served = Control::listen(&socket).unwrap();
+        assert!(Control::listen(&socket).is_err());
+        drop(served);
+
+        // Left behind by a session that crashed.
+        drop(UnixListener::bind(&socket).unwrap());
+        assert!(Control::listen(&socket).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
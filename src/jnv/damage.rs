@@ -0,0 +1,153 @@
+use std::{
+    cell::{Cell, RefCell},
+    time::{Duration, Instant},
+};
+
+use promkit::{grapheme::StyledGraphemes, pane::Pane};
+
+/// The shortest time between two frames, capping the redraws at 30 per second.
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(33);
+
+/// The parts of the screen, laid out apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaneIndex {
+    /// The query editor and the panes about it (e.g. the status, the hint or the suggestions).
+    Prompt,
+    /// The JSON viewer and the panes over it (e.g. the analysis or the diff).
+    Result,
+}
+
+const PANES: usize = 2;
+
+/// The rows of each pane of a part, and the width they were laid out for.
+type LaidOut = (u16, Vec<Vec<StyledGraphemes>>);
+
+/// Which parts of the screen changed since they were last drawn.
+///
+/// The parts left unchanged by the events of a frame are drawn as laid out
+/// for the previous one, and the frame is skipped if none changed
+/// (e.g. when woken up with nothing to show), so that a large JSON viewer
+/// isn't laid out and sent to the terminal again for nothing.
+pub struct Damage {
+    dirty: Cell<[bool; PANES]>,
+    /// Each part as last laid out.
+    laid_out: RefCell<[Option<LaidOut>; PANES]>,
+    /// The size of the terminal, as of the last resize.
+    size: Cell<Option<(u16, u16)>>,
+    /// When the last frame was drawn.
+    drawn: Cell<Option<Instant>>,
+}
+
+impl Default for Damage {
+    fn default() -> Self {
+        Self {
+            dirty: Cell::new([true; PANES]),
+            laid_out: RefCell::new([None, None]),
+            size: Cell::new(None),
+            drawn: Cell::new(None),
+        }
+    }
+}
+
+impl Damage {
+    /// Marks the part as changed.
+    pub fn mark(&self, index: PaneIndex) {
+        let mut dirty = self.dirty.get();
+        dirty[index as usize] = true;
+        self.dirty.set(dirty);
+    }
+
+    /// Marks every part as changed (e.g. after a key, which may change any).
+    pub fn mark_all(&self) {
+        self.dirty.set([true; PANES]);
+    }
+
+    /// Whether no part changed since the last frame.
+    pub fn is_clean(&self) -> bool {
+        !self.dirty.get().contains(&true)
+    }
+
+    /// Marks every part as changed if the terminal was resized,
+    /// rather than woken up with a resize event of the same size.
+    pub fn resized(&self, width: u16, height: u16) {
+        if self.size.replace(Some((width, height))) != Some((width, height)) {
+            self.mark_all();
+        }
+    }
+
+    /// Returns the panes of the part, laid out anew if it changed
+    /// or the width did, or else as laid out for the previous frame.
+    pub fn panes(
+        &self,
+        index: PaneIndex,
+        width: u16,
+        lay_out: impl FnOnce() -> Vec<Pane>,
+    ) -> Vec<Pane> {
+        let mut laid_out = self.laid_out.borrow_mut();
+        let slot = &mut laid_out[index as usize];
+        let mut dirty = self.dirty.get();
+        if dirty[index as usize] || slot.as_ref().is_none_or(|(w, _)| *w != width) {
+            let rows = lay_out()
+                .iter()
+                .map(|pane| pane.extract(usize::MAX))
+                .collect();
+            *slot = Some((width, rows));
+            dirty[index as usize] = false;
+            self.dirty.set(dirty);
+        }
+        slot.as_ref()
+            .map(|(_, panes)| {
+                panes
+                    .iter()
+                    .map(|rows| Pane::new(rows.clone(), 0, None))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Counts a frame drawn now.
+    pub fn drawn(&self) {
+        self.drawn.set(Some(Instant::now()));
+    }
+
+    /// Returns how long until the next frame may be drawn.
+    pub fn until_next_frame(&self) -> Duration {
+        self.drawn.get().map_or(Duration::ZERO, |drawn| {
+            FRAME_INTERVAL.saturating_sub(drawn.elapsed())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchanged_parts_are_not_laid_out_again() {
+        let damage = Damage::default();
+        let layouts = Cell::new(0);
+        let lay_out = || {
+            layouts.set(layouts.get() + 1);
+            vec![Pane::new(Vec::new(), 0, None)]
+        };
+        assert_eq!(damage.panes(PaneIndex::Result, 80, lay_out).len(), 1);
+        assert_eq!(damage.panes(PaneIndex::Result, 80, lay_out).len(), 1);
+        assert_eq!(layouts.get(), 1);
+        assert!(!damage.is_clean());
+        damage.panes(PaneIndex::Prompt, 80, Vec::new);
+        assert!(damage.is_clean());
+
+        damage.mark(PaneIndex::Result);
+        damage.panes(PaneIndex::Result, 80, lay_out);
+        damage.panes(PaneIndex::Result, 100, lay_out);
+        assert_eq!(layouts.get(), 3);
+
+        damage.resized(100, 30);
+        assert!(!damage.is_clean());
+        damage.panes(PaneIndex::Prompt, 100, Vec::new);
+        damage.panes(PaneIndex::Result, 100, lay_out);
+        // A wake-up arrives as a resize to the same size.
+        damage.resized(100, 30);
+        assert!(damage.is_clean());
+    }
+}
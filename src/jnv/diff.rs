@@ -0,0 +1,154 @@
+use promkit::serde_json::Value;
+
+/// The number of differences listed at most.
+const MAX_DIFFERENCES: usize = 10;
+/// The largest product of the lengths of two arrays whose items are aligned,
+/// beyond which they are compared index by index.
+const MAX_ALIGNED: usize = 250_000;
+
+/// Describes the structural differences between the pinned results and the live ones,
+/// one line for each changed path (e.g. `~ /0/name: "a" → "b"`).
+pub fn diff(query: &str, pinned: &[Value], live: &[Value]) -> Vec<String> {
+    let mut differences = Vec::new();
+    walk(
+        String::new(),
+        &Value::Array(pinned.to_vec()),
+        &Value::Array(live.to_vec()),
+        &mut differences,
+    );
+    if differences.is_empty() {
+        return vec![format!("Same results as the pinned query '{}'", query)];
+    }
+
+    let mut ret = vec![format!(
+        "{} differences from the pinned query '{}'",
+        differences.len(),
+        query
+    )];
+    let more = differences.len().saturating_sub(MAX_DIFFERENCES);
+    ret.extend(differences.into_iter().take(MAX_DIFFERENCES));
+    if more > 0 {
+        ret.push(format!("… {} more", more));
+    }
+    ret
+}
+
+fn walk(path: String, pinned: &Value, live: &Value, differences: &mut Vec<String>) {
+    match (pinned, live) {
+        (Value::Object(pinned), Value::Object(live)) => {
+            for (key, value) in pinned {
+                let path = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                match live.get(key) {
+                    Some(other) => walk(path, value, other, differences),
+                    None => differences.push(format!("- {}: {}", path, value)),
+                }
+            }
+            for (key, value) in live {
+                if !pinned.contains_key(key) {
+                    let path = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                    differences.push(format!("+ {}: {}", path, value));
+                }
+            }
+        }
+        (Value::Array(pinned), Value::Array(live)) => {
+            for pair in align(pinned, live) {
+                match pair {
+                    (Some(i), Some(j)) => {
+                        walk(format!("{}/{}", path, i), &pinned[i], &live[j], differences)
+                    }
+                    (Some(i), None) => differences.push(format!("- {}/{}: {}", path, i, pinned[i])),
+                    (None, Some(j)) => differences.push(format!("+ {}/{}: {}", path, j, live[j])),
+                    (None, None) => {}
+                }
+            }
+        }
+        (pinned, live) if pinned != live => {
+            differences.push(format!("~ {}: {} → {}", path, pinned, live));
+        }
+        _ => (),
+    }
+}
+
+/// Pairs the indices of the items of the arrays: the equal items in order
+/// (their longest common subsequence), and the others by position between them,
+/// so that an item inserted or removed is reported once
+/// rather than as a change of every item after it.
+fn align(pinned: &[Value], live: &[Value]) -> Vec<(Option<usize>, Option<usize>)> {
+    let (n, m) = (pinned.len(), live.len());
+    if n.saturating_mul(m) > MAX_ALIGNED {
+        return (0..n.max(m))
+            .map(|i| ((i < n).then_some(i), (i < m).then_some(i)))
+            .collect();
+    }
+    // The length of the longest common subsequence of `pinned[i..]` and `live[j..]`.
+    let mut lengths = vec![vec![0; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if pinned[i] == live[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut ret = Vec::new();
+    // The items left out of the subsequence since the last equal ones.
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    let pair = |ret: &mut Vec<_>, removed: &mut Vec<usize>, added: &mut Vec<usize>| {
+        for k in 0..removed.len().max(added.len()) {
+            ret.push((removed.get(k).copied(), added.get(k).copied()));
+        }
+        removed.clear();
+        added.clear();
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && pinned[i] == live[j] {
+            pair(&mut ret, &mut removed, &mut added);
+            ret.push((Some(i), Some(j)));
+            (i, j) = (i + 1, j + 1);
+        } else if j == m || (i < n && lengths[i + 1][j] >= lengths[i][j + 1]) {
+            removed.push(i);
+            i += 1;
+        } else {
+            added.push(j);
+            j += 1;
+        }
+    }
+    pair(&mut ret, &mut removed, &mut added);
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use promkit::serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn inserted_items_are_reported_once() {
+        let pinned = [json!([{"id": 1}, {"id": 2}])];
+        let live = [json!([{"id": 0}, {"id": 1}, {"id": 2}])];
+        assert_eq!(
+            diff(".", &pinned, &live),
+            vec![
+                "1 differences from the pinned query '.'".to_string(),
+                "+ /0/0: {\"id\":0}".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn changed_items_are_compared_in_place() {
+        let pinned = [json!([1, {"name": "a"}, 3])];
+        let live = [json!([1, {"name": "b"}, 3, 4])];
+        assert_eq!(
+            diff(".", &pinned, &live)[1..],
+            [
+                "~ /0/1/name: \"a\" → \"b\"".to_string(),
+                "+ /0/3: 4".to_string(),
+            ]
+        );
+    }
+}
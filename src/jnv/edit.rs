@@ -0,0 +1,159 @@
+use anyhow::{anyhow, bail, Result};
+use promkit::serde_json::{self, json, Value};
+
+/// A value being edited in the query editor.
+pub struct Editing {
+    /// The query that selected the value, restored after editing.
+    pub query: String,
+    /// The path to the value, as returned by jq's `path(...)`.
+    pub path: Vec<Value>,
+    /// The value before editing.
+    pub value: Value,
+}
+
+impl Editing {
+    /// Starts editing the value selected by the query.
+    ///
+    /// The query must be a path expression (e.g. `.users[0].name`)
+    /// that selects exactly one scalar value.
+    pub fn start(query: &str, json: &str) -> Result<Self> {
+        let paths = super::run_jq(&format!("path({})", query), json)?;
+        if paths.len() != 1 {
+            bail!("The query must select exactly one value to edit");
+        }
+        let document = serde_json::from_str::<Value>(json)?;
+        let path = normalize(serde_json::from_str(&paths[0])?, &document)?;
+        let value = document
+            .pointer(&pointer(&path))
+            .cloned()
+            .ok_or_else(|| anyhow!("No value found at '{}'", query))?;
+        if value.is_object() || value.is_array() {
+            bail!("Only scalar values can be edited");
+        }
+        Ok(Self {
+            query: query.to_string(),
+            path,
+            value,
+        })
+    }
+}
+
+/// A change made to a value of the input.
+#[derive(Clone)]
+pub struct Edit {
+    /// The position of the edited document in the input stream.
+    pub document: usize,
+    pub path: Vec<Value>,
+    pub value: Value,
+}
+
+impl Edit {
+    /// Applies the change to the JSON, returning the modified JSON.
+    pub fn apply(&self, json: &str) -> Result<String> {
+        let mut document: Value = serde_json::from_str(json)?;
+        let target = document
+            .pointer_mut(&pointer(&self.path))
+            .ok_or_else(|| anyhow!("No value found at '{}'", pointer(&self.path)))?;
+        *target = self.value.clone();
+        Ok(serde_json::to_string_pretty(&document)?)
+    }
+}
+
+/// The edited input, to be exported at the end of a session.
+pub struct Edits {
+    /// The documents of the input with the edits applied.
+    pub documents: Vec<String>,
+    /// The JSON Patches describing the edits, one for each document.
+    pub patches: Vec<Value>,
+}
+
+/// Resolves the indices of the jq path counted from the end of arrays (e.g. `.[-1]`)
+/// into positions in the document, which JSON Pointers only have.
+/// Paths with slices (e.g. `.[1:3]`) or indices out of the arrays are rejected.
+fn normalize(path: Vec<Value>, document: &Value) -> Result<Vec<Value>> {
+    let mut value = Some(document);
+    let mut ret = Vec::with_capacity(path.len());
+    for segment in path {
+        let segment = match (&segment, value) {
+            (Value::String(_), _) => segment,
+            (Value::Number(index), Some(Value::Array(items))) => {
+                let index = index
+                    .as_i64()
+                    .ok_or_else(|| anyhow!("The index {} is not an integer", index))?;
+                let position = if index < 0 {
+                    items.len() as i64 + index
+                } else {
+                    index
+                };
+                if position < 0 || position as usize >= items.len() {
+                    bail!(
+                        "The index {} is out of the array of {} items",
+                        index,
+                        items.len()
+                    );
+                }
+                Value::from(position)
+            }
+            (Value::Number(_), _) => bail!("The index {} is not in an array", segment),
+            _ => bail!("Only paths of keys and indices can be edited, not slices"),
+        };
+        value = match (&segment, value) {
+            (Value::String(key), Some(Value::Object(map))) => map.get(key),
+            (Value::Number(position), Some(Value::Array(items))) => position
+                .as_u64()
+                .and_then(|position| items.get(position as usize)),
+            _ => None,
+        };
+        ret.push(segment);
+    }
+    Ok(ret)
+}
+
+/// Converts a jq path into a JSON Pointer (RFC 6901).
+fn pointer(path: &[Value]) -> String {
+    path.iter()
+        .map(|segment| match segment {
+            Value::String(key) => format!("/{}", key.replace('~', "~0").replace('/', "~1")),
+            other => format!("/{}", other),
+        })
+        .collect()
+}
+
+/// Describes the edits as JSON Patches (RFC 6902), one for each document.
+pub fn patches(edits: &[Edit], documents: usize) -> Vec<Value> {
+    (0..documents)
+        .map(|document| {
+            Value::Array(
+                edits
+                    .iter()
+                    .filter(|edit| edit.document == document)
+                    .map(|edit| {
+                        json!({
+                            "op": "replace",
+                            "path": pointer(&edit.path),
+                            "value": edit.value,
+                        })
+                    })
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_indices_count_from_the_end() {
+        let document = json!({"items": [{"a": 1}, {"a": 2}, {"a": 3}]});
+        let path = normalize(vec![json!("items"), json!(-1), json!("a")], &document).unwrap();
+        assert_eq!(pointer(&path), "/items/2/a");
+        assert!(normalize(vec![json!("items"), json!(-4)], &document).is_err());
+        assert!(normalize(
+            vec![json!("items"), json!({"start": 1, "end": 2})],
+            &document
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,182 @@
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use promkit::{
+    crossterm::style::{Attribute, Color, ContentStyle},
+    json,
+    serde_json::Value,
+};
+
+use crate::jnv::formats::escape_non_ascii;
+
+/// Writes the values shown in the viewer to the file, as an HTML page
+/// for a `.html` path or as text, colored with ANSI escape codes if asked, otherwise,
+/// escaping the characters beyond ASCII if asked.
+pub fn save(
+    path: &Path,
+    values: &[Value],
+    theme: &json::Theme,
+    colored: bool,
+    ascii: bool,
+) -> Result<()> {
+    let is_html = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("html"));
+    let text = if is_html {
+        html(values, theme)
+    } else {
+        values
+            .iter()
+            .map(|value| {
+                if colored {
+                    ansi(value, theme, false) + "\n"
+                } else {
+                    let mut text = String::new();
+                    write_value(&mut text, value, theme, 0, false, &|_, text| {
+                        text.to_string()
+                    });
+                    text + "\n"
+                }
+            })
+            .collect()
+    };
+    fs::write(path, if ascii { escape_non_ascii(&text) } else { text })?;
+    Ok(())
+}
+
+/// Prints the value with the colors of the JSON viewer as ANSI escape codes,
+/// pretty or on one line if compact.
+pub fn ansi(value: &Value, theme: &json::Theme, compact: bool) -> String {
+    let mut ret = String::new();
+    write_value(&mut ret, value, theme, 0, compact, &|style, text| {
+        style.apply(text).to_string()
+    });
+    ret
+}
+
+/// Pretty-prints the values with the colors of the JSON viewer
+/// into a standalone HTML page, to be embedded into wikis and reports.
+pub fn html(values: &[Value], theme: &json::Theme) -> String {
+    let mut body = String::new();
+    for value in values {
+        write_value(&mut body, value, theme, 0, false, &|style, text| {
+            let text = escape(text);
+            match css(style) {
+                Some(css) => format!("<span style=\"{}\">{}</span>", css, text),
+                None => text,
+            }
+        });
+        body.push('\n');
+    }
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n</head>\n<body>\n<pre style=\"background: #1e1e1e; color: #d4d4d4; padding: 1em\">\n{}</pre>\n</body>\n</html>\n",
+        body
+    )
+}
+
+/// Formats the filter and its results as a Markdown snippet,
+/// the shape pasted into issues and chats.
+pub fn markdown(query: &str, results: &[String]) -> String {
+    format!(
+        "```jq\n{}\n```\n\n```json\n{}\n```\n",
+        query,
+        results.join("\n")
+    )
+}
+
+fn write_value(
+    out: &mut String,
+    value: &Value,
+    theme: &json::Theme,
+    level: usize,
+    compact: bool,
+    paint: &dyn Fn(&ContentStyle, &str) -> String,
+) {
+    // Starts a line indented to the level, unless compact.
+    let break_line = |out: &mut String, level: usize| {
+        if !compact {
+            out.push('\n');
+            out.push_str(&" ".repeat(theme.indent * level));
+        }
+    };
+    match value {
+        Value::Object(map) if map.is_empty() => {
+            out.push_str(&paint(&theme.curly_brackets_style, "{}"))
+        }
+        Value::Array(array) if array.is_empty() => {
+            out.push_str(&paint(&theme.square_brackets_style, "[]"))
+        }
+        Value::Object(map) => {
+            out.push_str(&paint(&theme.curly_brackets_style, "{"));
+            for (i, (key, child)) in map.iter().enumerate() {
+                break_line(out, level + 1);
+                out.push_str(&paint(
+                    &theme.key_style,
+                    &Value::from(key.as_str()).to_string(),
+                ));
+                out.push_str(if compact { ":" } else { ": " });
+                write_value(out, child, theme, level + 1, compact, paint);
+                if i + 1 < map.len() {
+                    out.push(',');
+                }
+            }
+            break_line(out, level);
+            out.push_str(&paint(&theme.curly_brackets_style, "}"));
+        }
+        Value::Array(array) => {
+            out.push_str(&paint(&theme.square_brackets_style, "["));
+            for (i, child) in array.iter().enumerate() {
+                break_line(out, level + 1);
+                write_value(out, child, theme, level + 1, compact, paint);
+                if i + 1 < array.len() {
+                    out.push(',');
+                }
+            }
+            break_line(out, level);
+            out.push_str(&paint(&theme.square_brackets_style, "]"));
+        }
+        Value::String(_) => out.push_str(&paint(&theme.string_value_style, &value.to_string())),
+        Value::Number(_) => out.push_str(&paint(&theme.number_value_style, &value.to_string())),
+        Value::Bool(_) => out.push_str(&paint(&theme.boolean_value_style, &value.to_string())),
+        Value::Null => out.push_str(&paint(&theme.null_value_style, &value.to_string())),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Converts the style into CSS, with the colors of a common dark terminal palette.
+fn css(style: &ContentStyle) -> Option<String> {
+    let mut css = Vec::new();
+    let color = match style.foreground_color {
+        Some(Color::Black) => Some("#000000".to_string()),
+        Some(Color::DarkGrey) => Some("#808080".to_string()),
+        Some(Color::Grey) => Some("#c0c0c0".to_string()),
+        Some(Color::White) => Some("#ffffff".to_string()),
+        Some(Color::Red) => Some("#f14c4c".to_string()),
+        Some(Color::DarkRed) => Some("#cd3131".to_string()),
+        Some(Color::Green) => Some("#23d18b".to_string()),
+        Some(Color::DarkGreen) => Some("#0dbc79".to_string()),
+        Some(Color::Yellow) => Some("#f5f543".to_string()),
+        Some(Color::DarkYellow) => Some("#e5e510".to_string()),
+        Some(Color::Blue) => Some("#3b8eea".to_string()),
+        Some(Color::DarkBlue) => Some("#2472c8".to_string()),
+        Some(Color::Magenta) => Some("#d670d6".to_string()),
+        Some(Color::DarkMagenta) => Some("#bc3fbc".to_string()),
+        Some(Color::Cyan) => Some("#29b8db".to_string()),
+        Some(Color::DarkCyan) => Some("#11a8cd".to_string()),
+        Some(Color::Rgb { r, g, b }) => Some(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+        _ => None,
+    };
+    if let Some(color) = color {
+        css.push(format!("color: {}", color));
+    }
+    if style.attributes.has(Attribute::Bold) {
+        css.push("font-weight: bold".to_string());
+    }
+    (!css.is_empty()).then(|| css.join("; "))
+}
@@ -0,0 +1,443 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use promkit::serde_json::{self, Map, Value};
+use serde::Deserialize;
+
+/// The number of paths listed when the results can't be written as TOML.
+const LISTED_PATHS: usize = 5;
+
+/// The formats the results can be output and exported in, besides JSON.
+#[derive(Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    Yaml,
+    Toml,
+    Gron,
+    JsonLines,
+}
+
+impl OutputFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Toml => "toml",
+            OutputFormat::Gron => "gron",
+            OutputFormat::JsonLines => "jsonl",
+        }
+    }
+
+    /// The next format, wrapping around to JSON.
+    pub fn next(self) -> Self {
+        match self {
+            OutputFormat::Json => OutputFormat::Yaml,
+            OutputFormat::Yaml => OutputFormat::Toml,
+            OutputFormat::Toml => OutputFormat::Gron,
+            OutputFormat::Gron => OutputFormat::JsonLines,
+            OutputFormat::JsonLines => OutputFormat::Json,
+        }
+    }
+
+    /// The format of a file exported with the extension, if any.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "json" => Some(OutputFormat::Json),
+            "yaml" | "yml" => Some(OutputFormat::Yaml),
+            "toml" => Some(OutputFormat::Toml),
+            "gron" => Some(OutputFormat::Gron),
+            "jsonl" | "ndjson" => Some(OutputFormat::JsonLines),
+            _ => None,
+        }
+    }
+
+    /// Formats the results of the filter, each given as JSON,
+    /// escaping the characters beyond ASCII if asked, like `jq --ascii-output`.
+    pub fn render(self, results: &[String], ascii: bool) -> Result<String> {
+        let values = results
+            .iter()
+            .map(|result| serde_json::from_str::<Value>(result))
+            .collect::<Result<Vec<_>, _>>()?;
+        let text = match self {
+            OutputFormat::Json => results
+                .iter()
+                .map(|result| format!("{}\n", result))
+                .collect(),
+            // A YAML stream, with a document for each result.
+            OutputFormat::Yaml => values
+                .iter()
+                .map(|value| yaml(value, ascii).join("\n") + "\n")
+                .collect::<Vec<_>>()
+                .join("---\n"),
+            OutputFormat::Gron => {
+                let mut lines = Vec::new();
+                match values.as_slice() {
+                    [value] => gron(value, "json".to_string(), &mut lines),
+                    // A stream, like `gron --stream`.
+                    _ => gron(&Value::Array(values), "json".to_string(), &mut lines),
+                }
+                lines.into_iter().map(|line| line + "\n").collect()
+            }
+            // The items of a single array, or the results of a stream,
+            // a compact document on each line, as log ingestion and bulk imports expect.
+            OutputFormat::JsonLines => {
+                let values = match values.as_slice() {
+                    [Value::Array(items)] => items.clone(),
+                    _ => values,
+                };
+                values.iter().map(|value| format!("{}\n", value)).collect()
+            }
+            OutputFormat::Toml => match values.as_slice() {
+                [value] => toml(value)?,
+                _ => {
+                    return Err(anyhow!(
+                        "TOML holds a single table, but the filter produced {} results",
+                        values.len()
+                    ))
+                }
+            },
+        };
+        Ok(if ascii { escape_non_ascii(&text) } else { text })
+    }
+}
+
+/// Escapes the characters beyond ASCII in the JSON text as `\uXXXX`,
+/// which only appear in strings.
+pub fn escape_non_ascii(text: &str) -> String {
+    let mut ret = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii() {
+            ret.push(c);
+        } else {
+            let mut units = [0; 2];
+            for unit in c.encode_utf16(&mut units) {
+                ret.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+    ret
+}
+
+/// Returns whether the file is read as YAML, by its extension.
+pub fn is_yaml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"))
+}
+
+/// Converts a YAML stream into a JSON document for each of its documents.
+pub fn yaml_to_json(text: &str) -> Result<String> {
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(text) {
+        let value = Value::deserialize(document)?;
+        documents.push(serde_json::to_string(&value)?);
+    }
+    Ok(documents.join("\n"))
+}
+
+/// Reads the JSON of the file, converting it from YAML by its extension.
+pub fn read_file(path: &Path) -> Result<String> {
+    let text = fs::read_to_string(path)?;
+    if is_yaml(path) {
+        yaml_to_json(&text)
+    } else {
+        Ok(text)
+    }
+}
+
+/// Formats the value as the lines of a YAML block,
+/// quoting the strings beyond ASCII to be escaped if asked.
+fn yaml(value: &Value, ascii: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                let key = yaml_string(key, ascii);
+                if is_block(child) {
+                    lines.push(format!("{}:", key));
+                    lines.extend(
+                        yaml(child, ascii)
+                            .into_iter()
+                            .map(|line| format!("  {}", line)),
+                    );
+                } else {
+                    lines.push(format!("{}: {}", key, yaml_scalar(child, ascii)));
+                }
+            }
+        }
+        Value::Array(array) if !array.is_empty() => {
+            for child in array {
+                if is_block(child) {
+                    // The first line of the item follows the dash.
+                    for (i, line) in yaml(child, ascii).into_iter().enumerate() {
+                        let prefix = if i == 0 { "- " } else { "  " };
+                        lines.push(format!("{}{}", prefix, line));
+                    }
+                } else {
+                    lines.push(format!("- {}", yaml_scalar(child, ascii)));
+                }
+            }
+        }
+        _ => lines.push(yaml_scalar(value, ascii)),
+    }
+    lines
+}
+
+/// Whether the value is written as an indented block, rather than on the line of its key.
+fn is_block(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => !map.is_empty(),
+        Value::Array(array) => !array.is_empty(),
+        _ => false,
+    }
+}
+
+fn yaml_scalar(value: &Value, ascii: bool) -> String {
+    match value {
+        Value::Object(_) => "{}".to_string(),
+        Value::Array(_) => "[]".to_string(),
+        Value::String(s) => yaml_string(s, ascii),
+        _ => value.to_string(),
+    }
+}
+
+/// Writes the string plain when YAML reads it back as the same string,
+/// and double-quoted, which is also valid JSON, otherwise
+/// or when its characters beyond ASCII are to be escaped.
+fn yaml_string(s: &str, ascii: bool) -> String {
+    let reserved = [
+        "true", "false", "null", "yes", "no", "on", "off", "y", "n", "~",
+    ];
+    let is_plain = !s.is_empty()
+        && s.trim() == s
+        && !s.starts_with(|c: char| "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        // Numbers, dates and the like.
+        && !s.starts_with(|c: char| c.is_ascii_digit() || "+.".contains(c))
+        && !s.contains(": ")
+        && !s.contains(" #")
+        && !s.ends_with(':')
+        && !s.chars().any(char::is_control)
+        && !reserved.contains(&s.to_lowercase().as_str())
+        && (!ascii || s.is_ascii());
+    if is_plain {
+        s.to_string()
+    } else {
+        Value::from(s).to_string()
+    }
+}
+
+/// Formats the object as a TOML document, failing with the paths
+/// of the values TOML has no way to write (null).
+fn toml(value: &Value) -> Result<String> {
+    let Value::Object(map) = value else {
+        return Err(anyhow!(
+            "TOML needs an object at the top, but the result is {}",
+            type_name(value)
+        ));
+    };
+    let mut nulls = Vec::new();
+    find_nulls(value, String::new(), &mut nulls);
+    if !nulls.is_empty() {
+        let mut listed = nulls.iter().take(LISTED_PATHS).cloned().collect::<Vec<_>>();
+        if nulls.len() > LISTED_PATHS {
+            listed.push(format!("and {} more", nulls.len() - LISTED_PATHS));
+        }
+        return Err(anyhow!(
+            "TOML can't hold null, found at {}",
+            listed.join(", ")
+        ));
+    }
+    let mut lines = Vec::new();
+    toml_table(map, &[], &mut lines);
+    Ok(lines.join("\n") + "\n")
+}
+
+fn find_nulls(value: &Value, path: String, nulls: &mut Vec<String>) {
+    match value {
+        Value::Null => nulls.push(if path.is_empty() {
+            ".".to_string()
+        } else {
+            path
+        }),
+        Value::Object(map) => {
+            for (key, child) in map {
+                find_nulls(child, format!("{}.{}", path, key), nulls);
+            }
+        }
+        Value::Array(array) => {
+            for (i, child) in array.iter().enumerate() {
+                find_nulls(child, format!("{}[{}]", path, i), nulls);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Writes the entries of the table, the values first,
+/// then the tables and arrays of tables under their headers.
+fn toml_table(map: &Map<String, Value>, path: &[String], lines: &mut Vec<String>) {
+    for (key, child) in map {
+        if !is_table(child) && !is_array_of_tables(child) {
+            lines.push(format!("{} = {}", toml_key(key), toml_inline(child)));
+        }
+    }
+    for (key, child) in map {
+        let mut child_path = path.to_vec();
+        child_path.push(toml_key(key));
+        match child {
+            Value::Object(child_map) if is_table(child) => {
+                if !lines.is_empty() {
+                    lines.push(String::new());
+                }
+                lines.push(format!("[{}]", child_path.join(".")));
+                toml_table(child_map, &child_path, lines);
+            }
+            Value::Array(array) if is_array_of_tables(child) => {
+                for item in array.iter().filter_map(Value::as_object) {
+                    if !lines.is_empty() {
+                        lines.push(String::new());
+                    }
+                    lines.push(format!("[[{}]]", child_path.join(".")));
+                    toml_table(item, &child_path, lines);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_table(value: &Value) -> bool {
+    value.as_object().is_some_and(|map| !map.is_empty())
+}
+
+fn is_array_of_tables(value: &Value) -> bool {
+    value
+        .as_array()
+        .is_some_and(|array| !array.is_empty() && array.iter().all(Value::is_object))
+}
+
+fn toml_inline(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let entries = map
+                .iter()
+                .map(|(key, child)| format!("{} = {}", toml_key(key), toml_inline(child)))
+                .collect::<Vec<_>>();
+            if entries.is_empty() {
+                "{}".to_string()
+            } else {
+                format!("{{ {} }}", entries.join(", "))
+            }
+        }
+        Value::Array(array) => format!(
+            "[{}]",
+            array.iter().map(toml_inline).collect::<Vec<_>>().join(", ")
+        ),
+        // JSON strings and numbers are valid TOML.
+        _ => value.to_string(),
+    }
+}
+
+/// Writes the key bare if TOML allows it, and quoted otherwise.
+fn toml_key(key: &str) -> String {
+    let is_bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if is_bare {
+        key.to_string()
+    } else {
+        Value::from(key).to_string()
+    }
+}
+
+/// Writes an assignment for every value, as gron does
+/// (e.g. `json.items[0].name = "x";`), to grep and diff the output.
+fn gron(value: &Value, path: String, lines: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            lines.push(format!("{} = {{}};", path));
+            for (key, child) in map {
+                gron(child, format!("{}{}", path, gron_key(key)), lines);
+            }
+        }
+        Value::Array(array) => {
+            lines.push(format!("{} = [];", path));
+            for (i, child) in array.iter().enumerate() {
+                gron(child, format!("{}[{}]", path, i), lines);
+            }
+        }
+        _ => lines.push(format!("{} = {};", path, value)),
+    }
+}
+
+/// Writes the key as a property when it is an identifier, and quoted otherwise.
+fn gron_key(key: &str) -> String {
+    let is_identifier = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        format!(".{}", key)
+    } else {
+        format!("[{}]", Value::from(key))
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_non_ascii_writes_utf16_units() {
+        assert_eq!(escape_non_ascii("\"é\""), "\"\\u00e9\"");
+        assert_eq!(escape_non_ascii("😀"), "\\ud83d\\ude00");
+        assert_eq!(escape_non_ascii("{\"a\": 1}"), "{\"a\": 1}");
+    }
+
+    #[test]
+    fn yaml_string_quotes_what_yaml_would_read_otherwise() {
+        assert_eq!(yaml_string("plain text", false), "plain text");
+        for s in ["", "yes", "Null", "1.0", "-x", "a: b", " padded", "key:"] {
+            assert_eq!(yaml_string(s, false), Value::from(s).to_string());
+        }
+        assert_eq!(yaml_string("é", false), "é");
+        assert_eq!(yaml_string("é", true), "\"é\"");
+    }
+
+    #[test]
+    fn yaml_documents_become_json_documents() {
+        let text = "a: 1\nb: [x, true]\n---\n- null\n";
+        assert_eq!(
+            yaml_to_json(text).unwrap(),
+            "{\"a\":1,\"b\":[\"x\",true]}\n[null]"
+        );
+        assert!(yaml_to_json("a: [").is_err());
+    }
+
+    #[test]
+    fn toml_writes_tables() {
+        let value = serde_json::json!({"a": 1, "t": {"b": "x"}});
+        assert_eq!(toml(&value).unwrap(), "a = 1\n\n[t]\nb = \"x\"\n");
+    }
+
+    #[test]
+    fn toml_fails_on_null_and_non_objects() {
+        let null = serde_json::json!({"a": {"b": null}});
+        assert!(toml(&null).unwrap_err().to_string().contains(".a.b"));
+        assert!(toml(&serde_json::json!([1])).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+use promkit::text_editor::TextEditor;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Returns the positions (counted in chars) at which grapheme clusters start,
+/// followed by the position of the end of the text.
+///
+/// The text editor moves its cursor char by char, so an emoji sequence
+/// or a character with combining marks spans multiple cursor positions.
+fn boundaries(text: &str) -> Vec<usize> {
+    let mut ret = vec![0];
+    let mut position = 0;
+    for cluster in text.graphemes(true) {
+        position += cluster.chars().count();
+        ret.push(position);
+    }
+    ret
+}
+
+/// Returns the start of the grapheme cluster before the cursor.
+fn previous_boundary(texteditor: &TextEditor) -> usize {
+    let position = texteditor.position();
+    boundaries(&texteditor.text_without_cursor().to_string())
+        .into_iter()
+        .rev()
+        .find(|&boundary| boundary < position)
+        .unwrap_or(0)
+}
+
+/// Moves the cursor to the start of the previous grapheme cluster.
+pub fn backward(texteditor: &mut TextEditor) {
+    let position = texteditor.position();
+    for _ in previous_boundary(texteditor)..position {
+        texteditor.backward();
+    }
+}
+
+/// Moves the cursor to the start of the next grapheme cluster.
+pub fn forward(texteditor: &mut TextEditor) {
+    let position = texteditor.position();
+    let text = texteditor.text_without_cursor().to_string();
+    let target = boundaries(&text)
+        .into_iter()
+        .find(|&boundary| boundary > position)
+        .unwrap_or(position);
+    for _ in position..target {
+        texteditor.forward();
+    }
+}
+
+/// Erases the whole grapheme cluster before the cursor.
+pub fn erase(texteditor: &mut TextEditor) {
+    let position = texteditor.position();
+    for _ in previous_boundary(texteditor)..position {
+        texteditor.erase();
+    }
+}
+
+/// Returns the number of columns the text takes up on the terminal,
+/// where wide characters (e.g. CJK) take two and combining marks none.
+pub fn width(text: &str) -> usize {
+    text.width()
+}
+
+/// Returns how many characters of the given widths take up the columns scrolled past,
+/// including a wide character cut in half at the edge.
+pub fn columns_skipped(widths: impl Iterator<Item = usize>, column: usize) -> usize {
+    let mut scrolled = 0;
+    widths
+        .take_while(|width| {
+            let skip = scrolled < column;
+            scrolled += width;
+            skip
+        })
+        .count()
+}
+
+/// Returns how many characters of the given widths go on each line
+/// when they wrap within the columns, a character wider than them taking a line.
+pub fn wrap_columns(widths: impl Iterator<Item = usize>, columns: usize) -> Vec<usize> {
+    let mut lines = vec![0];
+    let mut used = 0;
+    for width in widths {
+        let last = lines.len() - 1;
+        if used + width > columns && lines[last] > 0 {
+            lines.push(0);
+            used = 0;
+        }
+        let last = lines.len() - 1;
+        lines[last] += 1;
+        used += width;
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn width_counts_columns() {
+        assert_eq!(width(".name"), 5);
+        assert_eq!(width(".日本"), 5);
+        assert_eq!(width("e\u{301}"), 1);
+    }
+
+    #[test]
+    fn boundaries_of_clusters() {
+        assert_eq!(boundaries("ae\u{301}b"), vec![0, 1, 3, 4]);
+    }
+
+    #[test]
+    fn wrap_columns_fills_each_line() {
+        assert_eq!(wrap_columns([1, 1, 1, 1, 1].into_iter(), 2), vec![2, 2, 1]);
+        assert_eq!(wrap_columns([1, 2, 1].into_iter(), 2), vec![1, 1, 1]);
+        assert_eq!(wrap_columns([3].into_iter(), 2), vec![1]);
+        assert_eq!(wrap_columns([].into_iter(), 2), vec![0]);
+    }
+
+    #[test]
+    fn columns_skipped_include_a_wide_character_cut_in_half() {
+        assert_eq!(columns_skipped([1, 1, 1, 1].into_iter(), 2), 2);
+        assert_eq!(columns_skipped([1, 2, 1].into_iter(), 2), 2);
+        assert_eq!(columns_skipped([1, 1].into_iter(), 8), 2);
+        assert_eq!(columns_skipped([1, 1].into_iter(), 0), 0);
+    }
+}
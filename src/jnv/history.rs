@@ -0,0 +1,41 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Result;
+use promkit::serde_json;
+
+use super::store;
+
+/// The file in the data directory keeping the accepted filters of every input.
+const HISTORY_FILE: &str = "history.json";
+
+/// The filters accepted in past sessions, most recent first,
+/// offered as completions along with the paths of the input.
+#[derive(Default)]
+pub struct History {
+    /// The file, if a data directory is available.
+    file: Option<PathBuf>,
+    pub queries: Vec<String>,
+}
+
+impl History {
+    pub fn load() -> Self {
+        let file = store::data_dir().map(|dir| dir.join(HISTORY_FILE));
+        let queries = file
+            .as_ref()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        Self { file, queries }
+    }
+
+    /// Moves the filter to the front, forgetting the oldest ones past the history size.
+    pub fn push(&mut self, query: &str) -> Result<()> {
+        self.queries.retain(|kept| kept != query);
+        self.queries.insert(0, query.to_string());
+        self.queries.truncate(store::history_size());
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        store::write(file, &serde_json::to_string(&self.queries)?)
+    }
+}
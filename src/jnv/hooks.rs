@@ -0,0 +1,199 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+
+#[cfg(feature = "scripting")]
+mod scripting;
+
+/// How long the calls to `settle` stay quiet before the last one is made.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// A user script called on events of the session:
+/// - `on_start`: the first document, when the interface starts.
+/// - `on_query_result <query>`: the results as JSON Lines,
+///   when a query succeeds and is left unchanged for a while.
+/// - `custom_command <query>`: the value at the cursor, on Alt + J.
+///
+/// The script is either an executable, written in any language, given the name
+/// of the event and its arguments on the command line and the JSON on standard input,
+/// or, with the `scripting` feature, a Rhai script (`.rhai`) run by an embedded engine.
+#[derive(Default)]
+pub struct Hooks {
+    script: Option<Arc<Script>>,
+    /// The calls waiting for the calls after them to settle.
+    settling: Option<Sender<Call>>,
+}
+
+enum Script {
+    Executable(PathBuf),
+    #[cfg(feature = "scripting")]
+    Rhai(Box<scripting::Script>),
+}
+
+/// An event with its arguments and its JSON.
+struct Call {
+    event: String,
+    args: Vec<String>,
+    input: String,
+}
+
+impl Script {
+    fn open(path: PathBuf) -> Result<Self> {
+        #[cfg(feature = "scripting")]
+        if path.extension().is_some_and(|ext| ext == "rhai") {
+            return Ok(Self::Rhai(Box::new(scripting::Script::compile(&path)?)));
+        }
+        Ok(Self::Executable(path))
+    }
+
+    /// Calls the script, returning its output if captured.
+    fn call(&self, call: Call, capture: bool) -> Result<String> {
+        match self {
+            Self::Executable(path) => {
+                let mut command = Command::new(path);
+                command.arg(call.event).args(call.args).stdout(if capture {
+                    Stdio::piped()
+                } else {
+                    Stdio::null()
+                });
+                run(command, call.input)
+            }
+            #[cfg(feature = "scripting")]
+            Self::Rhai(script) => script.call(&call.event, &call.args, &call.input),
+        }
+    }
+}
+
+/// Checks that the script can be called, i.e. that a Rhai script compiles.
+pub fn check(path: &Path) -> Result<()> {
+    Script::open(path.to_path_buf()).map(drop)
+}
+
+impl Hooks {
+    /// Opens the script, if any, which is left out if it fails to compile.
+    pub fn new(script: Option<PathBuf>) -> Self {
+        let script = script.and_then(|path| Script::open(path).ok().map(Arc::new));
+        let settling = script.clone().map(|script| {
+            let (tx, rx) = mpsc::channel::<Call>();
+            thread::spawn(move || {
+                while let Ok(mut last) = rx.recv() {
+                    loop {
+                        match rx.recv_timeout(SETTLE_TIME) {
+                            Ok(next) => last = next,
+                            Err(RecvTimeoutError::Timeout) => break,
+                            Err(RecvTimeoutError::Disconnected) => return,
+                        }
+                    }
+                    let _ = script.call(last, false);
+                }
+            });
+            tx
+        });
+        Self { script, settling }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.script.is_none()
+    }
+
+    /// Calls the script in the background, not to slow down the interface,
+    /// ignoring its output.
+    pub fn notify(&self, event: &str, args: &[&str], input: String) {
+        let Some(script) = self.script.clone() else {
+            return;
+        };
+        let call = Call::new(event, args, input);
+        thread::spawn(move || script.call(call, false));
+    }
+
+    /// Calls the script in the background like `notify`, but only once no other call
+    /// has been made for a while (e.g. for the results of a query being typed),
+    /// the earlier ones being dropped.
+    pub fn settle(&self, event: &str, args: &[&str], input: String) {
+        if let Some(settling) = &self.settling {
+            let _ = settling.send(Call::new(event, args, input));
+        }
+    }
+
+    /// Calls the script and returns its output.
+    pub fn run(&self, event: &str, args: &[&str], input: String) -> Result<String> {
+        let Some(script) = &self.script else {
+            return Err(anyhow!("No hooks script is installed"));
+        };
+        script.call(Call::new(event, args, input), true)
+    }
+}
+
+impl Call {
+    fn new(event: &str, args: &[&str], input: String) -> Self {
+        Self {
+            event: event.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            input,
+        }
+    }
+}
+
+fn run(mut command: Command, input: String) -> Result<String> {
+    // The script must not draw over the interface.
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("Failed to write to the hooks script"))?;
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    // The script may exit without reading its input.
+    let _ = writer.join();
+    if !output.status.success() {
+        return Err(anyhow!("The hooks script failed with {}", output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{fs, os::unix::fs::PermissionsExt, time::Instant};
+
+    use super::*;
+
+    #[test]
+    fn settled_calls_are_made_once() {
+        let dir = std::env::temp_dir().join(format!("jnv-hooks-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("log");
+        let script = dir.join("hooks");
+        fs::write(
+            &script,
+            format!("#!/bin/sh\necho \"$1 $2\" >> '{}'\n", log.display()),
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let hooks = Hooks::new(Some(script));
+        for query in [".a", ".ab", ".abc"] {
+            hooks.settle("on_query_result", &[query], String::new());
+        }
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !log.exists() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        thread::sleep(SETTLE_TIME);
+        assert_eq!(fs::read_to_string(&log).unwrap(), "on_query_result .abc\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
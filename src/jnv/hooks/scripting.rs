@@ -0,0 +1,129 @@
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+};
+
+use anyhow::{anyhow, Result};
+use promkit::serde_json::{self, Value};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Scope, AST};
+
+/// The operations a script may run for one event, after which it is stopped as runaway.
+const MAX_OPERATIONS: u64 = 100_000_000;
+
+/// A Rhai script defining a function for each event it handles,
+/// given the arguments of the event and then its JSON as Rhai values
+/// (the results of `on_query_result` as an array),
+/// and returning the text to show for `custom_command`.
+///
+/// Besides the standard functions of Rhai, the script may call
+/// `exec(program, args, input)` to run a command (e.g. `curl` to post
+/// the value to an API), which returns its standard output.
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+}
+
+impl Script {
+    pub fn compile(path: &Path) -> Result<Self> {
+        Self::from_source(&fs::read_to_string(path)?)
+    }
+
+    fn from_source(source: &str) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        // The script must not draw over the interface.
+        engine.on_print(|_| {});
+        engine.on_debug(|_, _, _| {});
+        engine.register_fn("exec", exec);
+        let ast = engine.compile(source)?;
+        Ok(Self { engine, ast })
+    }
+
+    /// Calls the function of the event, returning what it returns as text.
+    pub fn call(&self, event: &str, args: &[String], input: &str) -> Result<String> {
+        if !self.ast.iter_functions().any(|f| f.name == event) {
+            return Err(anyhow!("The hooks script has no '{}' function", event));
+        }
+        let values = serde_json::Deserializer::from_str(input)
+            .into_iter::<Value>()
+            .collect::<Result<Vec<_>, _>>()?;
+        let input = if event == "on_query_result" {
+            rhai::serde::to_dynamic(values)?
+        } else {
+            rhai::serde::to_dynamic(values.into_iter().next().unwrap_or(Value::Null))?
+        };
+        let mut call_args: Vec<Dynamic> = args.iter().map(|arg| arg.clone().into()).collect();
+        call_args.push(input);
+        let ret: Dynamic = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, event, call_args)?;
+        Ok(if ret.is_unit() {
+            String::new()
+        } else {
+            ret.to_string()
+        })
+    }
+}
+
+/// Runs the program with the arguments and the input on standard input,
+/// returning its standard output.
+fn exec(program: &str, args: Array, input: &str) -> Result<String, Box<EvalAltResult>> {
+    let run = || -> Result<String> {
+        let mut child = Command::new(program)
+            .args(args.iter().map(|arg| arg.to_string()))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("Failed to write to '{}'", program))?;
+        let input = input.to_string();
+        let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+        let output = child.wait_with_output()?;
+        // The program may exit without reading its input.
+        let _ = writer.join();
+        if !output.status.success() {
+            return Err(anyhow!("'{}' failed with {}", program, output.status));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    };
+    run().map_err(|e| e.to_string().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_call_the_functions_of_the_script() {
+        let script = Script::from_source(
+            r#"
+            fn on_query_result(query, results) { print(query); results.len() }
+            fn custom_command(query, value) { `${query}: ${value.name}` }
+            fn on_start(document) { loop {} }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            script
+                .call("custom_command", &[".[]".into()], r#"{"name": "a"}"#)
+                .unwrap(),
+            ".[]: a"
+        );
+        assert_eq!(
+            script
+                .call("on_query_result", &[".[]".into()], "1\n2\n3\n")
+                .unwrap(),
+            "3"
+        );
+        // Runaway scripts are stopped.
+        assert!(script.call("on_start", &[], "{}").is_err());
+
+        assert!(Script::from_source("fn on_start(").is_err());
+    }
+}
@@ -1,254 +1,104 @@
 use promkit::{
     crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
-    listbox::Listbox,
-    text_editor, PromptSignal, Result,
+    text_editor,
 };
 
-pub fn default(event: &Event, renderer: &mut crate::jnv::render::Renderer) -> Result<PromptSignal> {
-    let query_editor_after_mut = renderer.query_editor_snapshot.after_mut();
-    let suggest_after_mut = renderer.suggest_snapshot.after_mut();
-    let json_bundle_after_mut = renderer.json_bundle_snapshot.after_mut();
-
+mod actions;
+mod chord;
+mod default;
+mod edit;
+mod export;
+mod help;
+mod jump;
+mod key_filter;
+mod modes;
+mod navigate;
+mod overlays;
+mod pick;
+mod pipe;
+mod registers;
+mod suggest;
+mod visual;
+mod wizard;
+pub use chord::{on_chord, on_chord_timeout, on_wrap, Chord};
+pub use default::default;
+pub use edit::on_edit;
+pub use export::on_export;
+pub use help::on_help;
+pub use jump::on_jump;
+pub use key_filter::on_key_filter;
+pub use navigate::on_navigate;
+pub use overlays::{on_settings, on_theme};
+pub use pick::on_pick;
+pub use pipe::on_pipe;
+pub use registers::{on_macro, on_mark};
+pub use suggest::{finish_indexing, on_suggest};
+pub use visual::on_visual;
+pub use wizard::on_wizard;
+
+/// Normalizes a key event so that the keymaps behave the same across terminals.
+///
+/// Windows Terminal and ConPTY report key releases and repeats,
+/// lock keys (e.g. Caps Lock) in the event state,
+/// and characters typed with AltGr (e.g. `{` or `|` on European layouts)
+/// as Ctrl + Alt combinations. Returns `None` for events to be ignored.
+pub fn normalize(event: &Event) -> Option<Event> {
     match event {
         Event::Key(KeyEvent {
-            code: KeyCode::Tab,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        }) => {
-            let query = query_editor_after_mut
-                .texteditor
-                .text_without_cursor()
-                .to_string();
-            if let Some(mut candidates) = renderer.suggest.prefix_search(query) {
-                candidates.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
-
-                suggest_after_mut.listbox = Listbox::from_iter(candidates);
-                query_editor_after_mut
-                    .texteditor
-                    .replace(&suggest_after_mut.listbox.get());
-
-                renderer.keymap.switch("on_suggest");
-            }
-        }
-
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('c'),
-            modifiers: KeyModifiers::CONTROL,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        }) => return Ok(PromptSignal::Quit),
-
-        // Move cursor.
-        Event::Key(KeyEvent {
-            code: KeyCode::Left,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        }) => {
-            query_editor_after_mut.texteditor.backward();
-        }
-        Event::Key(KeyEvent {
-            code: KeyCode::Right,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        }) => {
-            query_editor_after_mut.texteditor.forward();
-        }
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('a'),
-            modifiers: KeyModifiers::CONTROL,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        }) => query_editor_after_mut.texteditor.move_to_head(),
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('e'),
-            modifiers: KeyModifiers::CONTROL,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        }) => query_editor_after_mut.texteditor.move_to_tail(),
-
-        // Erase char(s).
-        Event::Key(KeyEvent {
-            code: KeyCode::Backspace,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        }) => query_editor_after_mut.texteditor.erase(),
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('u'),
-            modifiers: KeyModifiers::CONTROL,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        }) => query_editor_after_mut.texteditor.erase_all(),
-
-        // Move up.
-        Event::Key(KeyEvent {
-            code: KeyCode::Up,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        })
-        | Event::Key(KeyEvent {
-            code: KeyCode::Char('k'),
-            modifiers: KeyModifiers::CONTROL,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        }) => {
-            json_bundle_after_mut.bundle.backward();
-        }
-
-        // Move down.
-        Event::Key(KeyEvent {
-            code: KeyCode::Down,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        })
-        | Event::Key(KeyEvent {
-            code: KeyCode::Char('j'),
-            modifiers: KeyModifiers::CONTROL,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        }) => {
-            json_bundle_after_mut.bundle.forward();
-        }
-
-        // Move to tail
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('h'),
-            modifiers: KeyModifiers::CONTROL,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        }) => {
-            json_bundle_after_mut.bundle.move_to_tail();
-        }
-
-        // Move to head
+            kind: KeyEventKind::Release,
+            ..
+        }) => None,
         Event::Key(KeyEvent {
-            code: KeyCode::Char('l'),
-            modifiers: KeyModifiers::CONTROL,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        }) => {
-            json_bundle_after_mut.bundle.move_to_head();
-        }
-
-        // Toggle collapse/expand
-        Event::Key(KeyEvent {
-            code: KeyCode::Enter,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
+            code, modifiers, ..
         }) => {
-            json_bundle_after_mut.bundle.toggle();
+            let mut modifiers = *modifiers;
+            if let KeyCode::Char(_) = code {
+                if modifiers.contains(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+                    modifiers.remove(KeyModifiers::CONTROL | KeyModifiers::ALT);
+                }
+            }
+            Some(Event::Key(KeyEvent {
+                code: *code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                state: KeyEventState::NONE,
+            }))
         }
+        _ => Some(event.clone()),
+    }
+}
 
+/// Returns whether the event only edits the text in the query editor.
+fn is_text_input(event: &Event) -> bool {
+    matches!(
+        event,
         Event::Key(KeyEvent {
-            code: KeyCode::Char('p'),
-            modifiers: KeyModifiers::CONTROL,
+            code: KeyCode::Char(_) | KeyCode::Backspace | KeyCode::Left | KeyCode::Right,
+            modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
             kind: KeyEventKind::Press,
             state: KeyEventState::NONE,
-        }) => {
-            json_bundle_after_mut.bundle.expand_all();
-        }
-
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('n'),
+        }) | Event::Key(KeyEvent {
+            code: KeyCode::Char('a' | 'e' | 'u'),
             modifiers: KeyModifiers::CONTROL,
             kind: KeyEventKind::Press,
             state: KeyEventState::NONE,
-        }) => {
-            json_bundle_after_mut.bundle.collapse_all();
-        }
-
-        // Input char.
-        Event::Key(KeyEvent {
-            code: KeyCode::Char(ch),
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        })
-        | Event::Key(KeyEvent {
-            code: KeyCode::Char(ch),
-            modifiers: KeyModifiers::SHIFT,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        }) => match query_editor_after_mut.edit_mode {
-            text_editor::Mode::Insert => query_editor_after_mut.texteditor.insert(*ch),
-            text_editor::Mode::Overwrite => query_editor_after_mut.texteditor.overwrite(*ch),
-        },
+        }) | Event::Paste(_)
+    )
+}
 
-        _ => (),
+fn input_char(query_editor: &mut text_editor::Renderer, ch: char) {
+    match query_editor.edit_mode {
+        text_editor::Mode::Insert => query_editor.texteditor.insert(ch),
+        text_editor::Mode::Overwrite => query_editor.texteditor.overwrite(ch),
     }
-    Ok(PromptSignal::Continue)
 }
 
-pub fn on_suggest(
-    event: &Event,
-    renderer: &mut crate::jnv::render::Renderer,
-) -> Result<PromptSignal> {
-    let query_editor_after_mut = renderer.query_editor_snapshot.after_mut();
-    let suggest_after_mut = renderer.suggest_snapshot.after_mut();
-
-    match event {
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('c'),
-            modifiers: KeyModifiers::CONTROL,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        }) => return Ok(PromptSignal::Quit),
-
-        Event::Key(KeyEvent {
-            code: KeyCode::Tab,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        })
-        | Event::Key(KeyEvent {
-            code: KeyCode::Down,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        }) => {
-            suggest_after_mut.listbox.forward();
-            query_editor_after_mut
-                .texteditor
-                .replace(&suggest_after_mut.listbox.get());
-        }
-
-        Event::Key(KeyEvent {
-            code: KeyCode::Up,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        }) => {
-            suggest_after_mut.listbox.backward();
-            query_editor_after_mut
-                .texteditor
-                .replace(&suggest_after_mut.listbox.get());
-        }
-
-        _ => {
-            suggest_after_mut.listbox = Listbox::from_iter(Vec::<String>::new());
-            renderer.keymap.switch("default");
-
-            // This block is specifically designed to prevent the default action of toggling collapse/expand
-            // from being executed when the Enter key is pressed. This is done from the perspective of user
-            // experimentation, ensuring that pressing Enter while in the suggest mode does not trigger
-            // the default behavior associated with the Enter key in the default mode.
-            if let Event::Key(KeyEvent {
-                code: KeyCode::Enter,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::NONE,
-            }) = event
-            {
-            } else {
-                return default(event, renderer);
-            }
-        }
+/// Replaces the query with the completion spliced
+/// between the text around the token, leaving the cursor after the completion.
+fn splice(texteditor: &mut text_editor::TextEditor, around: &(String, String), completion: &str) {
+    let (head, tail) = around;
+    texteditor.replace(&format!("{}{}{}", head, completion, tail));
+    for _ in 0..tail.chars().count() {
+        texteditor.backward();
     }
-    Ok(PromptSignal::Continue)
 }
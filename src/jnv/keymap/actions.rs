@@ -0,0 +1,454 @@
+use std::io;
+
+use promkit::{
+    crossterm::{
+        event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+        execute,
+        style::Color,
+        terminal::{DisableLineWrap, EnableLineWrap},
+    },
+    json::{JsonPath, JsonPathSegment},
+    listbox::Listbox,
+    serde_json, PromptSignal, Result,
+};
+
+use crate::jnv::{
+    analysis::{self, Analysis},
+    export,
+    formats::{self, OutputFormat},
+    run_jq, yank,
+};
+
+/// Handles the keys acting on the query, its results or the interface at once,
+/// leaving the others to the keys switching modes.
+pub fn on_action(
+    event: &Event,
+    renderer: &mut crate::jnv::render::Renderer,
+) -> Result<PromptSignal> {
+    let query_editor_after_mut = renderer.query_editor_snapshot.after_mut();
+
+    match event {
+        // Toggle the histogram of the values produced by the query.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('d'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => toggle_analysis(renderer, Analysis::Histogram),
+
+        // Toggle the summary statistics of the numbers produced by the query.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('s'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => toggle_analysis(renderer, Analysis::Statistics),
+
+        // Yank the value starting at the row of the cursor in the JSON viewer.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('y'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => match yank_value(renderer) {
+            Ok(message) => renderer.show_message(message, Color::Green),
+            Err(e) => renderer.show_message(e.to_string(), Color::Red),
+        },
+
+        // Pass the value at the cursor to the custom command of the hooks script.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('j'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => match run_custom_command(renderer) {
+            Ok(message) => renderer.show_message(message, Color::Green),
+            Err(e) => renderer.show_message(e.to_string(), Color::Red),
+        },
+
+        // Pin the results to compare those of the query with.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('k'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            if let Err(e) = renderer.toggle_pin() {
+                renderer.show_message(e.to_string(), Color::Red);
+            }
+        }
+
+        // Read the input again now.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('r'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => match &renderer.refresh {
+            // The input held back for the edits, loaded once warned about them.
+            Some(refresh) if refresh.held.is_some() => {
+                if let Err(e) = renderer.refresh(true) {
+                    renderer.show_message(format!("Failed to refresh: {}", e), Color::Red);
+                }
+            }
+            Some(refresh) => refresh.force(),
+            None => renderer.show_message("Nothing to refresh: use --refresh", Color::Red),
+        },
+
+        // Pause or resume refreshing the input.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('x'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => match &mut renderer.refresh {
+            Some(refresh) => refresh.toggle_pause(),
+            None => renderer.show_message("Nothing to refresh: use --refresh", Color::Red),
+        },
+
+        // Replace the input with the JSON in the clipboard.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            if renderer.confirm_discard("Pasting the input", "Alt + C") {
+                if let Err(e) = yank::paste_from_clipboard()
+                    .and_then(|input| renderer.replace_input(&input, None))
+                {
+                    renderer.show_message(e.to_string(), Color::Red);
+                }
+            }
+        }
+
+        // Refine the results of the query as the documents, with an empty query.
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let query = query_editor_after_mut
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            match renderer.push(&query, false) {
+                Ok(level) => {
+                    renderer
+                        .query_editor_snapshot
+                        .after_mut()
+                        .texteditor
+                        .erase_all();
+                    renderer.show_message(
+                        format!(
+                            "Refining the results of '{}' (level {}, Alt+Backspace to go back)",
+                            query, level
+                        ),
+                        Color::Green,
+                    );
+                }
+                Err(e) => renderer.show_message(e.to_string(), Color::Red),
+            }
+        }
+
+        // Go back to the documents the last pushed query ran on, with that query.
+        Event::Key(KeyEvent {
+            code: KeyCode::Backspace,
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => match renderer.pop() {
+            Ok(Some(query)) => {
+                renderer
+                    .query_editor_snapshot
+                    .after_mut()
+                    .texteditor
+                    .replace(&query);
+            }
+            Ok(None) => renderer.show_message("No filter to go back from", Color::Red),
+            Err(e) => renderer.show_message(e.to_string(), Color::Red),
+        },
+
+        // Restore the query accepted last time for the input.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('u'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => match &renderer.last_query {
+            Some(query) => query_editor_after_mut.texteditor.replace(query),
+            None => renderer.show_message("No filter was accepted for the input", Color::Red),
+        },
+
+        // Switch to the tab, or open a new one.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(digit @ '1'..='9'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => renderer.switch_tab(*digit as usize - '1' as usize),
+
+        // Show the location of the value at the cursor in the document,
+        // or go back to the query.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('o'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let query = query_editor_after_mut
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            match renderer.origin_query.take() {
+                Some(origin) if query == "." => query_editor_after_mut.texteditor.replace(&origin),
+                _ => match locate_in_document(renderer, &query) {
+                    Ok(path) => {
+                        renderer
+                            .query_editor_snapshot
+                            .after_mut()
+                            .texteditor
+                            .replace(".");
+                        renderer.pending_location = Some(path);
+                        renderer.origin_query = Some(query);
+                    }
+                    Err(e) => renderer.show_message(e.to_string(), Color::Red),
+                },
+            }
+        }
+
+        // Toggle the visibility of panes.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('i'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => renderer.hint_visible = !renderer.hint_visible,
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('l'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => renderer.suggest_visible = !renderer.suggest_visible,
+
+        // Toggle the JSON viewer taking up the whole terminal.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('z'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => renderer.fullscreen = !renderer.fullscreen,
+
+        // Toggle the debug overlay on performance.
+        Event::Key(KeyEvent {
+            code: KeyCode::F(12),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => renderer.hud_visible = !renderer.hud_visible,
+
+        // Toggle wrapping long lines in the JSON viewer.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('w'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            toggle_wrap(renderer)?;
+        }
+
+        // Scroll the JSON viewer horizontally, while long lines don't wrap.
+        Event::Key(KeyEvent {
+            code: code @ (KeyCode::Left | KeyCode::Right),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            if renderer.wrap {
+                renderer.show_message(
+                    "Turn off wrapping with Alt+W to scroll horizontally",
+                    Color::Yellow,
+                );
+            } else {
+                renderer.scroll_horizontally(*code == KeyCode::Right);
+            }
+        }
+
+        // Show a level more or less of the JSON viewer.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('+' | '='),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => renderer.change_depth(true),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('-'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => renderer.change_depth(false),
+
+        // Page through every result of the query, beyond the size shown in the viewer.
+        Event::Key(KeyEvent {
+            code: KeyCode::F(4),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let query = query_editor_after_mut
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            let theme = &renderer.json_bundle_snapshot.after().theme;
+            let format = renderer.output_format;
+            let ascii = renderer.ascii_output;
+            let colored = renderer.colored();
+            if let Err(e) = run_jq(&query, &renderer.documents[renderer.document_position])
+                .and_then(|results| {
+                    if format != OutputFormat::Json {
+                        return renderer.page(format.render(&results, ascii)?);
+                    }
+                    let text = results
+                        .iter()
+                        .map(|result| match serde_json::from_str(result) {
+                            Ok(value) if colored => export::ansi(&value, theme, false),
+                            _ => result.clone(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    if ascii {
+                        return renderer.page(formats::escape_non_ascii(&text) + "\n");
+                    }
+                    renderer.page(text + "\n")
+                })
+            {
+                renderer.show_message(e.to_string(), Color::Red);
+            }
+        }
+
+        _ => return super::modes::on_switch(event, renderer),
+    }
+    Ok(PromptSignal::Continue)
+}
+
+/// Shows the analysis of the values produced by the query in the analysis pane,
+/// or hides the pane if it already shows the same kind of analysis.
+fn toggle_analysis(renderer: &mut crate::jnv::render::Renderer, analysis: Analysis) {
+    if renderer.analysis == Some(analysis) {
+        renderer.analysis = None;
+        renderer.analysis_snapshot.after_mut().listbox = Listbox::from_iter(Vec::<String>::new());
+        return;
+    }
+    let query = renderer
+        .query_editor_snapshot
+        .after()
+        .texteditor
+        .text_without_cursor()
+        .to_string();
+    match run_jq(&query, &renderer.documents[renderer.document_position])
+        .and_then(|results| analysis::values(&results))
+        .and_then(|values| analysis.run(&query, &values))
+    {
+        Ok(lines) => {
+            renderer.analysis = Some(analysis);
+            renderer.analysis_snapshot.after_mut().listbox = Listbox::from_iter(lines);
+        }
+        Err(e) => renderer.show_message(e.to_string(), Color::Red),
+    }
+}
+
+/// Copies the value starting at the row of the cursor in the JSON viewer
+/// to the clipboard, or appends it to the yank file if any.
+fn yank_value(renderer: &crate::jnv::render::Renderer) -> anyhow::Result<String> {
+    let (Some(row), Some(_)) = (renderer.json_row, renderer.json_rows) else {
+        anyhow::bail!("The value at the cursor is unknown after toggling folds");
+    };
+    let value = renderer.value_at(row)?;
+    match &renderer.yank_file {
+        Some(path) => {
+            yank::append_to_file(path, &serde_json::to_string(&value)?)?;
+            Ok(format!("Appended the value to '{}'", path.display()))
+        }
+        None => {
+            renderer.copy_to_clipboard(&serde_json::to_string_pretty(&value)?)?;
+            Ok("Copied the value to the clipboard".to_string())
+        }
+    }
+}
+
+/// Toggles wrapping long lines in the JSON viewer,
+/// which the terminal does unless told otherwise.
+pub(super) fn toggle_wrap(renderer: &mut crate::jnv::render::Renderer) -> io::Result<()> {
+    renderer.wrap = !renderer.wrap;
+    renderer.column = 0;
+    if renderer.headless {
+        return Ok(());
+    }
+    if renderer.wrap {
+        execute!(io::stdout(), EnableLineWrap)
+    } else {
+        execute!(io::stdout(), DisableLineWrap)
+    }
+}
+
+/// Calls the custom command of the hooks script with the value at the cursor,
+/// returning the first line of its output to show.
+fn run_custom_command(renderer: &crate::jnv::render::Renderer) -> anyhow::Result<String> {
+    if renderer.hooks.is_empty() {
+        anyhow::bail!("No hooks script is installed in ~/.config/jnv/plugins/");
+    }
+    let (Some(row), Some(_)) = (renderer.json_row, renderer.json_rows) else {
+        anyhow::bail!("The value at the cursor is unknown after toggling folds");
+    };
+    let value = renderer.value_at(row)?;
+    let query = renderer
+        .query_editor_snapshot
+        .after()
+        .texteditor
+        .text_without_cursor()
+        .to_string();
+    let output = renderer
+        .hooks
+        .run("custom_command", &[&query], serde_json::to_string(&value)?)?;
+    Ok(output
+        .lines()
+        .next()
+        .unwrap_or("The custom command succeeded")
+        .to_string())
+}
+
+/// Returns the path in the document to the value at the cursor of the JSON viewer,
+/// which requires the query to preserve paths (e.g. `.[] | select(.active)`).
+pub(super) fn locate_in_document(
+    renderer: &crate::jnv::render::Renderer,
+    query: &str,
+) -> anyhow::Result<JsonPath> {
+    let (Some(row), Some(_)) = (renderer.json_row, renderer.json_rows) else {
+        anyhow::bail!("The value at the cursor is unknown after toggling folds");
+    };
+    let Some((result, inner)) = renderer.location_at(row) else {
+        anyhow::bail!("No value of the results starts at the row");
+    };
+    let paths = run_jq(
+        &format!("path({})", query),
+        &renderer.documents[renderer.document_position],
+    )
+    .map_err(|_| anyhow::anyhow!("The query does not preserve the paths in the document"))?;
+    if paths.len() != renderer.source_values.len() {
+        anyhow::bail!("The JSON viewer does not show the results of the query");
+    }
+    let mut ret = serde_json::from_str::<Vec<serde_json::Value>>(&paths[result])?
+        .into_iter()
+        .map(|segment| match segment {
+            serde_json::Value::String(key) => Ok(JsonPathSegment::Key(key)),
+            serde_json::Value::Number(index) => index
+                .as_u64()
+                .map(|index| JsonPathSegment::Index(index as usize))
+                .ok_or_else(|| anyhow::anyhow!("Unsupported path segment '{}'", index)),
+            segment => Err(anyhow::anyhow!("Unsupported path segment '{}'", segment)),
+        })
+        .collect::<anyhow::Result<JsonPath>>()?;
+    ret.extend(inner);
+    Ok(ret)
+}
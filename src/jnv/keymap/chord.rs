@@ -0,0 +1,296 @@
+use std::{
+    sync::OnceLock,
+    thread,
+    time::{Duration, Instant},
+};
+
+use promkit::{
+    crossterm::{
+        event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+        style::Color,
+    },
+    PromptSignal, Result,
+};
+use radix_trie::{Trie, TrieCommon};
+
+use crate::jnv::{wake, wizard};
+
+use super::default;
+
+/// How long the key sequence started with Ctrl+X waits for each next key.
+const CHORD_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long a sequence which a longer one starts with (e.g. `z` and `z a`)
+/// waits for the next key before it runs.
+const AMBIGUITY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// The key sequences typed after Ctrl+X, a character for each key,
+/// each standing for the key of an action.
+pub(super) const SEQUENCES: &[(&str, KeyCode, KeyModifiers, &str)] = &[
+    ("gg", KeyCode::Char('l'), KeyModifiers::CONTROL, "top"),
+    ("G", KeyCode::Char('h'), KeyModifiers::CONTROL, "bottom"),
+    ("e", KeyCode::Char('p'), KeyModifiers::CONTROL, "expand"),
+    ("c", KeyCode::Char('n'), KeyModifiers::CONTROL, "collapse"),
+    ("z", KeyCode::Char('z'), KeyModifiers::ALT, "fullscreen"),
+    (
+        "za",
+        KeyCode::Char('t'),
+        KeyModifiers::CONTROL,
+        "toggle fold",
+    ),
+    ("zR", KeyCode::Char('p'), KeyModifiers::CONTROL, "expand"),
+    ("zM", KeyCode::Char('n'), KeyModifiers::CONTROL, "collapse"),
+    (" w", KeyCode::Char('w'), KeyModifiers::ALT, "wrap"),
+    (" h", KeyCode::Char('i'), KeyModifiers::ALT, "hints"),
+    (" l", KeyCode::Char('l'), KeyModifiers::ALT, "suggestions"),
+    (" s", KeyCode::F(2), KeyModifiers::NONE, "settings"),
+    (" c", KeyCode::F(3), KeyModifiers::NONE, "colors"),
+    ("y", KeyCode::Char('y'), KeyModifiers::ALT, "yank"),
+    ("v", KeyCode::Char('v'), KeyModifiers::ALT, "select"),
+    ("t", KeyCode::Char('t'), KeyModifiers::ALT, "tree"),
+    ("k", KeyCode::Char('k'), KeyModifiers::ALT, "pin"),
+    ("f", KeyCode::Char('f'), KeyModifiers::ALT, "files"),
+    ("r", KeyCode::Char('r'), KeyModifiers::CONTROL, "refresh"),
+    ("u", KeyCode::Char('u'), KeyModifiers::ALT, "last filter"),
+    ("/", KeyCode::Char('b'), KeyModifiers::ALT, "key filter"),
+    (":", KeyCode::Char(':'), KeyModifiers::ALT, "jump to row"),
+    ("!", KeyCode::Char('!'), KeyModifiers::ALT, "pipe"),
+    ("h", KeyCode::Char('h'), KeyModifiers::ALT, "jq help"),
+    ("(", KeyCode::Char('('), KeyModifiers::ALT, "wrap"),
+];
+
+/// Wraps the filter in a combinator.
+type Wrap = fn(&str) -> String;
+
+/// The combinators the filter is wrapped in after Alt+(, by their keys.
+/// The cursor is left before a closing parenthesis, to refine the argument.
+pub(super) const WRAPS: &[(char, &str, Wrap)] = &[
+    ('[', "[…]", |query| format!("[{}]", query)),
+    ('m', "map(…)", |query| format!("map({})", query)),
+    ('s', "select(…)", |query| format!("select({})", query)),
+    ('l', "| length", |query| wizard::pipe(query, "length")),
+    ('k', "| keys", |query| wizard::pipe(query, "keys")),
+];
+
+/// The key sequences by their keys, to the positions in `SEQUENCES`.
+fn sequences() -> &'static Trie<String, usize> {
+    static SEQUENCES_TRIE: OnceLock<Trie<String, usize>> = OnceLock::new();
+    SEQUENCES_TRIE.get_or_init(|| {
+        let mut trie = Trie::new();
+        for (i, (keys, _, _, _)) in SEQUENCES.iter().enumerate() {
+            trie.insert(keys.to_string(), i);
+        }
+        trie
+    })
+}
+
+/// Returns the positions of the sequences longer than the keys starting with them.
+fn continuations(keys: &str) -> Vec<usize> {
+    let mut ret = sequences()
+        .get_raw_descendant(keys)
+        .map(|subtrie| {
+            subtrie
+                .iter()
+                .filter(|(sequence, _)| sequence.len() > keys.len() && sequence.starts_with(keys))
+                .map(|(_, i)| *i)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    ret.sort();
+    ret
+}
+
+/// Writes the keys of the sequence as typed (e.g. `Space w`).
+pub(super) fn describe(keys: &str) -> String {
+    keys.chars()
+        .map(|ch| match ch {
+            ' ' => "Space".to_string(),
+            ch => ch.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Lists the sequences with what they stand for, e.g. `g g top, G bottom`.
+pub(super) fn list(positions: impl Iterator<Item = usize>) -> String {
+    positions
+        .map(|i| format!("{} {}", describe(SEQUENCES[i].0), SEQUENCES[i].3))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The keys of a sequence being typed after Ctrl+X.
+pub struct Chord {
+    keys: String,
+    /// When the last key was typed.
+    typed: Instant,
+}
+
+impl Chord {
+    pub fn start() -> Self {
+        Self {
+            keys: String::new(),
+            typed: Instant::now(),
+        }
+    }
+
+    /// Returns the position of the sequence typed, if complete.
+    fn complete(&self) -> Option<usize> {
+        sequences().get(&self.keys).copied()
+    }
+}
+
+pub fn on_chord(
+    event: &Event,
+    renderer: &mut crate::jnv::render::Renderer,
+) -> Result<PromptSignal> {
+    let Some(chord) = renderer.chord.take() else {
+        leave(renderer);
+        return default(event, renderer);
+    };
+    // The sequence is abandoned after a while, and the key acts alone.
+    if chord.typed.elapsed() > CHORD_TIMEOUT {
+        leave(renderer);
+        return default(event, renderer);
+    }
+    // A complete sequence waiting for a longer one runs once it waited long enough,
+    // which it does without the key where the interface isn't woken up (see `on_chord_timeout`).
+    if let Some(i) = chord
+        .complete()
+        .filter(|_| chord.typed.elapsed() >= AMBIGUITY_TIMEOUT)
+    {
+        leave(renderer);
+        return run_then(i, event, renderer);
+    }
+    let Event::Key(KeyEvent {
+        code: KeyCode::Char(ch),
+        modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+        kind: KeyEventKind::Press,
+        ..
+    }) = event
+    else {
+        leave(renderer);
+        return Ok(PromptSignal::Continue);
+    };
+    let keys = format!("{}{}", chord.keys, ch);
+    let longer = continuations(&keys);
+    match (sequences().get(&keys).copied(), longer.is_empty()) {
+        (Some(i), true) => {
+            leave(renderer);
+            run(i, renderer)
+        }
+        (complete, false) => {
+            let mut message = format!("Ctrl+X {} … {}", describe(&keys), list(longer.into_iter()));
+            if let Some(i) = complete {
+                message.push_str(&format!(", or wait for {}", SEQUENCES[i].3));
+                thread::spawn(|| {
+                    thread::sleep(AMBIGUITY_TIMEOUT);
+                    wake::wake();
+                });
+            }
+            renderer.chord = Some(Chord {
+                keys,
+                typed: Instant::now(),
+            });
+            renderer.show_message(message, Color::Green);
+            Ok(PromptSignal::Continue)
+        }
+        (None, true) => {
+            leave(renderer);
+            // The sequence typed so far (e.g. `z`) stands alone, and the key acts alone.
+            if let Some(i) = chord.complete() {
+                return run_then(i, event, renderer);
+            }
+            renderer.show_message(
+                format!("No action for Ctrl+X {}", describe(&keys)),
+                Color::Red,
+            );
+            Ok(PromptSignal::Continue)
+        }
+    }
+}
+
+/// Runs the sequence waiting for a longer one once it waited long enough,
+/// on the wake-up raised for it.
+pub fn on_chord_timeout(renderer: &mut crate::jnv::render::Renderer) -> Result<PromptSignal> {
+    let Some(i) = renderer
+        .chord
+        .as_ref()
+        .filter(|chord| chord.typed.elapsed() >= AMBIGUITY_TIMEOUT)
+        .and_then(Chord::complete)
+    else {
+        return Ok(PromptSignal::Continue);
+    };
+    renderer.chord = None;
+    leave(renderer);
+    run(i, renderer)
+}
+
+fn run(i: usize, renderer: &mut crate::jnv::render::Renderer) -> Result<PromptSignal> {
+    let (_, code, modifiers, _) = SEQUENCES[i];
+    default(&Event::Key(KeyEvent::new(code, modifiers)), renderer)
+}
+
+/// Runs the sequence, and then the key alone.
+fn run_then(
+    i: usize,
+    event: &Event,
+    renderer: &mut crate::jnv::render::Renderer,
+) -> Result<PromptSignal> {
+    match run(i, renderer)? {
+        PromptSignal::Continue => default(event, renderer),
+        signal => Ok(signal),
+    }
+}
+
+fn leave(renderer: &mut crate::jnv::render::Renderer) {
+    renderer.keymap.switch("default");
+    renderer.hint_message_snapshot.reset_after_to_init();
+}
+
+pub fn on_wrap(event: &Event, renderer: &mut crate::jnv::render::Renderer) -> Result<PromptSignal> {
+    renderer.keymap.switch("default");
+    renderer.hint_message_snapshot.reset_after_to_init();
+    let Event::Key(KeyEvent {
+        code: KeyCode::Char(ch),
+        modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+        kind: KeyEventKind::Press,
+        ..
+    }) = event
+    else {
+        return Ok(PromptSignal::Continue);
+    };
+    let Some((_, _, wrap)) = WRAPS.iter().find(|(key, _, _)| key == ch) else {
+        renderer.show_message(format!("No combinator for {}", ch), Color::Red);
+        return Ok(PromptSignal::Continue);
+    };
+    let texteditor = &mut renderer.query_editor_snapshot.after_mut().texteditor;
+    let query = texteditor.text_without_cursor().to_string();
+    let wrapped = wrap(query.trim());
+    texteditor.replace(&wrapped);
+    if wrapped.ends_with(')') {
+        texteditor.backward();
+    }
+    Ok(PromptSignal::Continue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequences_continue_in_the_trie() {
+        assert_eq!(sequences().len(), SEQUENCES.len(), "a sequence is repeated");
+        let keys = |positions: Vec<usize>| {
+            positions
+                .into_iter()
+                .map(|i| SEQUENCES[i].0)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(continuations("g")), vec!["gg"]);
+        assert_eq!(keys(continuations("z")), vec!["za", "zR", "zM"]);
+        assert_eq!(keys(continuations(" ")).len(), 5);
+        assert!(continuations("gg").is_empty());
+        assert_eq!(describe(" w"), "Space w");
+    }
+}
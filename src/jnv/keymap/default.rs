@@ -0,0 +1,248 @@
+use promkit::{
+    crossterm::{
+        event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+        style::Color,
+    },
+    PromptSignal, Result,
+};
+
+use crate::jnv::grapheme;
+
+use super::input_char;
+
+/// Handles the keys editing the query and moving in the JSON viewer,
+/// leaving the others to the actions.
+pub fn default(event: &Event, renderer: &mut crate::jnv::render::Renderer) -> Result<PromptSignal> {
+    let query_editor_after_mut = renderer.query_editor_snapshot.after_mut();
+    let json_bundle_after_mut = renderer.json_bundle_snapshot.after_mut();
+
+    match event {
+        // Complete the path at the cursor.
+        Event::Key(KeyEvent {
+            code: KeyCode::Tab,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            super::suggest::start(renderer);
+        }
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => return Ok(PromptSignal::Quit),
+
+        // Accept the query.
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            if renderer.loading.is_some() {
+                renderer.show_message("The input is still loading", Color::Yellow);
+            } else {
+                renderer.accepted = true;
+                return Ok(PromptSignal::Quit);
+            }
+        }
+
+        // Move cursor.
+        Event::Key(KeyEvent {
+            code: KeyCode::Left,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            grapheme::backward(&mut query_editor_after_mut.texteditor);
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Right,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            grapheme::forward(&mut query_editor_after_mut.texteditor);
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('a'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => query_editor_after_mut.texteditor.move_to_head(),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('e'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => query_editor_after_mut.texteditor.move_to_tail(),
+
+        // Erase char(s).
+        Event::Key(KeyEvent {
+            code: KeyCode::Backspace,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => grapheme::erase(&mut query_editor_after_mut.texteditor),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('u'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => query_editor_after_mut.texteditor.erase_all(),
+
+        // Move up.
+        Event::Key(KeyEvent {
+            code: KeyCode::Up,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Char('k'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let moved = json_bundle_after_mut.bundle.backward();
+            if moved {
+                renderer.json_row = renderer.json_row.map(|row| row.saturating_sub(1));
+            }
+        }
+
+        // Move down.
+        Event::Key(KeyEvent {
+            code: KeyCode::Down,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Char('j'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let moved = json_bundle_after_mut.bundle.forward();
+            if moved {
+                renderer.json_row = renderer.json_row.map(|row| row + 1);
+            }
+        }
+
+        // Move to tail
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('h'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            json_bundle_after_mut.bundle.move_to_tail();
+            renderer.json_row = renderer.json_rows.map(|rows| rows.saturating_sub(1));
+        }
+
+        // Move to head
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('l'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            json_bundle_after_mut.bundle.move_to_head();
+            renderer.json_row = Some(0);
+        }
+
+        // Move to next document
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('n'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) if renderer.document_position + 1 < renderer.documents.len() => {
+            renderer.document_position += 1;
+        }
+
+        // Move to previous document
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('p'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.document_position = renderer.document_position.saturating_sub(1);
+        }
+
+        // Toggle collapse/expand
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('t'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            json_bundle_after_mut.bundle.toggle();
+            renderer.json_rows = None;
+        }
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('p'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            json_bundle_after_mut.bundle.expand_all();
+            renderer.json_row = None;
+            renderer.json_rows = None;
+        }
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('n'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            json_bundle_after_mut.bundle.collapse_all();
+            renderer.json_row = None;
+            renderer.json_rows = None;
+        }
+
+        // Input char.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(ch),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Char(ch),
+            modifiers: KeyModifiers::SHIFT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            // Terminals deliver the text committed by an IME (e.g. for Japanese,
+            // Chinese or Korean) as a burst of key events, which the evaluator takes
+            // from the queue one by one and evaluates the query once for.
+            input_char(query_editor_after_mut, *ch);
+        }
+
+        // Paste text at once, since it may contain chars bound to other actions (e.g. Tab).
+        Event::Paste(text) => {
+            for ch in text.chars() {
+                if ch.is_whitespace() {
+                    input_char(query_editor_after_mut, ' ');
+                } else if !ch.is_control() {
+                    input_char(query_editor_after_mut, ch);
+                }
+            }
+        }
+
+        _ => return super::actions::on_action(event, renderer),
+    }
+    Ok(PromptSignal::Continue)
+}
@@ -0,0 +1,78 @@
+use promkit::{
+    crossterm::{
+        event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+        style::Color,
+    },
+    serde_json, PromptSignal, Result,
+};
+
+use crate::jnv::{edit::Edit, render::EditorMode};
+
+use super::{default, is_text_input};
+
+pub fn on_edit(event: &Event, renderer: &mut crate::jnv::render::Renderer) -> Result<PromptSignal> {
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => return Ok(PromptSignal::Quit),
+
+        // Apply the edit.
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let Some(EditorMode::Edit(editing)) = &renderer.editor_mode else {
+                return Ok(PromptSignal::Continue);
+            };
+            let path = editing.path.clone();
+            let text = renderer
+                .query_editor_snapshot
+                .after()
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            let value = match serde_json::from_str::<serde_json::Value>(&text) {
+                Ok(value) if !value.is_object() && !value.is_array() => value,
+                _ => {
+                    renderer
+                        .show_message(format!("'{}' is not a scalar JSON value", text), Color::Red);
+                    return Ok(PromptSignal::Continue);
+                }
+            };
+            let edit = Edit {
+                document: renderer.document_position,
+                path,
+                value,
+            };
+            renderer.leave_mode();
+            match edit.apply(&renderer.documents[edit.document]) {
+                Ok(document) => {
+                    renderer.documents[edit.document] = document;
+                    renderer.edits.push(edit);
+                }
+                Err(e) => renderer.show_message(e.to_string(), Color::Red),
+            }
+        }
+
+        // Cancel the edit.
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.leave_mode();
+        }
+
+        // Edit the value in the query editor.
+        _ if is_text_input(event) => return default(event, renderer),
+
+        _ => (),
+    }
+    Ok(PromptSignal::Continue)
+}
@@ -0,0 +1,119 @@
+use std::{fs, path::Path};
+
+use promkit::{
+    crossterm::{
+        event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+        style::Color,
+    },
+    PromptSignal, Result,
+};
+
+use crate::jnv::{export, formats::OutputFormat, render::EditorMode, run_jq, yank};
+
+use super::{default, is_text_input};
+
+pub fn on_export(
+    event: &Event,
+    renderer: &mut crate::jnv::render::Renderer,
+) -> Result<PromptSignal> {
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => return Ok(PromptSignal::Quit),
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let path = renderer
+                .query_editor_snapshot
+                .after()
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            let path = path.trim();
+            let query = close_export(renderer)
+                .map(|mode| mode.query().to_string())
+                .unwrap_or_default();
+            let extension = Path::new(path)
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_lowercase());
+            let is_markdown = path.is_empty() || extension.as_deref() == Some("md");
+            let format = extension.as_deref().and_then(OutputFormat::from_extension);
+            let ret = if let Some(format) = format {
+                // Like the output on accept, the results of the filter in the format.
+                run_jq(&query, &renderer.documents[renderer.document_position]).and_then(
+                    |results| {
+                        fs::write(path, format.render(&results, renderer.ascii_output)?)?;
+                        Ok(format!(
+                            "Saved the results as {} to {}",
+                            format.name(),
+                            path
+                        ))
+                    },
+                )
+            } else if is_markdown {
+                // The report shows the filter with its results, regardless of the view.
+                run_jq(&query, &renderer.documents[renderer.document_position]).and_then(
+                    |results| {
+                        let report = export::markdown(&renderer.pipeline(&query), &results);
+                        if path.is_empty() {
+                            renderer.copy_to_clipboard(&report)?;
+                            Ok("Copied the Markdown report of the filter".to_string())
+                        } else {
+                            fs::write(path, report)?;
+                            Ok(format!(
+                                "Saved the Markdown report of the filter to {}",
+                                path
+                            ))
+                        }
+                    },
+                )
+            } else {
+                let values = renderer
+                    .json_nodes
+                    .iter()
+                    .map(yank::to_value)
+                    .collect::<Vec<_>>();
+                let theme = &renderer.json_bundle_snapshot.after().theme;
+                export::save(
+                    Path::new(path),
+                    &values,
+                    theme,
+                    renderer.colored(),
+                    renderer.ascii_output,
+                )
+                .map(|()| format!("Exported the view to {}", path))
+            };
+            match ret {
+                Ok(message) => renderer.show_message(message, Color::Green),
+                Err(e) => renderer.show_message(format!("Failed to export: {}", e), Color::Red),
+            }
+        }
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            close_export(renderer);
+        }
+
+        _ if is_text_input(event) => return default(event, renderer),
+
+        _ => (),
+    }
+    Ok(PromptSignal::Continue)
+}
+
+/// Restores the query and returns the mode left.
+fn close_export(renderer: &mut crate::jnv::render::Renderer) -> Option<EditorMode> {
+    renderer.hint_message_snapshot.reset_after_to_init();
+    renderer.leave_mode()
+}
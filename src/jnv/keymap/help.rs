@@ -0,0 +1,111 @@
+use promkit::{
+    crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+    listbox::Listbox,
+    PromptSignal, Result,
+};
+
+use crate::jnv::{builtins, render::EditorMode};
+
+use super::{default, is_text_input, splice};
+
+pub fn on_help(event: &Event, renderer: &mut crate::jnv::render::Renderer) -> Result<PromptSignal> {
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => return Ok(PromptSignal::Quit),
+
+        // Put the name of the highlighted builtin in place of the one under the cursor.
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let help = renderer.help_snapshot.after();
+            if help.listbox.items().is_empty() {
+                return Ok(PromptSignal::Continue);
+            }
+            let line = help.listbox.get().to_string();
+            let name = line
+                .split(|ch: char| ch == '(' || ch.is_whitespace())
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            if let Some(EditorMode::Help { query, cursor }) = close_help(renderer) {
+                let (start, end) = builtins::name_at(&query, cursor);
+                let chars = query.chars().collect::<Vec<_>>();
+                let around = (
+                    chars[..start].iter().collect(),
+                    chars[end..].iter().collect(),
+                );
+                splice(
+                    &mut renderer.query_editor_snapshot.after_mut().texteditor,
+                    &around,
+                    &name,
+                );
+            }
+        }
+
+        // Close and restore the query.
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            close_help(renderer);
+        }
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Down | KeyCode::Tab,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.help_snapshot.after_mut().listbox.forward();
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Up,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.help_snapshot.after_mut().listbox.backward();
+        }
+
+        // Search the builtins in the query editor.
+        _ if is_text_input(event) => {
+            let signal = default(event, renderer)?;
+            filter_builtins(renderer);
+            return Ok(signal);
+        }
+
+        _ => (),
+    }
+    Ok(PromptSignal::Continue)
+}
+
+/// Lists the jq builtins matching the text in the query editor.
+pub(super) fn filter_builtins(renderer: &mut crate::jnv::render::Renderer) {
+    let pattern = renderer
+        .query_editor_snapshot
+        .after()
+        .texteditor
+        .text_without_cursor()
+        .to_string();
+    let lines = builtins::search(pattern.trim())
+        .into_iter()
+        .map(|builtin| builtin.line())
+        .collect::<Vec<_>>();
+    renderer.help_snapshot.after_mut().listbox = Listbox::from_iter(lines);
+}
+
+/// Closes the help pane, restoring the query and the cursor, and returns the mode left.
+fn close_help(renderer: &mut crate::jnv::render::Renderer) -> Option<EditorMode> {
+    renderer.help_snapshot.after_mut().listbox = Listbox::from_iter(Vec::<String>::new());
+    renderer.hint_message_snapshot.reset_after_to_init();
+    renderer.leave_mode()
+}
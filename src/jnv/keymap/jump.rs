@@ -0,0 +1,160 @@
+use promkit::{
+    crossterm::{
+        event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+        style::Color,
+    },
+    json::JsonPathSegment,
+    PromptSignal, Result,
+};
+
+use crate::jnv::{navigate::key_segment, render::EditorMode};
+
+use super::{actions::locate_in_document, default, is_text_input};
+
+pub fn on_jump(event: &Event, renderer: &mut crate::jnv::render::Renderer) -> Result<PromptSignal> {
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => return Ok(PromptSignal::Quit),
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let input = renderer
+                .query_editor_snapshot
+                .after()
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            if let Some(path) = input
+                .trim()
+                .strip_prefix("root")
+                .filter(|path| path.is_empty() || path.starts_with(' '))
+            {
+                match set_root(renderer, path.trim()) {
+                    Ok(path) => renderer.show_message(
+                        format!(
+                            "Querying under {} of every document (Alt+Backspace to go back)",
+                            path
+                        ),
+                        Color::Green,
+                    ),
+                    Err(e) => renderer.show_message(e.to_string(), Color::Red),
+                }
+                return Ok(PromptSignal::Continue);
+            }
+            let rows = renderer.json_rows.unwrap_or_default();
+            match parse_row(&input, rows) {
+                Some(row) => {
+                    close_jump(renderer);
+                    renderer.move_json_cursor(row);
+                }
+                None => renderer.show_message(
+                    format!(
+                        "'{}' is neither a row from 1 to {} nor a percentage",
+                        input, rows
+                    ),
+                    Color::Red,
+                ),
+            }
+        }
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            close_jump(renderer);
+        }
+
+        _ if is_text_input(event) => return default(event, renderer),
+
+        _ => (),
+    }
+    Ok(PromptSignal::Continue)
+}
+
+fn close_jump(renderer: &mut crate::jnv::render::Renderer) {
+    renderer.leave_mode();
+    renderer.hint_message_snapshot.reset_after_to_init();
+}
+
+/// Makes the values at the path, or at the cursor of the JSON viewer without a path,
+/// the documents to query, clearing the query. Returns the path.
+fn set_root(renderer: &mut crate::jnv::render::Renderer, path: &str) -> anyhow::Result<String> {
+    let path = if path.is_empty() {
+        let query = match &renderer.editor_mode {
+            Some(EditorMode::Jump { query }) => query.clone(),
+            _ => String::new(),
+        };
+        let segments = locate_in_document(renderer, &query)?
+            .into_iter()
+            .map(|segment| match segment {
+                JsonPathSegment::Key(key) => key_segment(&key),
+                JsonPathSegment::Index(index) => format!("[{}]", index),
+            })
+            .collect::<String>();
+        if segments.is_empty() {
+            ".".to_string()
+        } else {
+            segments
+        }
+    } else {
+        path.to_string()
+    };
+    renderer.push(&path, true)?;
+    renderer.leave_mode();
+    renderer
+        .query_editor_snapshot
+        .after_mut()
+        .texteditor
+        .erase_all();
+    Ok(path)
+}
+
+/// Parses the row to jump to, given as a row number from 1
+/// or a percentage of the rows (e.g. `50%`), into the index of the row.
+fn parse_row(input: &str, rows: usize) -> Option<usize> {
+    let input = input.trim();
+    let row = match input.strip_suffix('%') {
+        Some(percent) => {
+            let percent = percent.trim().parse::<f64>().ok()?;
+            if !(0.0..=100.0).contains(&percent) {
+                return None;
+            }
+            (rows as f64 * percent / 100.0).ceil() as usize
+        }
+        None => input.parse::<usize>().ok().filter(|row| *row <= rows)?,
+    };
+    (rows > 0).then(|| row.max(1) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_row_reads_row_numbers_from_1() {
+        assert_eq!(parse_row("1", 10), Some(0));
+        assert_eq!(parse_row(" 10 ", 10), Some(9));
+        assert_eq!(parse_row("0", 10), Some(0));
+        assert_eq!(parse_row("11", 10), None);
+        assert_eq!(parse_row("x", 10), None);
+    }
+
+    #[test]
+    fn parse_row_reads_percentages_of_the_rows() {
+        assert_eq!(parse_row("50%", 10), Some(4));
+        assert_eq!(parse_row("100%", 10), Some(9));
+        assert_eq!(parse_row("0%", 10), Some(0));
+        assert_eq!(parse_row("101%", 10), None);
+        assert_eq!(parse_row("50%", 0), None);
+    }
+}
@@ -0,0 +1,64 @@
+use promkit::{
+    crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+    PromptSignal, Result,
+};
+
+use super::{default, is_text_input};
+
+pub fn on_key_filter(
+    event: &Event,
+    renderer: &mut crate::jnv::render::Renderer,
+) -> Result<PromptSignal> {
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => return Ok(PromptSignal::Quit),
+
+        // Keep the filter and restore the query.
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            close_key_filter(renderer);
+        }
+
+        // Clear the filter and restore the query.
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.key_filter = None;
+            renderer.reshow();
+            close_key_filter(renderer);
+        }
+
+        // Filter the keys as the pattern is typed.
+        _ if is_text_input(event) => {
+            let signal = default(event, renderer)?;
+            let pattern = renderer
+                .query_editor_snapshot
+                .after()
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            renderer.key_filter = (!pattern.is_empty()).then_some(pattern);
+            renderer.reshow();
+            return Ok(signal);
+        }
+
+        _ => (),
+    }
+    Ok(PromptSignal::Continue)
+}
+
+fn close_key_filter(renderer: &mut crate::jnv::render::Renderer) {
+    renderer.leave_mode();
+    renderer.hint_message_snapshot.reset_after_to_init();
+}
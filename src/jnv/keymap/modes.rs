@@ -0,0 +1,360 @@
+use promkit::{
+    crossterm::{
+        event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+        style::Color,
+    },
+    PromptSignal, Result,
+};
+
+use crate::jnv::{
+    builtins,
+    edit::Editing,
+    macros::MacroAction,
+    marks::MarkAction,
+    navigate::Navigator,
+    render::EditorMode,
+    wizard::{GroupBy, Replace, Wizard},
+};
+
+use super::{
+    chord::{self, Chord, SEQUENCES, WRAPS},
+    help::filter_builtins,
+    pick::filter_files,
+};
+
+/// Handles the keys switching to another mode (e.g. editing a value or picking a file).
+pub fn on_switch(
+    event: &Event,
+    renderer: &mut crate::jnv::render::Renderer,
+) -> Result<PromptSignal> {
+    let query_editor_after_mut = renderer.query_editor_snapshot.after_mut();
+
+    match event {
+        // Edit the value selected by the query.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('e'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let query = query_editor_after_mut
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            match Editing::start(&query, &renderer.documents[renderer.document_position]) {
+                Ok(editing) => {
+                    let value = editing.value.to_string();
+                    renderer.enter_mode(EditorMode::Edit(editing), &value);
+                    renderer.show_message(
+                        "Editing the value: Enter to apply, Esc to cancel",
+                        Color::Green,
+                    );
+                }
+                Err(e) => renderer.show_message(e.to_string(), Color::Red),
+            }
+        }
+
+        // Build a group_by filter step by step.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('g'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let query = query_editor_after_mut
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            start_wizard(renderer, Wizard::GroupBy(GroupBy::new(&query)));
+        }
+
+        // Build a filter replacing the matches of a regex in strings step by step.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('r'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let query = query_editor_after_mut
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            start_wizard(renderer, Wizard::Replace(Replace::new(&query)));
+        }
+
+        // Select rows of the JSON viewer.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('v'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => match (renderer.json_row, renderer.json_rows) {
+            (Some(row), Some(_)) => {
+                renderer.visual_anchor = Some(row);
+                renderer.keymap.switch("on_visual");
+                renderer.show_message(
+                    "Selecting rows: ↑/↓ to extend, Enter to copy, Esc to cancel",
+                    Color::Green,
+                );
+            }
+            _ => renderer.show_message("The rows are unknown after toggling folds", Color::Red),
+        },
+
+        // Show only the keys matching the pattern typed in the query editor.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('b'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let query = query_editor_after_mut
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            let pattern = renderer.key_filter.clone().unwrap_or_default();
+            renderer.enter_mode(EditorMode::KeyFilter { query }, &pattern);
+            renderer.show_message(
+                "Filter keys: type a part of the key names, Enter to keep, Esc to clear",
+                Color::Green,
+            );
+        }
+
+        // Jump to the row or the percentage of the rows typed in the query editor.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(':'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let query = query_editor_after_mut
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            renderer.enter_mode(EditorMode::Jump { query }, "");
+            renderer.show_message(
+                "Jump to row: type a row number (e.g. 1200) or a percentage (e.g. 50%), or root and a path (e.g. root .data) to query under it, the value at the cursor without a path, Enter to jump, Esc to cancel",
+                Color::Green,
+            );
+        }
+
+        // Wrap the query in a combinator chosen by the next key.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('('),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.keymap.switch("on_wrap");
+            let keys = WRAPS
+                .iter()
+                .map(|(key, name, _)| format!("{} {}", key, name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            renderer.show_message(format!("Wrap the filter in: {}", keys), Color::Green);
+        }
+
+        // Pipe the results of the query into the shell command typed in the query editor.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('!'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let query = query_editor_after_mut
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            renderer.enter_mode(EditorMode::Pipe { query }, "");
+            renderer.show_message(
+                "Pipe the results into: type a shell command (e.g. wc -l), Enter to run, Esc to cancel",
+                Color::Green,
+            );
+        }
+
+        // Search the jq builtins, starting with the function name under the cursor.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('h'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let query = query_editor_after_mut
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            let cursor = query_editor_after_mut.texteditor.position();
+            let (start, end) = builtins::name_at(&query, cursor);
+            let name = query
+                .chars()
+                .skip(start)
+                .take(end - start)
+                .collect::<String>();
+            renderer.enter_mode(EditorMode::Help { query, cursor }, &name);
+            filter_builtins(renderer);
+            renderer.show_message(
+                "jq builtins: type to search, Enter to insert the name, Esc to close",
+                Color::Green,
+            );
+        }
+
+        // Pick a file of the workspace as the input.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('f'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            if renderer.workspace.is_empty() {
+                renderer.show_message("No files to pick: pass a directory to jnv", Color::Red);
+            } else {
+                let query = query_editor_after_mut
+                    .texteditor
+                    .text_without_cursor()
+                    .to_string();
+                renderer.enter_mode(EditorMode::Pick { query }, "");
+                filter_files(renderer);
+                renderer.show_message(
+                    "Pick a file: type to filter, Enter to load, Esc to cancel",
+                    Color::Green,
+                );
+            }
+        }
+
+        // Mark the row of the cursor, or jump to a mark, named by the next key.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('m'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.mark_action = Some(MarkAction::Set);
+            renderer.keymap.switch("on_mark");
+            renderer.show_message("Mark the row as (a-z):", Color::Green);
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('\''),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.mark_action = Some(MarkAction::Jump);
+            renderer.keymap.switch("on_mark");
+            renderer.show_message("Jump to the mark (a-z):", Color::Green);
+        }
+
+        // Record the keys into a register, or stop recording.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('q'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => match renderer.macros.stop() {
+            Some((name, count)) => renderer.show_message(
+                format!("Recorded {} keys into '{}'", count, name),
+                Color::Green,
+            ),
+            None => {
+                renderer.macro_action = Some(MacroAction::Record);
+                renderer.keymap.switch("on_macro");
+                renderer.show_message("Record the keys into (a-z):", Color::Green);
+            }
+        },
+        // Play the keys recorded into a register.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('a'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.macro_action = Some(MacroAction::Play);
+            renderer.keymap.switch("on_macro");
+            renderer.show_message("Play the keys recorded into (a-z):", Color::Green);
+        }
+
+        // Start a key sequence for the actions beyond the single keys.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('x'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.chord = Some(Chord::start());
+            renderer.keymap.switch("on_chord");
+            let keys = chord::list(0..SEQUENCES.len());
+            renderer.show_message(format!("Ctrl+X … {}", keys), Color::Green);
+        }
+
+        // Walk the tree of the document with the arrow keys.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('t'),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let query = query_editor_after_mut
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            match Navigator::start(&query, &renderer.documents[renderer.document_position]) {
+                Ok(navigator) => {
+                    query_editor_after_mut
+                        .texteditor
+                        .replace(&navigator.query_path());
+                    renderer.navigator = Some(navigator);
+                    renderer.keymap.switch("on_navigate");
+                }
+                Err(e) => renderer.show_message(e.to_string(), Color::Red),
+            }
+        }
+
+        // Show the overlay of display options.
+        Event::Key(KeyEvent {
+            code: KeyCode::F(2),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.settings_visible = true;
+            renderer.update_settings();
+            renderer.keymap.switch("on_settings");
+        }
+
+        // Show the overlay of the colors of the JSON viewer.
+        Event::Key(KeyEvent {
+            code: KeyCode::F(3),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.theme_visible = true;
+            renderer.update_theme();
+            renderer.keymap.switch("on_theme");
+        }
+
+        // Export the view to the file typed in the query editor.
+        Event::Key(KeyEvent {
+            code: KeyCode::F(5),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let query = query_editor_after_mut
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            renderer.enter_mode(EditorMode::Export { query }, "");
+            renderer.show_message(
+                "Export to: type a file path (.html page, .md report, .json, .yaml, .toml, .gron or .jsonl results, ANSI colored text otherwise) or nothing to copy a Markdown report, Enter to save, Esc to cancel",
+                Color::Green,
+            );
+        }
+
+        _ => (),
+    }
+    Ok(PromptSignal::Continue)
+}
+
+fn start_wizard(renderer: &mut crate::jnv::render::Renderer, wizard: Wizard) {
+    renderer.show_message(wizard.question(), Color::Green);
+    renderer.enter_mode(EditorMode::Wizard(wizard), "");
+}
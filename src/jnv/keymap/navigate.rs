@@ -0,0 +1,94 @@
+use promkit::{
+    crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+    PromptSignal, Result,
+};
+
+pub fn on_navigate(
+    event: &Event,
+    renderer: &mut crate::jnv::render::Renderer,
+) -> Result<PromptSignal> {
+    let Some(navigator) = renderer.navigator.as_mut() else {
+        renderer.keymap.switch("default");
+        return Ok(PromptSignal::Continue);
+    };
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => return Ok(PromptSignal::Quit),
+
+        // Apply the path at the cursor.
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.navigator = None;
+            renderer.keymap.switch("default");
+            return Ok(PromptSignal::Continue);
+        }
+
+        // Cancel and restore the query.
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let query = navigator.query.clone();
+            renderer
+                .query_editor_snapshot
+                .after_mut()
+                .texteditor
+                .replace(&query);
+            renderer.navigator = None;
+            renderer.keymap.switch("default");
+            return Ok(PromptSignal::Continue);
+        }
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Right,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            navigator.child();
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Left,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            navigator.parent();
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Down,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            navigator.sibling(true);
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Up,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            navigator.sibling(false);
+        }
+
+        _ => return Ok(PromptSignal::Continue),
+    }
+    let path = navigator.query_path();
+    renderer
+        .query_editor_snapshot
+        .after_mut()
+        .texteditor
+        .replace(&path);
+    Ok(PromptSignal::Continue)
+}
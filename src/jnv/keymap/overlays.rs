@@ -0,0 +1,172 @@
+use promkit::{
+    crossterm::{
+        event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+        style::Color,
+    },
+    PromptSignal, Result,
+};
+
+use crate::jnv::{
+    settings::{Setting, SETTINGS},
+    theme::ELEMENTS,
+};
+
+use super::actions::toggle_wrap;
+
+pub fn on_settings(
+    event: &Event,
+    renderer: &mut crate::jnv::render::Renderer,
+) -> Result<PromptSignal> {
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => return Ok(PromptSignal::Quit),
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Down | KeyCode::Tab,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.settings_snapshot.after_mut().listbox.forward();
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Up,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.settings_snapshot.after_mut().listbox.backward();
+        }
+
+        // Flip the highlighted option, or cycle through its levels.
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter | KeyCode::Char(' '),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let position = renderer.settings_snapshot.after().listbox.position();
+            match SETTINGS[position] {
+                Setting::Wrap => toggle_wrap(renderer)?,
+                Setting::LineNumbers => renderer.line_numbers = !renderer.line_numbers,
+                Setting::Hints => renderer.hint_visible = !renderer.hint_visible,
+                Setting::Verbosity => {
+                    renderer.verbosity = renderer.verbosity.next();
+                    renderer.stale = true;
+                }
+                Setting::Suggestions => renderer.suggest_visible = !renderer.suggest_visible,
+                Setting::Fullscreen => renderer.fullscreen = !renderer.fullscreen,
+                Setting::SortKeys => {
+                    renderer.sort_keys = !renderer.sort_keys;
+                    renderer.stale = true;
+                }
+                Setting::HideEmpty => {
+                    renderer.hide_empty = !renderer.hide_empty;
+                    renderer.stale = true;
+                }
+                Setting::SampleArrays => {
+                    renderer.sample_arrays = !renderer.sample_arrays;
+                    renderer.stale = true;
+                }
+                Setting::HighlightChanges => {
+                    renderer.highlight_changes = !renderer.highlight_changes;
+                    renderer.changes = None;
+                }
+                Setting::RawOutput => renderer.raw_output = !renderer.raw_output,
+                Setting::CompactOutput => renderer.compact_output = !renderer.compact_output,
+                Setting::AsciiOutput => renderer.ascii_output = !renderer.ascii_output,
+                Setting::OutputFormat => renderer.output_format = renderer.output_format.next(),
+            }
+            renderer.update_settings();
+        }
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc | KeyCode::F(2),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.settings_visible = false;
+            renderer.keymap.switch("default");
+        }
+
+        _ => (),
+    }
+    Ok(PromptSignal::Continue)
+}
+
+pub fn on_theme(
+    event: &Event,
+    renderer: &mut crate::jnv::render::Renderer,
+) -> Result<PromptSignal> {
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => return Ok(PromptSignal::Quit),
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Down | KeyCode::Tab,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.theme_snapshot.after_mut().listbox.forward();
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Up,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.theme_snapshot.after_mut().listbox.backward();
+        }
+
+        // Change the color of the highlighted element.
+        Event::Key(KeyEvent {
+            code: code @ (KeyCode::Right | KeyCode::Left | KeyCode::Enter),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let position = renderer.theme_snapshot.after().listbox.position();
+            renderer
+                .theme
+                .cycle(ELEMENTS[position], *code != KeyCode::Left);
+            renderer.update_theme();
+        }
+
+        // Save the colors to the theme file.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('s'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => match renderer.theme.save() {
+            Ok(path) => {
+                let message = format!("Saved the theme to {}", path.display());
+                renderer.show_message(message, Color::Green);
+            }
+            Err(e) => renderer.show_message(format!("Failed to save the theme: {}", e), Color::Red),
+        },
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc | KeyCode::F(3),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.theme_visible = false;
+            renderer.keymap.switch("default");
+        }
+
+        _ => (),
+    }
+    Ok(PromptSignal::Continue)
+}
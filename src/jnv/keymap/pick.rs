@@ -0,0 +1,105 @@
+use std::path::PathBuf;
+
+use promkit::{
+    crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+    listbox::Listbox,
+    PromptSignal, Result,
+};
+
+use super::{default, is_text_input};
+
+pub fn on_pick(event: &Event, renderer: &mut crate::jnv::render::Renderer) -> Result<PromptSignal> {
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => return Ok(PromptSignal::Quit),
+
+        // Load the highlighted file.
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let picker = renderer.picker_snapshot.after();
+            if picker.listbox.items().is_empty() {
+                return Ok(PromptSignal::Continue);
+            }
+            let path = PathBuf::from(picker.listbox.get());
+            if !renderer.confirm_discard(&format!("Loading '{}'", path.display()), "Enter") {
+                return Ok(PromptSignal::Continue);
+            }
+            // Shown once read and parsed, the current input staying shown meanwhile.
+            renderer.load(&path);
+            close_picker(renderer);
+        }
+
+        // Cancel and restore the query.
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            close_picker(renderer);
+            renderer.hint_message_snapshot.reset_after_to_init();
+        }
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Down | KeyCode::Tab,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.picker_snapshot.after_mut().listbox.forward();
+        }
+        Event::Key(KeyEvent {
+            code: KeyCode::Up,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.picker_snapshot.after_mut().listbox.backward();
+        }
+
+        // Filter the files in the query editor.
+        _ if is_text_input(event) => {
+            let signal = default(event, renderer)?;
+            filter_files(renderer);
+            return Ok(signal);
+        }
+
+        _ => (),
+    }
+    Ok(PromptSignal::Continue)
+}
+
+/// Lists the files of the workspace matching the text in the query editor,
+/// whose characters must appear in the path in order (e.g. `usrjs` for `users.json`).
+pub(super) fn filter_files(renderer: &mut crate::jnv::render::Renderer) {
+    let pattern = renderer
+        .query_editor_snapshot
+        .after()
+        .texteditor
+        .text_without_cursor()
+        .to_string()
+        .to_lowercase();
+    let files = renderer
+        .workspace
+        .iter()
+        .map(|path| path.display().to_string())
+        .filter(|path| {
+            let mut chars = path.to_lowercase().chars().collect::<Vec<_>>().into_iter();
+            pattern.chars().all(|ch| chars.any(|c| c == ch))
+        })
+        .collect::<Vec<_>>();
+    renderer.picker_snapshot.after_mut().listbox = Listbox::from_iter(files);
+}
+
+fn close_picker(renderer: &mut crate::jnv::render::Renderer) {
+    renderer.leave_mode();
+    renderer.picker_snapshot.after_mut().listbox = Listbox::from_iter(Vec::<String>::new());
+}
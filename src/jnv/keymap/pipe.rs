@@ -0,0 +1,68 @@
+use promkit::{
+    crossterm::{
+        event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+        style::Color,
+    },
+    listbox::Listbox,
+    PromptSignal, Result,
+};
+
+use crate::jnv::{pipe, run_jq};
+
+use super::{default, is_text_input};
+
+pub fn on_pipe(event: &Event, renderer: &mut crate::jnv::render::Renderer) -> Result<PromptSignal> {
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => return Ok(PromptSignal::Quit),
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let command = renderer
+                .query_editor_snapshot
+                .after()
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            if command.trim().is_empty() {
+                return Ok(PromptSignal::Continue);
+            }
+            let query = renderer
+                .leave_mode()
+                .map(|mode| mode.query().to_string())
+                .unwrap_or_default();
+            renderer.hint_message_snapshot.reset_after_to_init();
+            match run_jq(&query, &renderer.documents[renderer.document_position])
+                .and_then(|results| pipe::run(&command, results.join("\n") + "\n"))
+            {
+                Ok(lines) => {
+                    renderer.scratch_snapshot.after_mut().listbox = Listbox::from_iter(lines)
+                }
+                Err(e) => renderer.show_message(e.to_string(), Color::Red),
+            }
+        }
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.leave_mode();
+            renderer.hint_message_snapshot.reset_after_to_init();
+        }
+
+        _ if is_text_input(event) => return default(event, renderer),
+
+        _ => (),
+    }
+    Ok(PromptSignal::Continue)
+}
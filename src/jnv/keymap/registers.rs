@@ -0,0 +1,82 @@
+use promkit::{
+    crossterm::{
+        event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+        style::Color,
+    },
+    PromptSignal, Result,
+};
+
+use crate::jnv::{macros::MacroAction, marks::MarkAction};
+
+pub fn on_mark(event: &Event, renderer: &mut crate::jnv::render::Renderer) -> Result<PromptSignal> {
+    let action = renderer.mark_action.take();
+    renderer.keymap.switch("default");
+    renderer.hint_message_snapshot.reset_after_to_init();
+
+    let (
+        Some(action),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(name @ 'a'..='z'),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }),
+    ) = (action, event)
+    else {
+        return Ok(PromptSignal::Continue);
+    };
+    let (Some(row), Some(rows)) = (renderer.json_row, renderer.json_rows) else {
+        renderer.show_message("The rows are unknown after toggling folds", Color::Red);
+        return Ok(PromptSignal::Continue);
+    };
+    match action {
+        MarkAction::Set => {
+            let location = renderer.location_at(row);
+            renderer.marks.set(*name, row, location);
+            renderer.show_message(format!("Marked the row as '{}'", name), Color::Green);
+        }
+        MarkAction::Jump => {
+            match renderer
+                .marks
+                .get(*name, |location| renderer.row_of(location), rows)
+            {
+                Some(mark) => renderer.move_json_cursor(mark),
+                None => {
+                    renderer.show_message(format!("No mark '{}' in the rows", name), Color::Red)
+                }
+            }
+        }
+    }
+    Ok(PromptSignal::Continue)
+}
+
+pub fn on_macro(
+    event: &Event,
+    renderer: &mut crate::jnv::render::Renderer,
+) -> Result<PromptSignal> {
+    let action = renderer.macro_action.take();
+    renderer.keymap.switch("default");
+    renderer.hint_message_snapshot.reset_after_to_init();
+
+    let (
+        Some(action),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(name @ 'a'..='z'),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }),
+    ) = (action, event)
+    else {
+        return Ok(PromptSignal::Continue);
+    };
+    match action {
+        MacroAction::Record => renderer.macros.start(*name),
+        MacroAction::Play => {
+            if let Err(e) = renderer.macros.play(*name) {
+                renderer.show_message(e, Color::Red);
+            }
+        }
+    }
+    Ok(PromptSignal::Continue)
+}
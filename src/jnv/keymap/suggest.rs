@@ -0,0 +1,583 @@
+use promkit::{
+    crossterm::{
+        event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+        style::Color,
+    },
+    listbox::Listbox,
+    PromptSignal, Result,
+};
+
+use crate::jnv::{paths::Kind, render::CaseSensitivity, run_jq, wizard};
+
+use super::{default, splice};
+
+/// The number of rows previewed for the highlighted suggestion.
+const SUGGESTION_PREVIEW_LINES: usize = 5;
+
+/// The length of the slices suggested for an array.
+const SLICE_LENGTH: usize = 10;
+
+/// Lists the paths starting with the token at the cursor, followed by the filters
+/// of the session and of the history, and switches to cycling through them.
+pub fn start(renderer: &mut crate::jnv::render::Renderer) {
+    let query_editor_after_mut = renderer.query_editor_snapshot.after_mut();
+    let suggest_after_mut = renderer.suggest_snapshot.after_mut();
+    let query = query_editor_after_mut
+        .texteditor
+        .text_without_cursor()
+        .to_string();
+    let (head, token, tail) = split_token(&query, query_editor_after_mut.texteditor.position());
+    let (candidates, note) =
+        match array_index_suggestions(&token, &renderer.documents[renderer.document_position]) {
+            Some((candidates, note)) => (Some(candidates), Some(note)),
+            None => {
+                let ignore_case = ignores_case(renderer.suggest_case, &token);
+                // The paths in the input of the stage the token is in
+                // (e.g. the items for `.items[] | select(.sta`), or else in the document.
+                let stage = stage_input(&head)
+                    .filter(|input| !input.is_empty())
+                    .and_then(|input| {
+                        run_jq(&input, &renderer.documents[renderer.document_position]).ok()
+                    })
+                    .and_then(|results| crate::jnv::index(&results).ok());
+                let mut paths = match stage {
+                    Some(index) => index.search(&token, ignore_case, None),
+                    None => renderer.suggest.search(&token, ignore_case, None),
+                };
+                renderer.suggest_ranking.sort(&mut paths);
+                let mut candidates = paths.into_iter().map(|(path, _)| path).collect::<Vec<_>>();
+                // Then the filters of the other tabs, the pinned one and the accepted ones.
+                let session = renderer
+                    .tabs
+                    .iter()
+                    .map(|tab| tab.query.clone())
+                    .chain(renderer.pinned.iter().map(|(query, _)| query.clone()));
+                let history = renderer.history.borrow().queries.clone();
+                for filter in session.chain(history) {
+                    if filter != token
+                        && filter != query
+                        && matches_case(renderer.suggest_case, &token, &filter)
+                        && !candidates.contains(&filter)
+                    {
+                        candidates.push(filter);
+                    }
+                }
+                ((!candidates.is_empty()).then_some(candidates), None)
+            }
+        };
+    if let Some(candidates) = candidates {
+        suggest_after_mut.listbox = Listbox::from_iter(candidates);
+        splice(
+            &mut query_editor_after_mut.texteditor,
+            &(head.clone(), tail.clone()),
+            &suggest_after_mut.listbox.get(),
+        );
+
+        renderer.completion = (head, tail);
+        renderer.suggest_token = token;
+        renderer.suggest_note = note;
+        renderer.suggest_kind = None;
+        renderer.suggest_marked.clear();
+        renderer.keymap.switch("on_suggest");
+        preview_suggestion(renderer);
+    } else if renderer.indexing() {
+        renderer.show_message("The paths are still being indexed", Color::Yellow);
+    }
+}
+
+/// Lists the suggestions anew for the token being completed once the paths
+/// indexed in the background (e.g. of a picked file) are ready.
+pub fn finish_indexing(renderer: &mut crate::jnv::render::Renderer) {
+    if renderer.suggest_snapshot.after().listbox.items().is_empty() {
+        return;
+    }
+    splice(
+        &mut renderer.query_editor_snapshot.after_mut().texteditor,
+        &renderer.completion,
+        &renderer.suggest_token,
+    );
+    renderer.suggest_snapshot.after_mut().listbox = Listbox::from_iter(Vec::<String>::new());
+    renderer.suggest_preview_snapshot.after_mut().listbox =
+        Listbox::from_iter(Vec::<String>::new());
+    renderer.keymap.switch("default");
+    start(renderer);
+}
+
+pub fn on_suggest(
+    event: &Event,
+    renderer: &mut crate::jnv::render::Renderer,
+) -> Result<PromptSignal> {
+    let query_editor_after_mut = renderer.query_editor_snapshot.after_mut();
+    let suggest_after_mut = renderer.suggest_snapshot.after_mut();
+
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => return Ok(PromptSignal::Quit),
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Tab,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Down,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            suggest_after_mut.listbox.forward();
+            splice(
+                &mut query_editor_after_mut.texteditor,
+                &renderer.completion,
+                &suggest_after_mut.listbox.get(),
+            );
+            preview_suggestion(renderer);
+        }
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Up,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            suggest_after_mut.listbox.backward();
+            splice(
+                &mut query_editor_after_mut.texteditor,
+                &renderer.completion,
+                &suggest_after_mut.listbox.get(),
+            );
+            preview_suggestion(renderer);
+        }
+
+        // Move by a page of the suggestion list.
+        Event::Key(KeyEvent {
+            code: code @ (KeyCode::PageDown | KeyCode::PageUp),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let page = suggest_after_mut.lines.unwrap_or(1).max(1);
+            for _ in 0..page {
+                if *code == KeyCode::PageDown {
+                    suggest_after_mut.listbox.forward();
+                } else {
+                    suggest_after_mut.listbox.backward();
+                }
+            }
+            splice(
+                &mut query_editor_after_mut.texteditor,
+                &renderer.completion,
+                &suggest_after_mut.listbox.get(),
+            );
+            preview_suggestion(renderer);
+        }
+
+        // Mark or unmark the highlighted path to be projected.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(' '),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let path = suggest_after_mut.listbox.get();
+            match renderer
+                .suggest_marked
+                .iter()
+                .position(|marked| marked == &path)
+            {
+                Some(position) => {
+                    renderer.suggest_marked.remove(position);
+                }
+                None => renderer.suggest_marked.push(path),
+            }
+            let message = format!(
+                "Marked: {} (Enter to project them into an object)",
+                renderer.suggest_marked.join(", ")
+            );
+            renderer.show_message(message, Color::Green);
+        }
+
+        // Project the marked paths into an object.
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) if !renderer.suggest_marked.is_empty() => {
+            splice(
+                &mut query_editor_after_mut.texteditor,
+                &renderer.completion,
+                &project(&renderer.suggest_marked),
+            );
+            renderer.suggest_marked.clear();
+            suggest_after_mut.listbox = Listbox::from_iter(Vec::<String>::new());
+            renderer.suggest_preview_snapshot.after_mut().listbox =
+                Listbox::from_iter(Vec::<String>::new());
+            renderer.keymap.switch("default");
+        }
+
+        // Narrow the suggested paths to arrays, objects or scalars,
+        // or widen them back with the same key.
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(key @ ('a' | 'o' | 's')),
+            modifiers: KeyModifiers::ALT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let kind = match key {
+                'a' => Kind::Array,
+                'o' => Kind::Object,
+                _ => Kind::Scalar,
+            };
+            let kind = (renderer.suggest_kind != Some(kind)).then_some(kind);
+            let token = &renderer.suggest_token;
+            let mut paths =
+                renderer
+                    .suggest
+                    .search(token, ignores_case(renderer.suggest_case, token), kind);
+            if paths.is_empty() {
+                let message = format!(
+                    "No {} at the paths starting with '{}'",
+                    kind.map_or("values", Kind::plural),
+                    token
+                );
+                renderer.show_message(message, Color::Yellow);
+            } else {
+                renderer.suggest_ranking.sort(&mut paths);
+                suggest_after_mut.listbox =
+                    Listbox::from_iter(paths.into_iter().map(|(path, _)| path).collect::<Vec<_>>());
+                splice(
+                    &mut query_editor_after_mut.texteditor,
+                    &renderer.completion,
+                    &suggest_after_mut.listbox.get(),
+                );
+                renderer.suggest_kind = kind;
+                renderer.suggest_note = kind.map(|kind| format!("{} only", kind.plural()));
+                preview_suggestion(renderer);
+            }
+        }
+
+        // Cancel and restore the path typed before the search.
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            splice(
+                &mut query_editor_after_mut.texteditor,
+                &renderer.completion,
+                &renderer.suggest_token,
+            );
+            suggest_after_mut.listbox = Listbox::from_iter(Vec::<String>::new());
+            renderer.suggest_marked.clear();
+            renderer.suggest_preview_snapshot.after_mut().listbox =
+                Listbox::from_iter(Vec::<String>::new());
+            renderer.hint_message_snapshot.reset_after_to_init();
+            renderer.keymap.switch("default");
+        }
+
+        // Enter accepts the suggestion and evaluates the query,
+        // while other keys accept it and go on editing after it.
+        _ => {
+            // Leaving with a suggestion in the editor accepts it.
+            let accepted = suggest_after_mut.listbox.get();
+            suggest_after_mut.listbox = Listbox::from_iter(Vec::<String>::new());
+            // The query was not evaluated while cycling through the suggestions.
+            renderer.stale = true;
+            if let Err(e) = renderer.suggest_ranking.accept(&accepted) {
+                renderer.show_message(
+                    format!("Failed to record the suggestion: {}", e),
+                    Color::Red,
+                );
+            }
+            renderer.suggest_marked.clear();
+            renderer.suggest_preview_snapshot.after_mut().listbox =
+                Listbox::from_iter(Vec::<String>::new());
+            renderer.keymap.switch("default");
+
+            // This block is specifically designed to prevent the default action of accepting the query
+            // from being executed when the Enter key is pressed. This is done from the perspective of user
+            // experimentation, ensuring that pressing Enter while in the suggest mode does not trigger
+            // the default behavior associated with the Enter key in the default mode.
+            if let Event::Key(KeyEvent {
+                code: KeyCode::Enter,
+                modifiers: KeyModifiers::NONE,
+                kind: KeyEventKind::Press,
+                state: KeyEventState::NONE,
+            }) = event
+            {
+            } else {
+                return default(event, renderer);
+            }
+        }
+    }
+    Ok(PromptSignal::Continue)
+}
+
+/// Shows the first rows of the value at the highlighted suggestion
+/// below the suggestions.
+fn preview_suggestion(renderer: &mut crate::jnv::render::Renderer) {
+    let path = renderer.suggest_snapshot.after().listbox.get();
+    let mut lines = match run_jq(&path, &renderer.documents[renderer.document_position]) {
+        Ok(results) => results
+            .iter()
+            .flat_map(|result| result.lines())
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>(),
+        Err(e) => vec![e.to_string()],
+    };
+    if lines.len() > SUGGESTION_PREVIEW_LINES {
+        lines.truncate(SUGGESTION_PREVIEW_LINES);
+        lines.push("…".to_string());
+    }
+    let listbox = &renderer.suggest_snapshot.after().listbox;
+    let mut counter = format!("({}/{})", listbox.position() + 1, listbox.items().len());
+    if renderer.indexing() {
+        counter.push_str(" loading more…");
+    }
+    lines.insert(
+        0,
+        match &renderer.suggest_note {
+            Some(note) => format!("{} {}", counter, note),
+            None => counter,
+        },
+    );
+    renderer.suggest_preview_snapshot.after_mut().listbox = Listbox::from_iter(lines);
+}
+
+/// Suggests indices and slices of the array when the token ends at
+/// its opening bracket (e.g. `.items[`), along with a note on its length.
+fn array_index_suggestions(token: &str, json: &str) -> Option<(Vec<String>, String)> {
+    let base = match token.strip_suffix('[')? {
+        "" => ".",
+        base => base,
+    };
+    let results = run_jq(
+        &format!(
+            r#"{} | if type == "array" then length else empty end"#,
+            base
+        ),
+        json,
+    )
+    .ok()?;
+    let [length] = results.as_slice() else {
+        return None;
+    };
+    let length = length.trim().parse::<usize>().ok()?;
+    let slice = length.clamp(1, SLICE_LENGTH);
+    Some((
+        vec![
+            format!("{}[0]", base),
+            format!("{}[-1]", base),
+            format!("{}[]", base),
+            format!("{}[:{}]", base, slice),
+            format!("{}[-{}:]", base, slice),
+        ],
+        format!("'{}' has {} items", base, length),
+    ))
+}
+
+/// Whether the letter case is ignored when completing the token, as configured.
+fn ignores_case(case: CaseSensitivity, token: &str) -> bool {
+    match case {
+        CaseSensitivity::Sensitive => false,
+        CaseSensitivity::Insensitive => true,
+        CaseSensitivity::Smart => !token.chars().any(char::is_uppercase),
+    }
+}
+
+/// Whether the candidate starts with the token, matching the letter case as configured.
+fn matches_case(case: CaseSensitivity, token: &str, candidate: &str) -> bool {
+    if ignores_case(case, token) {
+        candidate.to_lowercase().starts_with(&token.to_lowercase())
+    } else {
+        candidate.starts_with(token)
+    }
+}
+
+/// Splits the query at the path token ending at the cursor
+/// (e.g. `.sta` in `.items[] | select(.sta`), returning the text before it,
+/// the token itself and the text after the cursor.
+fn split_token(query: &str, cursor: usize) -> (String, String, String) {
+    let chars = query.chars().collect::<Vec<_>>();
+    let cursor = cursor.min(chars.len());
+    let start = chars[..cursor]
+        .iter()
+        .rposition(|ch| ch.is_whitespace() || "|(),;=<>!+*/{}:".contains(*ch))
+        .map_or(0, |position| position + 1);
+    (
+        chars[..start].iter().collect(),
+        chars[start..cursor].iter().collect(),
+        chars[cursor..].iter().collect(),
+    )
+}
+
+/// The functions whose argument is applied to the items of their input.
+const ITEM_FUNCTIONS: [&str; 8] = [
+    "map",
+    "sort_by",
+    "group_by",
+    "unique_by",
+    "min_by",
+    "max_by",
+    "any",
+    "all",
+];
+
+/// Returns the query producing the input of the pipeline stage the text before
+/// the token leads into, relative to the document (e.g. `.items[]`
+/// for `.items[] | select(`), empty for the document itself,
+/// or `None` within the arguments of other functions.
+fn stage_input(head: &str) -> Option<String> {
+    let chars = head.chars().collect::<Vec<_>>();
+    // The brackets left open, each with the last pipe after it, the first for the top level.
+    let mut opens = Vec::new();
+    let mut pipes = vec![None];
+    let (mut in_string, mut escaped) = (false, false);
+    for (i, ch) in chars.iter().enumerate() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '(' | '[' | '{' => {
+                opens.push(i);
+                pipes.push(None);
+            }
+            ')' | ']' | '}' if !opens.is_empty() => {
+                opens.pop();
+                pipes.pop();
+            }
+            '|' => *pipes.last_mut()? = Some(i),
+            _ => {}
+        }
+    }
+    input_within(&chars, &opens, &pipes)
+}
+
+/// Returns the input of the stage after the last pipe within the innermost bracket.
+fn input_within(chars: &[char], opens: &[usize], pipes: &[Option<usize>]) -> Option<String> {
+    let (input, start) = match opens.split_last() {
+        None => (String::new(), 0),
+        Some((open, outer)) => {
+            let input = input_within(chars, outer, &pipes[..pipes.len() - 1])?;
+            let name = chars[..*open]
+                .iter()
+                .rev()
+                .take_while(|ch| ch.is_alphanumeric() || **ch == '_')
+                .collect::<String>()
+                .chars()
+                .rev()
+                .collect::<String>();
+            let input = match (chars[*open], name.as_str()) {
+                // Grouping, the construction of arrays and objects, and select.
+                ('(', "" | "select") | ('[' | '{', _) => input,
+                ('(', name) if ITEM_FUNCTIONS.contains(&name) => wizard::pipe(&input, ".[]"),
+                _ => return None,
+            };
+            (input, open + 1)
+        }
+    };
+    Some(match pipes.last().copied().flatten() {
+        Some(pipe) => {
+            let stage = chars[start..pipe].iter().collect::<String>();
+            wizard::pipe(&input, stage.trim())
+        }
+        None => input,
+    })
+}
+
+/// Builds an object filter projecting the paths,
+/// each keyed by its last key (e.g. `{name: .user.name, city: .address.city}`).
+fn project(paths: &[String]) -> String {
+    let fields = paths
+        .iter()
+        .map(|path| format!("{}: {}", field_name(path), path))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// Returns the last key of the path, ignoring array indices
+/// (e.g. `tags` for `.items[0].tags[1]`).
+fn field_name(path: &str) -> String {
+    let mut path = path;
+    while let Some(stripped) = path
+        .strip_suffix(']')
+        .and_then(|path| path.rfind('[').map(|start| &path[..start]))
+    {
+        path = stripped;
+    }
+    // Quoted keys (e.g. `."content-type"`) are valid keys of object construction.
+    if let Some(quoted) = path.strip_suffix('"') {
+        if let Some(start) = quoted.rfind('"') {
+            return path[start..].to_string();
+        }
+    }
+    match path.rsplit_once('.') {
+        Some((_, key)) if !key.is_empty() => key.to_string(),
+        _ => "value".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_token_splits_at_the_cursor() {
+        assert_eq!(
+            split_token(".a | .bc", 7),
+            (".a | ".to_string(), ".b".to_string(), "c".to_string())
+        );
+        assert_eq!(
+            split_token("select(.na", 10),
+            ("select(".to_string(), ".na".to_string(), String::new())
+        );
+        assert_eq!(
+            split_token(".a", 100),
+            (String::new(), ".a".to_string(), String::new())
+        );
+    }
+
+    #[test]
+    fn stage_input_is_the_query_before_the_enclosing_pipe() {
+        assert_eq!(stage_input("").as_deref(), Some(""));
+        assert_eq!(stage_input(".items[] | ").as_deref(), Some(".items[]"));
+        assert_eq!(
+            stage_input(".items[] | select(").as_deref(),
+            Some(".items[]")
+        );
+        assert_eq!(stage_input(".a | select(.b | ").as_deref(), Some(".a | .b"));
+        assert_eq!(stage_input(".a | map(").as_deref(), Some(".a | .[]"));
+        assert_eq!(
+            stage_input(".a[] | select(.s == \"a|b\" and ").as_deref(),
+            Some(".a[]")
+        );
+        assert_eq!(stage_input("{name: ").as_deref(), Some(""));
+        assert_eq!(stage_input(".a | test(").as_deref(), None);
+    }
+
+    #[test]
+    fn field_name_ignores_indices() {
+        assert_eq!(field_name(".items[0].tags[1]"), "tags");
+        assert_eq!(field_name(".a.\"content-type\""), "\"content-type\"");
+    }
+
+    #[test]
+    fn project_builds_an_object_of_the_paths() {
+        let paths = [".a.b".to_string(), ".c[0]".to_string()];
+        assert_eq!(project(&paths), "{b: .a.b, c: .c[0]}");
+    }
+}
@@ -0,0 +1,128 @@
+use promkit::{
+    crossterm::{
+        event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+        style::Color,
+    },
+    PromptSignal, Result,
+};
+
+use crate::jnv::{settings, yank};
+
+use super::default;
+
+pub fn on_visual(
+    event: &Event,
+    renderer: &mut crate::jnv::render::Renderer,
+) -> Result<PromptSignal> {
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => return Ok(PromptSignal::Quit),
+
+        // Copy the selected rows.
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            match yank_rows(renderer) {
+                Ok(message) => renderer.show_message(message, Color::Green),
+                Err(e) => renderer.show_message(e.to_string(), Color::Red),
+            }
+            renderer.visual_anchor = None;
+            renderer.keymap.switch("default");
+        }
+
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.hint_message_snapshot.reset_after_to_init();
+            renderer.visual_anchor = None;
+            renderer.keymap.switch("default");
+        }
+
+        // Extend the selection with the cursor of the JSON viewer.
+        Event::Key(KeyEvent {
+            code: KeyCode::Up | KeyCode::Down,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        })
+        | Event::Key(KeyEvent {
+            code: KeyCode::Char('k' | 'j' | 'h' | 'l'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => return default(event, renderer),
+
+        _ => (),
+    }
+    Ok(PromptSignal::Continue)
+}
+
+/// Copies the rows selected in the visual mode to the clipboard,
+/// or appends them to the yank file if any.
+fn yank_rows(renderer: &crate::jnv::render::Renderer) -> anyhow::Result<String> {
+    let (Some(anchor), Some(row), Some(_)) = (
+        renderer.visual_anchor,
+        renderer.json_row,
+        renderer.json_rows,
+    ) else {
+        anyhow::bail!("The selected rows are unknown after toggling folds");
+    };
+    let indent = renderer.json_bundle_snapshot.after().theme.indent;
+    let shown = yank::rows(&renderer.json_nodes, indent);
+    let (first, last) = (anchor.min(row), anchor.max(row));
+    let (Some(first), Some(last)) = (shown.get(first), shown.get(last)) else {
+        anyhow::bail!("The selected rows are out of the JSON viewer");
+    };
+    // The rows of the results between the values at both ends,
+    // rather than the notes shown in their place (e.g. `… skipped 49 items …`).
+    let locate = |row: &yank::Row| {
+        settings::source_location(&renderer.source_values, &renderer.view(), &row.location)
+    };
+    let (Some(start), Some(end)) = (locate(first), locate(last)) else {
+        anyhow::bail!("The selection starts or ends at a row standing for values left out");
+    };
+    let rows = yank::rows(
+        &settings::nodes(&renderer.source_values, &settings::View::default()),
+        indent,
+    );
+    let start = if first.closes {
+        rows.iter().rposition(|row| row.location == start)
+    } else {
+        rows.iter().position(|row| row.location == start)
+    };
+    let end = if last.opens {
+        rows.iter().position(|row| row.location == end)
+    } else {
+        rows.iter().rposition(|row| row.location == end)
+    };
+    let selected = start
+        .zip(end)
+        .and_then(|(start, end)| rows.get(start..=end))
+        .ok_or_else(|| anyhow::anyhow!("The selected rows are out of the results"))?;
+    let count = selected.len();
+    let selected = selected
+        .iter()
+        .map(|row| row.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    match &renderer.yank_file {
+        Some(path) => {
+            yank::append_to_file(path, &selected)?;
+            Ok(format!("Appended {} rows to '{}'", count, path.display()))
+        }
+        None => {
+            renderer.copy_to_clipboard(&selected)?;
+            Ok(format!("Copied {} rows to the clipboard", count))
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use promkit::{
+    crossterm::{
+        event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers},
+        style::Color,
+    },
+    serde_json, PromptSignal, Result,
+};
+
+use crate::jnv::{render::EditorMode, run_jq};
+
+use super::{default, is_text_input};
+
+pub fn on_wizard(
+    event: &Event,
+    renderer: &mut crate::jnv::render::Renderer,
+) -> Result<PromptSignal> {
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => return Ok(PromptSignal::Quit),
+
+        // Answer the question.
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            let Some(EditorMode::Wizard(wizard)) = renderer.editor_mode.as_mut() else {
+                return Ok(PromptSignal::Continue);
+            };
+            let query_editor_after_mut = renderer.query_editor_snapshot.after_mut();
+            let answer = query_editor_after_mut
+                .texteditor
+                .text_without_cursor()
+                .to_string();
+            match wizard.answer(&answer) {
+                Ok(Some(filter)) => {
+                    query_editor_after_mut.texteditor.replace(&filter);
+                    renderer.editor_mode = None;
+                    renderer.keymap.switch("default");
+                }
+                Ok(None) => {
+                    query_editor_after_mut.texteditor.erase_all();
+                    show_question(renderer);
+                }
+                Err(e) => renderer.show_message(e.to_string(), Color::Red),
+            }
+        }
+
+        // Cancel and restore the query.
+        Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }) => {
+            renderer.leave_mode();
+        }
+
+        // Answer in the query editor, previewing the answer being typed.
+        _ if is_text_input(event) => {
+            let signal = default(event, renderer)?;
+            show_question(renderer);
+            return Ok(signal);
+        }
+
+        _ => (),
+    }
+    Ok(PromptSignal::Continue)
+}
+
+/// Shows the question of the wizard, along with the preview of the answer being typed.
+fn show_question(renderer: &mut crate::jnv::render::Renderer) {
+    let Some(EditorMode::Wizard(wizard)) = renderer.editor_mode.as_ref() else {
+        return;
+    };
+    let answer = renderer
+        .query_editor_snapshot
+        .after()
+        .texteditor
+        .text_without_cursor()
+        .to_string();
+    let mut message = wizard.question().to_string();
+    // The answer being typed is often not a valid regex yet.
+    if let Some(preview) = wizard
+        .preview(&answer)
+        .and_then(|preview| run_jq(&preview, &renderer.documents[renderer.document_position]).ok())
+        .and_then(|ret| ret.first().cloned())
+        .and_then(|ret| serde_json::from_str::<String>(&ret).ok())
+    {
+        message = format!("{} ({})", message, preview);
+    }
+    renderer.show_message(message, Color::Green);
+}
@@ -0,0 +1,92 @@
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Result};
+
+use super::{paths::PathIndex, wake};
+
+/// The shown document until the input is loaded.
+pub const PLACEHOLDER: &str = "\"Loading…\"";
+
+/// The input split into documents and indexed for suggestions.
+pub struct Loaded {
+    pub input: String,
+    pub documents: Vec<String>,
+    pub suggest: PathIndex,
+    /// The time taken to index the paths to be suggested.
+    pub index_time: Duration,
+}
+
+impl Loaded {
+    pub fn parse(input: String) -> Result<Self> {
+        let documents = super::split_documents(&input)?;
+        if documents.is_empty() {
+            bail!("No data found");
+        }
+        let start = Instant::now();
+        let suggest = super::index(&documents)?;
+        Ok(Self {
+            input,
+            documents,
+            suggest,
+            index_time: start.elapsed(),
+        })
+    }
+}
+
+/// Reads and parses the input in a thread.
+///
+/// The interface starts at once with a placeholder, rather than freezing
+/// the terminal on a large input, and is woken up when the input is ready.
+pub struct Loading {
+    loaded: Receiver<Result<Loaded>>,
+}
+
+impl Loading {
+    pub fn new(read: Box<dyn FnOnce() -> Result<String> + Send>) -> Self {
+        let (tx, loaded) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(read().and_then(Loaded::parse));
+            wake::wake();
+        });
+        Self { loaded }
+    }
+
+    /// Returns the input once loaded, or the error reading or parsing it.
+    pub fn take(&self) -> Option<Result<Loaded>> {
+        match self.loaded.try_recv() {
+            Ok(loaded) => Some(loaded),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(anyhow!("Failed to load the input"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait(loading: &Loading) -> Result<Loaded> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(loaded) = loading.take() {
+                return loaded;
+            }
+            assert!(Instant::now() < deadline, "the input is not loaded");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn errors_arrive_in_place_of_the_input() {
+        let loading = Loading::new(Box::new(|| Ok("{".to_string())));
+        assert!(wait(&loading).is_err());
+        let loading = Loading::new(Box::new(|| Err(anyhow!("unreadable"))));
+        assert_eq!(wait(&loading).err().unwrap().to_string(), "unreadable");
+        let loading = Loading::new(Box::new(|| Ok(" ".to_string())));
+        assert_eq!(wait(&loading).err().unwrap().to_string(), "No data found");
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::{HashMap, VecDeque};
+
+use promkit::crossterm::event::Event;
+
+/// What to do with the register named by the next key.
+#[derive(Clone, Copy)]
+pub enum MacroAction {
+    Record,
+    Play,
+}
+
+/// Sequences of events recorded into registers named with a letter.
+#[derive(Default)]
+pub struct Macros {
+    /// The register being recorded and the events so far.
+    recording: Option<(char, Vec<Event>)>,
+    registers: HashMap<char, Vec<Event>>,
+    /// The events of the macros being played, yet to be handled,
+    /// each with the registers it is played from, the outermost first.
+    playback: VecDeque<(Event, Vec<char>)>,
+    /// The registers the event being handled is played from, none if typed.
+    playing: Vec<char>,
+}
+
+impl Macros {
+    pub fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|(name, _)| *name)
+    }
+
+    pub fn start(&mut self, name: char) {
+        self.recording = Some((name, Vec::new()));
+    }
+
+    /// Records the event, if recording.
+    pub fn record(&mut self, event: &Event) {
+        if let Some((_, events)) = &mut self.recording {
+            events.push(event.clone());
+        }
+    }
+
+    /// Stops recording, keeping the events but the last one,
+    /// which is the key stopping it.
+    pub fn stop(&mut self) -> Option<(char, usize)> {
+        let (name, mut events) = self.recording.take()?;
+        events.pop();
+        let count = events.len();
+        self.registers.insert(name, events);
+        Some((name, count))
+    }
+
+    /// Queues the events of the register to be handled before the rest of the playback,
+    /// so that a macro played by another one runs in its place.
+    ///
+    /// Fails if the register doesn't exist, or is being played
+    /// (e.g. `@a` recorded into `a`), which would never end.
+    pub fn play(&mut self, name: char) -> Result<(), String> {
+        if self.playing.contains(&name) {
+            return Err(format!("'{}' can't play itself", name));
+        }
+        let events = self
+            .registers
+            .get(&name)
+            .ok_or_else(|| format!("No keys recorded into '{}'", name))?;
+        let mut playing = self.playing.clone();
+        playing.push(name);
+        for event in events.iter().rev() {
+            self.playback.push_front((event.clone(), playing.clone()));
+        }
+        Ok(())
+    }
+
+    /// Returns the next event played, to be handled.
+    pub fn next(&mut self) -> Option<Event> {
+        let (event, playing) = self.playback.pop_front()?;
+        self.playing = playing;
+        Some(event)
+    }
+
+    /// Marks the events handled next as typed rather than played.
+    pub fn typed(&mut self) {
+        self.playing.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use promkit::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::*;
+
+    fn key(ch: char) -> Event {
+        Event::Key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE))
+    }
+
+    fn record(macros: &mut Macros, name: char, keys: &str) {
+        macros.start(name);
+        for ch in keys.chars().chain(['q']) {
+            macros.record(&key(ch));
+        }
+        macros.stop();
+    }
+
+    #[test]
+    fn nested_macros_play_in_place() {
+        let mut macros = Macros::default();
+        record(&mut macros, 'a', "x@by");
+        record(&mut macros, 'b', "z");
+        macros.play('a').unwrap();
+        let mut played = Vec::new();
+        while let Some(event) = macros.next() {
+            played.push(event.clone());
+            if event == key('b') {
+                macros.play('b').unwrap();
+            }
+        }
+        let expected = "x@bzy".chars().map(key).collect::<Vec<_>>();
+        assert_eq!(played, expected);
+    }
+
+    #[test]
+    fn macros_do_not_play_themselves() {
+        let mut macros = Macros::default();
+        record(&mut macros, 'a', "@a");
+        macros.play('a').unwrap();
+        while macros.next().is_some() {
+            if macros.play('a').is_err() {
+                break;
+            }
+        }
+        assert!(macros.play('a').is_err());
+        macros.typed();
+        assert!(macros.play('a').is_ok());
+        assert!(macros.play('c').is_err());
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use promkit::json::{JsonNode, JsonPath};
+
+/// What to do with the mark named by the next key.
+#[derive(Clone, Copy)]
+pub enum MarkAction {
+    Set,
+    Jump,
+}
+
+/// A position in the JSON viewer.
+struct Mark {
+    row: usize,
+    /// The result and the path within it at the row,
+    /// to find the same value after the query is evaluated again.
+    location: Option<(usize, JsonPath)>,
+}
+
+/// Positions in the JSON viewer marked with a letter.
+#[derive(Default)]
+pub struct Marks(HashMap<char, Mark>);
+
+impl Marks {
+    /// Marks the row with the name, along with the location of the value at the row.
+    pub fn set(&mut self, name: char, row: usize, location: Option<(usize, JsonPath)>) {
+        self.0.insert(name, Mark { row, location });
+    }
+
+    /// Returns the row marked with the name: the row showing the same value
+    /// if found by `find`, or else the same row if it still exists.
+    pub fn get(
+        &self,
+        name: char,
+        find: impl Fn(&(usize, JsonPath)) -> Option<usize>,
+        rows: usize,
+    ) -> Option<usize> {
+        let mark = self.0.get(&name)?;
+        mark.location
+            .as_ref()
+            .and_then(find)
+            .or_else(|| Some(mark.row).filter(|row| *row < rows))
+    }
+}
+
+/// Returns the result and the path within it at the row.
+pub fn locate(nodes: &[JsonNode], row: usize) -> Option<(usize, JsonPath)> {
+    let mut row = row;
+    for (i, node) in nodes.iter().enumerate() {
+        let kinds = node.flatten_visibles();
+        if row < kinds.len() {
+            return kinds[row].path().map(|path| (i, path.clone()));
+        }
+        row -= kinds.len();
+    }
+    None
+}
+
+/// Returns the first row at the result and the path within it.
+pub fn find(nodes: &[JsonNode], location: &(usize, JsonPath)) -> Option<usize> {
+    let (index, path) = location;
+    let offset = nodes
+        .iter()
+        .take(*index)
+        .map(|node| node.flatten_visibles().len())
+        .sum::<usize>();
+    nodes
+        .get(*index)?
+        .flatten_visibles()
+        .iter()
+        .position(|kind| kind.path() == Some(path))
+        .map(|row| offset + row)
+}
@@ -0,0 +1,70 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// The number of evaluations the latency percentile is taken over.
+const LATENCIES: usize = 100;
+
+/// The numbers shown in the debug overlay, for reports of performance problems.
+#[derive(Default)]
+pub struct Metrics {
+    /// When the frames of the last second were drawn.
+    frames: RefCell<VecDeque<Instant>>,
+    /// The time taken by the last evaluations of the query, the last one at the back.
+    latencies: VecDeque<Duration>,
+}
+
+impl Metrics {
+    /// Counts a frame drawn now.
+    pub fn frame(&self) {
+        let mut frames = self.frames.borrow_mut();
+        let now = Instant::now();
+        while frames
+            .front()
+            .is_some_and(|frame| now.duration_since(*frame) > Duration::from_secs(1))
+        {
+            frames.pop_front();
+        }
+        frames.push_back(now);
+    }
+
+    pub fn evaluated(&mut self, latency: Duration) {
+        if self.latencies.len() == LATENCIES {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(latency);
+    }
+
+    /// Describes the frame rate, the latency of the evaluations, the number of paths indexed
+    /// and the memory used (e.g. `12 fps  eval 3.10ms (p95 12.00ms)  2340 paths  54.2 MiB`).
+    pub fn summary(&self, paths: usize) -> String {
+        let mut ret = vec![format!("{} fps", self.frames.borrow().len())];
+        if let Some(last) = self.latencies.back() {
+            let mut sorted = self.latencies.iter().collect::<Vec<_>>();
+            sorted.sort();
+            let p95 = sorted[(sorted.len() * 95).div_ceil(100) - 1];
+            ret.push(format!("eval {:.2?} (p95 {:.2?})", last, p95));
+        }
+        ret.push(format!("{} paths", paths));
+        if let Some(bytes) = resident_memory() {
+            ret.push(format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0)));
+        }
+        ret.join("  ")
+    }
+}
+
+/// Returns the memory of the process resident in RAM, where known.
+#[cfg(target_os = "linux")]
+fn resident_memory() -> Option<usize> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages = statm.split_whitespace().nth(1)?.parse::<usize>().ok()?;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    Some(pages * usize::try_from(page_size).ok()?)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn resident_memory() -> Option<usize> {
+    None
+}
@@ -0,0 +1,119 @@
+use anyhow::Result;
+use promkit::serde_json::{self, Value};
+
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Walks the tree of a document with the cursor,
+/// describing the value at the cursor as a jq path.
+pub struct Navigator {
+    /// The query before navigating, restored on cancel.
+    pub query: String,
+    document: Value,
+    path: Vec<Segment>,
+}
+
+impl Navigator {
+    /// Starts at the root of the document.
+    pub fn start(query: &str, json: &str) -> Result<Self> {
+        Ok(Self {
+            query: query.to_string(),
+            document: serde_json::from_str(json)?,
+            path: Vec::new(),
+        })
+    }
+
+    /// Returns the jq path to the value at the cursor (e.g. `.items[3].name`).
+    pub fn query_path(&self) -> String {
+        if self.path.is_empty() {
+            return ".".to_string();
+        }
+        self.path
+            .iter()
+            .map(|segment| match segment {
+                Segment::Key(key) => key_segment(key),
+                Segment::Index(index) => format!("[{}]", index),
+            })
+            .collect()
+    }
+
+    /// Moves to the first child of the value at the cursor.
+    pub fn child(&mut self) -> bool {
+        let first = match self.value(&self.path) {
+            Some(Value::Object(object)) => object.keys().next().cloned().map(Segment::Key),
+            Some(Value::Array(array)) if !array.is_empty() => Some(Segment::Index(0)),
+            _ => None,
+        };
+        first.map(|segment| self.path.push(segment)).is_some()
+    }
+
+    /// Moves to the parent of the value at the cursor.
+    pub fn parent(&mut self) -> bool {
+        self.path.pop().is_some()
+    }
+
+    /// Moves to the next sibling, or the previous one if `forward` is false.
+    pub fn sibling(&mut self, forward: bool) -> bool {
+        let Some((last, parent)) = self.path.split_last() else {
+            return false;
+        };
+        let sibling = match (self.value(parent), last) {
+            (Some(Value::Object(object)), Segment::Key(key)) => {
+                let keys = object.keys().collect::<Vec<_>>();
+                keys.iter()
+                    .position(|k| *k == key)
+                    .and_then(|position| step(position, forward, keys.len()))
+                    .map(|position| Segment::Key(keys[position].clone()))
+            }
+            (Some(Value::Array(array)), Segment::Index(index)) => {
+                step(*index, forward, array.len()).map(Segment::Index)
+            }
+            _ => None,
+        };
+        match sibling {
+            Some(sibling) => {
+                self.path.pop();
+                self.path.push(sibling);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn value(&self, path: &[Segment]) -> Option<&Value> {
+        path.iter()
+            .try_fold(&self.document, |value, segment| match segment {
+                Segment::Key(key) => value.get(key),
+                Segment::Index(index) => value.get(index),
+            })
+    }
+}
+
+fn step(position: usize, forward: bool, len: usize) -> Option<usize> {
+    if forward {
+        Some(position + 1).filter(|next| *next < len)
+    } else {
+        position.checked_sub(1)
+    }
+}
+
+/// Converts a key into a jq segment, quoted and escaped unless it is an identifier
+/// (e.g. `.items`, `."content-type"` or `."first name"`).
+pub fn key_segment(key: &str) -> String {
+    if is_identifier(key) {
+        format!(".{}", key)
+    } else {
+        format!(".{}", Value::from(key))
+    }
+}
+
+/// Returns whether the key can be written without quotes (e.g. `.name`).
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '_')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
@@ -0,0 +1,70 @@
+use std::{
+    env,
+    io::{self, Write},
+    process::{Command, Stdio},
+    sync::OnceLock,
+};
+
+use anyhow::{anyhow, Result};
+use promkit::crossterm::{
+    cursor, execute,
+    terminal::{self, Clear, ClearType, EnterAlternateScreen},
+};
+
+/// The pager used without `$PAGER`, keeping the colors.
+const DEFAULT_PAGER: &str = "less -R";
+
+/// Whether the interface is drawn in the alternate screen.
+static ALT_SCREEN: OnceLock<bool> = OnceLock::new();
+
+/// Has the interface drawn again in the alternate screen after the pager,
+/// which leaves it on exit, once for the process.
+pub fn use_alt_screen() {
+    let _ = ALT_SCREEN.set(true);
+}
+
+/// Hands the terminal over to the pager of the user (`$PAGER`, or `less -R`)
+/// to show the text, until the pager exits.
+pub fn show(text: String) -> Result<()> {
+    let pager = env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+
+    terminal::disable_raw_mode()?;
+    execute!(io::stdout(), cursor::Show)?;
+    let ret = Command::new(shell)
+        .args([flag, &pager])
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run '{}': {}", pager, e))
+        .and_then(|mut child| {
+            if let Some(mut stdin) = child.stdin.take() {
+                // The pager may be quit before reading everything.
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            let status = child.wait()?;
+            if !status.success() {
+                return Err(anyhow!("'{}' failed with {}", pager, status));
+            }
+            Ok(())
+        });
+    // The interface is drawn again from a blank screen,
+    // in raw mode even if the screen fails to be restored.
+    terminal::enable_raw_mode()?;
+    if ALT_SCREEN.get().copied().unwrap_or(false) {
+        execute!(io::stdout(), EnterAlternateScreen)?;
+    }
+    execute!(
+        io::stdout(),
+        cursor::Hide,
+        cursor::MoveTo(0, 0),
+        Clear(ClearType::All)
+    )?;
+    ret
+}
@@ -0,0 +1,182 @@
+use std::collections::{HashMap, HashSet};
+
+/// The paths to be suggested, kept as a trie of their segments
+/// (e.g. `.items`, `[0]` and `.name` for `.items[0].name`),
+/// so that memory grows with the unique segments rather than the full paths,
+/// which are only spelled out for the matches of a search.
+pub struct PathIndex {
+    /// The nodes, the root (the path `.`) first, each standing for a path.
+    nodes: Vec<Node>,
+    /// The text of each unique segment.
+    segments: Vec<Box<str>>,
+    segment_ids: HashMap<Box<str>, u32>,
+    /// The child of a node by its segment.
+    children: HashMap<(u32, u32), u32>,
+}
+
+/// The kind of value at a path, to narrow the search to.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Object,
+    Array,
+    Scalar,
+}
+
+impl Kind {
+    pub fn plural(self) -> &'static str {
+        match self {
+            Kind::Object => "objects",
+            Kind::Array => "arrays",
+            Kind::Scalar => "scalars",
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// A path, linked to its first child and next sibling in insertion order.
+struct Node {
+    segment: u32,
+    first_child: Option<u32>,
+    last_child: Option<u32>,
+    next_sibling: Option<u32>,
+    /// The number of documents containing the path.
+    documents: u32,
+    /// The kinds of the values at the path, as bits,
+    /// since documents may differ.
+    kinds: u8,
+}
+
+impl Default for PathIndex {
+    fn default() -> Self {
+        Self {
+            nodes: vec![Node::new(0)],
+            segments: vec!["".into()],
+            segment_ids: HashMap::from([("".into(), 0)]),
+            children: HashMap::new(),
+        }
+    }
+}
+
+impl Node {
+    fn new(segment: u32) -> Self {
+        Self {
+            segment,
+            first_child: None,
+            last_child: None,
+            next_sibling: None,
+            documents: 0,
+            kinds: 0,
+        }
+    }
+}
+
+impl PathIndex {
+    /// The number of paths.
+    pub fn len(&self) -> usize {
+        // The root stands for a path only once a document was added.
+        self.nodes.len() - usize::from(self.nodes[0].documents == 0)
+    }
+
+    /// Adds the paths of a document, each given as its segments
+    /// with the kind of its value, counting the document once for each path.
+    pub fn add_document<I: IntoIterator<Item = (Vec<String>, Kind)>>(&mut self, paths: I) {
+        let mut seen = HashSet::new();
+        for (segments, kind) in paths {
+            let mut node = 0;
+            for segment in segments {
+                node = self.child(node, segment);
+            }
+            self.nodes[node as usize].kinds |= kind.bit();
+            if seen.insert(node) {
+                self.nodes[node as usize].documents += 1;
+            }
+        }
+    }
+
+    /// Returns the child of the node with the segment, adding it if missing.
+    fn child(&mut self, parent: u32, segment: String) -> u32 {
+        let segment = match self.segment_ids.get(segment.as_str()) {
+            Some(id) => *id,
+            None => {
+                let id = self.segments.len() as u32;
+                let segment = segment.into_boxed_str();
+                self.segments.push(segment.clone());
+                self.segment_ids.insert(segment, id);
+                id
+            }
+        };
+        if let Some(child) = self.children.get(&(parent, segment)) {
+            return *child;
+        }
+        let child = self.nodes.len() as u32;
+        self.nodes.push(Node::new(segment));
+        self.children.insert((parent, segment), child);
+        let parent = &mut self.nodes[parent as usize];
+        match parent.last_child.replace(child) {
+            Some(last) => self.nodes[last as usize].next_sibling = Some(child),
+            None => parent.first_child = Some(child),
+        }
+        child
+    }
+
+    /// Returns the paths starting with the prefix, in the order they were added,
+    /// each with the number of documents containing it,
+    /// keeping only the paths to values of the kind if given.
+    /// Quotes are ignored, so that `.content-t` finds `."content-type"`.
+    pub fn search(
+        &self,
+        prefix: &str,
+        ignore_case: bool,
+        kind: Option<Kind>,
+    ) -> Vec<(String, usize)> {
+        let has_kind =
+            |node: u32| kind.is_none_or(|kind| self.nodes[node as usize].kinds & kind.bit() != 0);
+        let prefix = if ignore_case {
+            prefix.to_lowercase()
+        } else {
+            prefix.to_string()
+        }
+        .replace('"', "");
+        let mut ret = Vec::new();
+        if self.nodes[0].documents > 0 && has_kind(0) && ".".starts_with(&prefix) {
+            ret.push((".".to_string(), self.nodes[0].documents as usize));
+        }
+        // Depth first, with the path of each node to visit.
+        let mut stack = vec![(0, String::new())];
+        while let Some((node, path)) = stack.pop() {
+            let is_root = node == 0;
+            let compared = if ignore_case {
+                path.to_lowercase()
+            } else {
+                path.clone()
+            }
+            .replace('"', "");
+            let matched = !is_root && compared.starts_with(&prefix);
+            // Descendants can only match if the path is a part of the prefix.
+            if !matched && !prefix.starts_with(&compared) {
+                continue;
+            }
+            for child in self.children_of(node).into_iter().rev() {
+                let segment = &self.segments[self.nodes[child as usize].segment as usize];
+                stack.push((child, format!("{}{}", path, segment)));
+            }
+            if matched && has_kind(node) {
+                ret.push((path, self.nodes[node as usize].documents as usize));
+            }
+        }
+        ret
+    }
+
+    fn children_of(&self, node: u32) -> Vec<u32> {
+        let mut children = Vec::new();
+        let mut child = self.nodes[node as usize].first_child;
+        while let Some(current) = child {
+            children.push(current);
+            child = self.nodes[current as usize].next_sibling;
+        }
+        children
+    }
+}
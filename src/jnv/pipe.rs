@@ -0,0 +1,57 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    thread,
+};
+
+use anyhow::{anyhow, Result};
+
+/// The number of rows of the output of a command shown in the scratch pane.
+const SCRATCH_LINES: usize = 10;
+
+/// Runs the shell command with the text on its standard input,
+/// returning the rows of its output and errors to show in the scratch pane.
+pub fn run(command: &str, input: String) -> Result<Vec<String>> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    // The command must not draw over the interface.
+    let mut child = Command::new(shell)
+        .args([flag, command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run '{}': {}", command, e))?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("Failed to write to '{}'", command))?;
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    // The command may exit without reading its input (e.g. `true`).
+    let _ = writer.join();
+
+    let mut lines = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .chain(String::from_utf8_lossy(&output.stderr).lines())
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>();
+    let total = lines.len();
+    if total > SCRATCH_LINES {
+        lines.truncate(SCRATCH_LINES);
+        lines.push(format!("… {} more lines", total - SCRATCH_LINES));
+    }
+    let status = if output.status.success() {
+        format!("$ {}", command)
+    } else {
+        format!("$ {} ({})", command, output.status)
+    };
+    lines.insert(0, status);
+    if total == 0 {
+        lines.push("(no output)".to_string());
+    }
+    Ok(lines)
+}
@@ -0,0 +1,54 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use anyhow::Result;
+use promkit::serde_json;
+
+use super::store::Store;
+
+/// The file recording how often each suggestion was accepted,
+/// keyed by the fingerprint of the input.
+const HISTORY_FILE: &str = "suggestions.json";
+
+/// Ranks the suggested paths by how often they were accepted for the same input,
+/// and then by how many documents of the input contain them.
+pub struct Ranking {
+    store: Store,
+    accepted: HashMap<String, usize>,
+}
+
+impl Ranking {
+    /// Loads the acceptances recorded for the input.
+    pub fn load(fingerprint: &str) -> Self {
+        let store = Store::open(HISTORY_FILE, fingerprint);
+        let accepted = store
+            .load()
+            .and_then(|accepted| serde_json::from_value(accepted).ok())
+            .unwrap_or_default();
+        Self { store, accepted }
+    }
+
+    /// Sorts the paths, each with the number of documents containing it,
+    /// the most useful first.
+    /// Ties are broken by the shorter path, then alphabetically.
+    pub fn sort(&self, paths: &mut [(String, usize)]) {
+        paths.sort_by(|(a, a_documents), (b, b_documents)| {
+            let key = |path: &String, documents: usize| {
+                (
+                    Reverse(self.accepted.get(path).copied().unwrap_or_default()),
+                    Reverse(documents),
+                    path.len(),
+                )
+            };
+            key(a, *a_documents)
+                .cmp(&key(b, *b_documents))
+                .then_with(|| a.cmp(b))
+        });
+    }
+
+    /// Records the acceptance of the path in the history file,
+    /// which keeps the acceptances of the inputs used most recently.
+    pub fn accept(&mut self, path: &str) -> Result<()> {
+        *self.accepted.entry(path.to_string()).or_default() += 1;
+        self.store.save(serde_json::to_value(&self.accepted)?)
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+
+use super::{store, wake};
+
+/// Reads the input again on an interval.
+///
+/// The input is read by a thread, so that a slow command doesn't hold up
+/// the interface, which is woken up when the input has changed.
+pub struct Refresh {
+    interval: Duration,
+    /// The inputs read again that have changed, or the errors reading them.
+    inputs: Receiver<Result<String>>,
+    /// Asks the thread to read the input now rather than at the end of the interval.
+    force: Sender<()>,
+    paused: Arc<AtomicBool>,
+    last: Arc<Mutex<Instant>>,
+    /// The fingerprint of the last input, which the input read again is compared with.
+    fingerprint: Arc<Mutex<String>>,
+    /// The input read again but held back, since loading it would lose the edits.
+    pub held: Option<String>,
+}
+
+impl Refresh {
+    pub fn new(
+        interval: Duration,
+        reload: Box<dyn Fn() -> Result<String> + Send>,
+        fingerprint: &str,
+    ) -> Self {
+        let (input_tx, inputs) = mpsc::channel();
+        let (force, force_rx) = mpsc::channel();
+        let paused = Arc::new(AtomicBool::new(false));
+        let last = Arc::new(Mutex::new(Instant::now()));
+        let fingerprint = Arc::new(Mutex::new(fingerprint.to_string()));
+        let (thread_paused, thread_last, thread_fingerprint) = (
+            Arc::clone(&paused),
+            Arc::clone(&last),
+            Arc::clone(&fingerprint),
+        );
+        thread::spawn(move || loop {
+            match force_rx.recv_timeout(interval) {
+                Ok(()) => {}
+                Err(RecvTimeoutError::Timeout) if thread_paused.load(Ordering::SeqCst) => continue,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+            *thread_last.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+            let input = match reload() {
+                Ok(input) => input,
+                Err(e) => {
+                    if input_tx.send(Err(e)).is_err() {
+                        return;
+                    }
+                    wake::wake();
+                    continue;
+                }
+            };
+            let changed = store::fingerprint(&input);
+            let mut fingerprint = thread_fingerprint.lock().unwrap_or_else(|e| e.into_inner());
+            if changed == *fingerprint {
+                continue;
+            }
+            *fingerprint = changed;
+            drop(fingerprint);
+            if input_tx.send(Ok(input)).is_err() {
+                return;
+            }
+            wake::wake();
+        });
+        Self {
+            interval,
+            inputs,
+            force,
+            paused,
+            last,
+            fingerprint,
+            held: None,
+        }
+    }
+
+    /// Returns the last input read again since the last call, if it has changed,
+    /// or the first error reading it.
+    pub fn take(&mut self) -> Result<Option<String>> {
+        let mut ret = None;
+        for input in self.inputs.try_iter() {
+            ret = Some(input?);
+        }
+        Ok(ret)
+    }
+
+    /// Compares the input read again with the one loaded since (e.g. at start),
+    /// rather than with the input it was started with.
+    pub fn seen(&self, fingerprint: &str) {
+        fingerprint.clone_into(&mut self.fingerprint.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+
+    /// Reads the input again now, even while paused.
+    pub fn force(&self) {
+        let _ = self.force.send(());
+    }
+
+    pub fn toggle_pause(&self) {
+        self.paused.fetch_xor(true, Ordering::SeqCst);
+    }
+
+    /// Describes the state for the status line (e.g. `live 5s, 2s ago`).
+    pub fn status(&self) -> String {
+        if self.held.is_some() {
+            return "changed, held for the edits".to_string();
+        }
+        if self.paused.load(Ordering::SeqCst) {
+            return "paused".to_string();
+        }
+        let last = *self.last.lock().unwrap_or_else(|e| e.into_inner());
+        format!(
+            "live {}, {}s ago",
+            format_duration(self.interval),
+            last.elapsed().as_secs()
+        )
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    if duration.subsec_millis() == 0 {
+        format!("{}s", duration.as_secs())
+    } else {
+        format!("{}ms", duration.as_millis())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_changed_inputs_are_taken() {
+        let reads = Arc::new(Mutex::new(vec!["[2]", "[1]", "[1]"]));
+        let remaining = Arc::clone(&reads);
+        let mut refresh = Refresh::new(
+            Duration::from_secs(3600),
+            Box::new(move || Ok(remaining.lock().unwrap().pop().unwrap_or("[2]").to_string())),
+            &store::fingerprint("[1]"),
+        );
+        refresh.force();
+        refresh.force();
+        refresh.force();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !reads.lock().unwrap().is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(refresh.take().unwrap().as_deref(), Some("[2]"));
+        assert_eq!(refresh.take().unwrap(), None);
+    }
+}
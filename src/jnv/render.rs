@@ -1,27 +1,1502 @@
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
 use promkit::{
-    impl_as_any, impl_cast, json, keymap::KeymapManager, listbox, pane::Pane, snapshot::Snapshot,
-    suggest::Suggest, text, text_editor,
+    crossterm::{
+        style::{Attribute, Attributes, Color, ContentStyle},
+        terminal,
+    },
+    grapheme::StyledGraphemes,
+    impl_as_any, impl_cast,
+    json::{self, JsonBundle, JsonNode, JsonPath},
+    keymap::KeymapManager,
+    listbox::{self, Listbox},
+    pane::Pane,
+    serde_json::{self, Value},
+    snapshot::Snapshot,
+    style::StyleBuilder,
+    text, text_editor,
 };
 
+#[cfg(unix)]
+use super::control::Control;
+use super::{
+    analysis::Analysis,
+    budget::{self, Remaining},
+    changes::Changes,
+    damage::{Damage, PaneIndex},
+    diff,
+    edit::{Edit, Editing},
+    formats::{self, OutputFormat},
+    grapheme,
+    history::History,
+    hooks::Hooks,
+    keymap::Chord,
+    loading::{Loaded, Loading},
+    macros::{MacroAction, Macros},
+    marks::{self, MarkAction, Marks},
+    metrics::Metrics,
+    navigate::Navigator,
+    pager,
+    paths::{Kind, PathIndex},
+    ranking::Ranking,
+    refresh::Refresh,
+    report,
+    settings::{self, Setting, SETTINGS},
+    store::{self, Store},
+    theme::{Theme, ELEMENTS},
+    wake,
+    wizard::{self, Wizard},
+    yank,
+};
+
+/// The number of columns the JSON viewer is scrolled by horizontally.
+const SCROLL_COLUMNS: usize = 8;
+
+/// Where the query editor is placed relative to the JSON viewer.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Layout {
+    /// The query editor and suggestions above the JSON viewer.
+    Top,
+    /// The query editor and suggestions below the JSON viewer,
+    /// close to where the command was typed.
+    Bottom,
+}
+
+/// How much the hint pane tells about the evaluation of the query.
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub enum Verbosity {
+    /// No hints at all.
+    Off,
+    /// Only the failures to evaluate the query.
+    Errors,
+    /// Also warnings on empty or null results and descriptions of the values.
+    Normal,
+    /// Also the time taken to evaluate the query and to index the paths.
+    Verbose,
+}
+
+impl Verbosity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Verbosity::Off => "off",
+            Verbosity::Errors => "errors",
+            Verbosity::Normal => "normal",
+            Verbosity::Verbose => "verbose",
+        }
+    }
+
+    /// The next level, wrapping around to `off`.
+    pub fn next(self) -> Self {
+        match self {
+            Verbosity::Off => Verbosity::Errors,
+            Verbosity::Errors => Verbosity::Normal,
+            Verbosity::Normal => Verbosity::Verbose,
+            Verbosity::Verbose => Verbosity::Off,
+        }
+    }
+}
+
+/// When the output outside the interface is colored with ANSI escape codes.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    /// The output to a terminal (e.g. the pager and exports),
+    /// unless the `NO_COLOR` environment variable is set.
+    Auto,
+    /// Also the output on accept, even when it is piped.
+    Always,
+    Never,
+}
+
+/// How the letter case of the path being completed is matched.
+#[derive(Clone, Copy, PartialEq)]
+pub enum CaseSensitivity {
+    Sensitive,
+    Insensitive,
+    /// Insensitive unless the path contains an uppercase letter.
+    Smart,
+}
+
+/// A query whose results became the documents refined by the next query,
+/// pushed with Alt + Enter, or a path set as the root with `:root`.
+pub struct Level {
+    pub query: String,
+    /// Whether the query ran on every document rather than the one shown.
+    every_document: bool,
+    /// The documents the query ran on, with the one shown and their paths,
+    /// restored when popped.
+    documents: Vec<String>,
+    document_position: usize,
+    suggest: PathIndex,
+}
+
+/// A query over the same document, switched with Alt + 1..9.
+#[derive(Clone)]
+pub struct Tab {
+    pub query: String,
+    /// The row of the cursor in the JSON viewer.
+    pub row: Option<usize>,
+}
+
+/// What the query editor holds instead of the query, with the query to restore.
+pub enum EditorMode {
+    /// The value being edited.
+    Edit(Editing),
+    /// The answer to the question of a wizard.
+    Wizard(Wizard),
+    /// The pattern the files to pick from are filtered by.
+    Pick { query: String },
+    /// The pattern the keys shown in the JSON viewer must contain.
+    KeyFilter { query: String },
+    /// The row to jump to.
+    Jump { query: String },
+    /// The command to pipe the results into.
+    Pipe { query: String },
+    /// The file to export the view to.
+    Export { query: String },
+    /// The search of the jq builtins, with the position of the cursor in the query.
+    Help { query: String, cursor: usize },
+}
+
+impl EditorMode {
+    /// The query shown before, restored when the mode is left.
+    pub fn query(&self) -> &str {
+        match self {
+            EditorMode::Edit(editing) => &editing.query,
+            EditorMode::Wizard(wizard) => wizard.query(),
+            EditorMode::Pick { query }
+            | EditorMode::KeyFilter { query }
+            | EditorMode::Jump { query }
+            | EditorMode::Pipe { query }
+            | EditorMode::Export { query }
+            | EditorMode::Help { query, .. } => query,
+        }
+    }
+
+    /// The name of the keymap handling the keys in the mode.
+    pub fn keymap(&self) -> &'static str {
+        match self {
+            EditorMode::Edit(_) => "on_edit",
+            EditorMode::Wizard(_) => "on_wizard",
+            EditorMode::Pick { .. } => "on_pick",
+            EditorMode::KeyFilter { .. } => "on_key_filter",
+            EditorMode::Jump { .. } => "on_jump",
+            EditorMode::Pipe { .. } => "on_pipe",
+            EditorMode::Export { .. } => "on_export",
+            EditorMode::Help { .. } => "on_help",
+        }
+    }
+}
+
 pub struct Renderer {
     pub keymap: KeymapManager<Self>,
     pub query_editor_snapshot: Snapshot<text_editor::Renderer>,
     pub hint_message_snapshot: Snapshot<text::Renderer>,
-    pub suggest: Suggest,
+    pub status_snapshot: Snapshot<text::Renderer>,
+    pub documents: Vec<String>,
+    pub document_position: usize,
+    pub accepted: bool,
+    /// What the query editor holds instead of the query, if anything.
+    pub editor_mode: Option<EditorMode>,
+    pub edits: Vec<Edit>,
+    /// The action replacing the input warned about, discarding the edits if repeated
+    /// with the next key.
+    pub discard_pending: Option<String>,
+    pub navigator: Option<Navigator>,
+    pub hint_visible: bool,
+    /// The maximum number of lines of the hint pane, if limited.
+    pub hint_lines: Option<usize>,
+    pub verbosity: Verbosity,
+    pub suggest_visible: bool,
+    /// Whether every pane but the JSON viewer is hidden.
+    pub fullscreen: bool,
+    /// Whether the debug overlay on the frame rate, the evaluations and memory is shown.
+    pub hud_visible: bool,
+    pub metrics: Metrics,
+    /// The parts of the screen changed since the last frame.
+    pub damage: Damage,
+    /// Whether long lines of the JSON viewer wrap,
+    /// instead of being cut off at the edge of the terminal.
+    pub wrap: bool,
+    /// The columns of the JSON viewer scrolled past, while long lines don't wrap.
+    pub column: usize,
+    /// Whether the rows of the JSON viewer are numbered in a gutter.
+    pub line_numbers: bool,
+    /// The row of the cursor in the JSON viewer, if known.
+    pub json_row: Option<usize>,
+    /// The number of rows in the JSON viewer, which is unknown
+    /// once folds are toggled.
+    pub json_rows: Option<usize>,
+    /// The nodes shown in the JSON viewer, with their initial folds.
+    pub json_nodes: Vec<JsonNode>,
+    /// The number of results of the query (e.g. `3 results` or `no results in 1.20ms`),
+    /// if it was evaluated.
+    pub result_summary: Option<String>,
+    /// The values shown in the JSON viewer, before the display options were applied,
+    /// which the values at the rows are looked up in.
+    pub source_values: Arc<Vec<Value>>,
+    /// The values left over after the frame budget, being laid out in the background.
+    pub remaining: Option<Remaining>,
+    /// The pattern the keys shown in the JSON viewer must contain, if any.
+    pub key_filter: Option<String>,
+    /// The jq builtins matching the search, with a short documentation.
+    pub help_snapshot: Snapshot<listbox::Renderer>,
+    /// The row where the selection of rows started, in the visual mode.
+    pub visual_anchor: Option<usize>,
+    pub marks: Marks,
+    /// What to do with the mark named by the next key, if awaiting it.
+    pub mark_action: Option<MarkAction>,
+    /// The key sequence started with Ctrl+X, if pending.
+    pub chord: Option<Chord>,
+    pub macros: Macros,
+    pub macro_action: Option<MacroAction>,
+    /// The location in the document to move the cursor to,
+    /// once the document is shown.
+    pub pending_location: Option<JsonPath>,
+    /// The query that was shown before jumping to the location in the document.
+    pub origin_query: Option<String>,
+    /// The queries of the tabs, as they were when last shown.
+    pub tabs: Vec<Tab>,
+    /// The queries whose results are being refined, the first one on the input.
+    pub stack: Vec<Level>,
+    /// The position of the shown tab.
+    pub tab: usize,
+    /// The row to move the cursor to, once the query of the tab is evaluated.
+    pub pending_row: Option<usize>,
+    /// The file the input was read from, if it was read from a single file,
+    /// which the accepted query is kept for rather than the content of the input.
+    pub source: Option<PathBuf>,
+    /// Keeps the accepted query for the input.
+    pub query_store: Store,
+    /// The filters accepted in past sessions, recorded on accept.
+    pub history: RefCell<History>,
+    /// The query accepted last time for the input, if any.
+    pub last_query: Option<String>,
+    /// The files to pick the input from.
+    pub workspace: Vec<PathBuf>,
+    pub refresh: Option<Refresh>,
+    /// The input being read and parsed, shown as a placeholder meanwhile.
+    pub loading: Option<Loading>,
+    /// The error loading the input, which ends the session.
+    pub failed: RefCell<Option<anyhow::Error>>,
+    /// The file picked to replace the input, being read and parsed
+    /// while the current input stays shown.
+    pub switching: Option<(PathBuf, Loading)>,
+    pub hooks: Hooks,
+    /// The control socket, if serving.
+    #[cfg(unix)]
+    pub control: Option<Control>,
+    pub picker_snapshot: Snapshot<listbox::Renderer>,
+    /// The overlay of display options.
+    pub settings_snapshot: Snapshot<listbox::Renderer>,
+    pub settings_visible: bool,
+    /// The overlay of the colors of the JSON viewer.
+    pub theme_snapshot: Snapshot<listbox::Renderer>,
+    pub theme_visible: bool,
+    pub theme: Theme,
+    pub sort_keys: bool,
+    /// Whether the entries with null or empty values are hidden from the viewer.
+    pub hide_empty: bool,
+    /// Whether only a sample of the items of long arrays is shown in the viewer.
+    pub sample_arrays: bool,
+    /// The number of levels shown in the viewer, if limited.
+    pub max_depth: Option<usize>,
+    /// The number of levels unfolded at first in the viewer, if limited.
+    pub expand_depth: Option<usize>,
+    /// Whether the rows changed by an edit of the query are highlighted.
+    pub highlight_changes: bool,
+    pub changes: Option<Changes>,
+    /// The text of the rows of the last results, for the next ones to be compared with.
+    pub result_rows: HashSet<String>,
+    pub raw_output: bool,
+    pub compact_output: bool,
+    /// Whether the characters beyond ASCII are escaped on accept and in exports.
+    pub ascii_output: bool,
+    pub output_format: OutputFormat,
+    pub color: ColorChoice,
+    /// Whether the results must be shown again, e.g. after sorting keys.
+    pub stale: bool,
+    /// The number of times the input was replaced by a picked file.
+    pub loads: usize,
+    /// The file to append yanked values to, instead of the clipboard.
+    pub yank_file: Option<PathBuf>,
+    pub suggest: PathIndex,
+    /// The time taken to index the paths to be suggested.
+    pub index_time: Duration,
+    /// The time taken by the last evaluation of the query.
+    pub eval_time: Duration,
+    pub suggest_ranking: Ranking,
+    pub suggest_case: CaseSensitivity,
     pub suggest_snapshot: Snapshot<listbox::Renderer>,
+    /// The suggested paths marked to be projected into an object.
+    pub suggest_marked: Vec<String>,
+    /// The query around the path token being completed,
+    /// which the highlighted suggestion is spliced into.
+    pub completion: (String, String),
+    /// The path token the suggestions were searched for,
+    /// highlighted in the suggestions starting with it.
+    pub suggest_token: String,
+    /// The first rows of the value at the highlighted suggestion.
+    pub suggest_preview_snapshot: Snapshot<listbox::Renderer>,
+    /// A note on the suggestions shown above the preview
+    /// (e.g. the length of the array whose indices are suggested).
+    pub suggest_note: Option<String>,
+    /// The kind of values the suggested paths are narrowed to.
+    pub suggest_kind: Option<Kind>,
+    pub analysis: Option<Analysis>,
+    pub analysis_snapshot: Snapshot<listbox::Renderer>,
+    /// The output of the command the results were piped into.
+    pub scratch_snapshot: Snapshot<listbox::Renderer>,
+    /// The query pinned to compare the results with, and its results.
+    pub pinned: Option<(String, Vec<serde_json::Value>)>,
+    pub diff_snapshot: Snapshot<listbox::Renderer>,
     pub json_bundle_snapshot: Snapshot<json::bundle::Renderer>,
+    pub layout: Layout,
+    /// The maximum number of lines for the JSON viewer, if configured.
+    pub result_lines: Option<usize>,
+    /// The size of the terminal when replaying events without one.
+    pub size: Option<(u16, u16)>,
+    /// Whether there is no terminal (e.g. replaying events),
+    /// which is then neither written to nor handed over to other programs.
+    pub headless: bool,
 }
 
 impl_as_any!(Renderer);
 impl_cast!(Renderer);
 
+impl Renderer {
+    /// Shows the values in the JSON viewer, with the cursor at the top.
+    pub fn show_json(&mut self, values: Vec<Value>) {
+        self.lay_out(Arc::new(values));
+    }
+
+    /// Shows the values laid out within the frame budget, leaving the rest
+    /// to be laid out in the background, except without a terminal (e.g. replayed)
+    /// or a way to wake the interface up when they are ready.
+    fn lay_out(&mut self, values: Arc<Vec<Value>>) {
+        let view = self.view();
+        // The values of the previous results are not laid out any further.
+        self.remaining = None;
+        let deadline = Instant::now() + budget::FRAME_BUDGET;
+        let (nodes, done) = settings::nodes_until(&values, &view, || {
+            !self.headless && wake::SUPPORTED && Instant::now() >= deadline
+        });
+        if done < values.len() {
+            self.remaining = Some(Remaining::start(Arc::clone(&values), done, view.clone()));
+        }
+        self.source_values = values;
+        self.json_row = Some(0);
+        if self.remaining.is_none() {
+            self.set_nodes(settings::or_empty(nodes, &view));
+        } else {
+            self.set_nodes(nodes);
+        }
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<JsonNode>) {
+        self.json_rows = Some(nodes.iter().map(|node| node.flatten_visibles().len()).sum());
+        self.json_bundle_snapshot.after_mut().bundle = JsonBundle::new(nodes.clone());
+        self.json_nodes = nodes;
+        self.damage.mark(PaneIndex::Result);
+    }
+
+    /// Shows the values left over after the frame budget once laid out,
+    /// keeping the cursor on its row.
+    pub fn finish_layout(&mut self) {
+        let Some(rest) = self.remaining.as_ref().and_then(Remaining::take) else {
+            return;
+        };
+        let compare = self
+            .remaining
+            .take()
+            .is_some_and(|remaining| remaining.compare);
+        let mut nodes = std::mem::take(&mut self.json_nodes);
+        nodes.extend(rest);
+        let view = self.view();
+        let row = self.json_row;
+        self.set_nodes(settings::or_empty(nodes, &view));
+        if let Some(row) = row {
+            self.move_json_cursor(row);
+        }
+        if compare {
+            self.compare_rows();
+        }
+    }
+    /// The display options the JSON viewer lays out the values with.
+    pub fn view(&self) -> settings::View {
+        settings::View {
+            key_filter: self
+                .key_filter
+                .as_ref()
+                .map(|pattern| pattern.to_lowercase()),
+            sample_arrays: self.sample_arrays,
+            hide_empty: self.hide_empty,
+            sort_keys: self.sort_keys,
+            max_depth: self.max_depth,
+            expand_depth: self.expand_depth,
+        }
+    }
+
+    /// Shows the nodes again, after changing how they are shown.
+    pub fn reshow(&mut self) {
+        let values = std::mem::take(&mut self.source_values);
+        self.lay_out(values);
+    }
+
+    /// Returns the result and the path within it of the value starting at the row
+    /// of the JSON viewer, in the values rather than in the nodes shown.
+    pub fn location_at(&self, row: usize) -> Option<(usize, JsonPath)> {
+        let location = marks::locate(&self.json_nodes, row)?;
+        settings::source_location(&self.source_values, &self.view(), &location)
+    }
+
+    /// Returns the first row of the JSON viewer showing the value
+    /// at the result and the path within it.
+    pub fn row_of(&self, location: &(usize, JsonPath)) -> Option<usize> {
+        let location = settings::view_location(&self.source_values, &self.view(), location)?;
+        marks::find(&self.json_nodes, &location)
+    }
+
+    /// Returns the value starting at the row of the JSON viewer,
+    /// looked up in the values rather than in the nodes shown.
+    pub fn value_at(&self, row: usize) -> anyhow::Result<Value> {
+        self.location_at(row)
+            .and_then(|location| settings::value_at(&self.source_values, &location))
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No value of the results starts at the row"))
+    }
+
+    /// Shows a level more of the nodes, or a level less,
+    /// showing every level once the deepest is reached.
+    pub fn change_depth(&mut self, deeper: bool) {
+        let deepest = self
+            .source_values
+            .iter()
+            .map(settings::depth)
+            .max()
+            .unwrap_or(0);
+        let current = self.max_depth.unwrap_or(deepest);
+        let depth = if deeper {
+            current + 1
+        } else {
+            current.saturating_sub(1).max(1)
+        };
+        self.max_depth = (depth < deepest).then_some(depth);
+        let message = match self.max_depth {
+            Some(depth) => format!("Showing {} of {} levels", depth, deepest),
+            None => "Showing every level".to_string(),
+        };
+        self.show_message(message, Color::Blue);
+        self.reshow();
+    }
+
+    /// Shows the results of the query, marking the rows that were not
+    /// among the results shown before to be highlighted, if enabled.
+    pub fn show_results(&mut self, values: Vec<Value>) {
+        if !self.highlight_changes {
+            self.show_json(values);
+            return;
+        }
+        self.show_json(values);
+        // Compared once every result is laid out.
+        match &mut self.remaining {
+            Some(remaining) => remaining.compare = true,
+            None => self.compare_rows(),
+        }
+    }
+
+    /// Marks the rows shown that were not among those of the previous results.
+    fn compare_rows(&mut self) {
+        // Formatted from the nodes, as the JSON viewer shows them, rather than rendered.
+        let indent = self.json_bundle_snapshot.after().theme.indent;
+        let rows = yank::rows(&self.json_nodes, indent)
+            .into_iter()
+            .map(|row| row.text)
+            .collect::<Vec<_>>();
+        self.changes = Changes::between(&self.result_rows, rows.clone());
+        self.result_rows = rows.into_iter().collect();
+    }
+
+    /// The state of the display option, shown between the brackets.
+    pub fn state(&self, setting: Setting) -> &'static str {
+        let enabled = match setting {
+            Setting::Verbosity => return self.verbosity.name(),
+            Setting::OutputFormat => return self.output_format.name(),
+            Setting::Wrap => self.wrap,
+            Setting::LineNumbers => self.line_numbers,
+            Setting::Hints => self.hint_visible,
+            Setting::Suggestions => self.suggest_visible,
+            Setting::Fullscreen => self.fullscreen,
+            Setting::SortKeys => self.sort_keys,
+            Setting::HideEmpty => self.hide_empty,
+            Setting::SampleArrays => self.sample_arrays,
+            Setting::HighlightChanges => self.highlight_changes,
+            Setting::RawOutput => self.raw_output,
+            Setting::CompactOutput => self.compact_output,
+            Setting::AsciiOutput => self.ascii_output,
+        };
+        if enabled {
+            "x"
+        } else {
+            " "
+        }
+    }
+
+    /// Lists the display options with their states in the overlay,
+    /// keeping the highlighted one.
+    pub fn update_settings(&mut self) {
+        let items = SETTINGS
+            .iter()
+            .map(|setting| format!("[{}] {}", self.state(*setting), setting.label()))
+            .collect::<Vec<_>>();
+        let listbox = &mut self.settings_snapshot.after_mut().listbox;
+        let position = if listbox.items().is_empty() {
+            0
+        } else {
+            listbox.position()
+        };
+        *listbox = Listbox::from_iter(items);
+        for _ in 0..position {
+            listbox.forward();
+        }
+    }
+
+    /// Lists the colors of the theme in the overlay, keeping the highlighted one,
+    /// and applies them to the JSON viewer.
+    pub fn update_theme(&mut self) {
+        let items = ELEMENTS
+            .iter()
+            .map(|element| format!("{:<16} {}", element.key(), self.theme.color_name(*element)))
+            .collect::<Vec<_>>();
+        let listbox = &mut self.theme_snapshot.after_mut().listbox;
+        let position = if listbox.items().is_empty() {
+            0
+        } else {
+            listbox.position()
+        };
+        *listbox = Listbox::from_iter(items);
+        for _ in 0..position {
+            listbox.forward();
+        }
+        self.theme
+            .apply(&mut self.json_bundle_snapshot.after_mut().theme);
+    }
+
+    /// Replaces the input with the file, indexing it for suggestions anew,
+    /// once read and parsed in the background.
+    pub fn load(&mut self, path: &Path) {
+        let read = {
+            let path = path.to_path_buf();
+            move || formats::read_file(&path)
+        };
+        if wake::SUPPORTED {
+            self.switching = Some((path.to_path_buf(), Loading::new(Box::new(read))));
+        } else {
+            // Without a wake-up, the file would only be shown after the next key.
+            self.switched(path.to_path_buf(), read().and_then(Loaded::parse));
+        }
+    }
+
+    /// Whether the paths to suggest are still being indexed in the background,
+    /// with the input or a picked file.
+    pub fn indexing(&self) -> bool {
+        self.loading.is_some() || self.switching.is_some()
+    }
+
+    /// Replaces the input with the picked file once the thread loading it is done.
+    pub fn finish_switch(&mut self) {
+        let Some(loaded) = self
+            .switching
+            .as_ref()
+            .and_then(|(_, loading)| loading.take())
+        else {
+            return;
+        };
+        if let Some((path, _)) = self.switching.take() {
+            self.switched(path, loaded);
+        }
+    }
+
+    fn switched(&mut self, path: PathBuf, loaded: anyhow::Result<Loaded>) {
+        match loaded {
+            Ok(loaded) => {
+                self.source = Some(path.clone());
+                self.install(loaded);
+                self.show_message(format!("Loaded '{}'", path.display()), Color::Green);
+            }
+            Err(e) => self.show_message(format!("{}: {}", path.display(), e), Color::Red),
+        }
+    }
+
+    /// Returns whether the action (e.g. loading a file) may replace the input:
+    /// at once without edits, or else when repeated with the next key
+    /// after a warning that the edits would be discarded.
+    pub fn confirm_discard(&mut self, action: &str, key: &str) -> bool {
+        if self.edits.is_empty() || self.discard_pending.take().as_deref() == Some(action) {
+            return true;
+        }
+        self.discard_pending = Some(action.to_string());
+        let message = format!(
+            "{} discards the {} edits: press {} again to confirm",
+            action,
+            self.edits.len(),
+            key
+        );
+        self.show_message(message, Color::Yellow);
+        false
+    }
+
+    /// Replaces the input with the text read from the file, if any,
+    /// indexing it for suggestions anew.
+    pub fn replace_input(&mut self, input: &str, source: Option<&Path>) -> anyhow::Result<()> {
+        let loaded = Loaded::parse(input.to_string())?;
+        self.source = source.map(Path::to_path_buf);
+        self.install(loaded);
+        Ok(())
+    }
+
+    fn install(&mut self, loaded: Loaded) {
+        let fingerprint = store::fingerprint(&loaded.input);
+        let key = self
+            .source
+            .as_deref()
+            .and_then(store::path_key)
+            .unwrap_or_else(|| fingerprint.clone());
+        self.index_time = loaded.index_time;
+        self.documents = loaded.documents;
+        self.document_position = 0;
+        self.edits.clear();
+        // The pushed queries refined the previous input.
+        self.stack.clear();
+        self.suggest = loaded.suggest;
+        self.suggest_ranking = Ranking::load(&fingerprint);
+        self.query_store = Store::open(super::QUERIES_FILE, &key);
+        self.last_query = self
+            .query_store
+            .load()
+            .and_then(|query| query.as_str().map(|query| query.to_string()));
+        self.loads += 1;
+        self.damage.mark_all();
+    }
+
+    /// Shows the input in place of the placeholder once the thread loading it is done,
+    /// or returns the error reading or parsing it.
+    pub fn finish_loading(&mut self) -> anyhow::Result<()> {
+        let Some(loaded) = self.loading.as_ref().and_then(Loading::take) else {
+            return Ok(());
+        };
+        self.loading = None;
+        let loaded = loaded?;
+        report::record_input(&loaded.input, &loaded.documents, loaded.suggest.len());
+        if let Some(refresh) = &self.refresh {
+            refresh.seen(&store::fingerprint(&loaded.input));
+        }
+        self.install(loaded);
+        if let Some(query) = self
+            .last_query
+            .as_ref()
+            .filter(|_| self.verbosity >= Verbosity::Normal)
+        {
+            let message = format!("Alt + U restores the last filter: '{}'", query);
+            self.show_message(message, Color::Blue);
+        }
+        self.hooks
+            .notify("on_start", &[], self.documents[0].clone());
+        Ok(())
+    }
+
+    /// Makes the results of the query on the shown document, or on every document
+    /// (e.g. the root of each envelope), the documents to refine,
+    /// returning the number of queries pushed.
+    pub fn push(&mut self, query: &str, every_document: bool) -> anyhow::Result<usize> {
+        if !self.edits.is_empty() {
+            anyhow::bail!("Edited values can't be refined: accept them first");
+        }
+        let mut results = Vec::new();
+        let mut document_position = 0;
+        if every_document {
+            // The documents without the value (e.g. at a missing path) are skipped.
+            let query = step(query, true, 0, 0);
+            for (i, document) in self.documents.iter().enumerate() {
+                // The first result of the shown document, or else of the next one.
+                if i == self.document_position {
+                    document_position = results.len();
+                }
+                results.extend(super::run_jq(&query, document)?);
+            }
+        } else {
+            results = super::run_jq(query, &self.documents[self.document_position])?;
+        }
+        if results.is_empty() {
+            anyhow::bail!("No results to refine");
+        }
+        let document_position = document_position.min(results.len() - 1);
+        let start = Instant::now();
+        let suggest = super::index(&results)?;
+        self.index_time = start.elapsed();
+        self.stack.push(Level {
+            query: query.to_string(),
+            every_document,
+            documents: std::mem::replace(&mut self.documents, results),
+            document_position: std::mem::replace(&mut self.document_position, document_position),
+            suggest: std::mem::replace(&mut self.suggest, suggest),
+        });
+        self.loads += 1;
+        Ok(self.stack.len())
+    }
+
+    /// Goes back to the documents the last pushed query ran on, returning the query.
+    pub fn pop(&mut self) -> anyhow::Result<Option<String>> {
+        if !self.edits.is_empty() {
+            anyhow::bail!("Edited values would be lost: accept them first");
+        }
+        let Some(level) = self.stack.pop() else {
+            return Ok(None);
+        };
+        self.documents = level.documents;
+        self.document_position = level.document_position;
+        self.suggest = level.suggest;
+        self.loads += 1;
+        Ok(Some(level.query))
+    }
+
+    /// Composes the pushed queries and the query into the pipeline
+    /// producing the same results from the input (e.g. `.items[] | .name`).
+    /// A query with several results on the shown document is narrowed
+    /// to the one refined (e.g. `[.[]][2]`), and the input stream to the document
+    /// the first query ran on (e.g. `[., inputs][1]`).
+    pub fn pipeline(&self, query: &str) -> String {
+        let input = self
+            .stack
+            .first()
+            .filter(|level| !level.every_document && level.documents.len() > 1)
+            .map(|level| format!("[., inputs][{}]", level.document_position));
+        let steps = self.stack.iter().enumerate().map(|(i, level)| {
+            let (results, position) = match self.stack.get(i + 1) {
+                Some(next) => (next.documents.len(), next.document_position),
+                None => (self.documents.len(), self.document_position),
+            };
+            step(&level.query, level.every_document, results, position)
+        });
+        compose(input.into_iter().chain(steps), query)
+    }
+
+    /// Loads the input read again if it has changed, keeping the document and the row
+    /// at the cursor where possible, and runs the pushed queries again on it.
+    ///
+    /// The edits apply to the input loaded before, so while there are any
+    /// the input is held back with a warning, and only loaded with `discard_edits`.
+    pub fn refresh(&mut self, discard_edits: bool) -> anyhow::Result<()> {
+        let Some(refresh) = &mut self.refresh else {
+            return Ok(());
+        };
+        if let Some(input) = refresh.take()? {
+            refresh.held = Some(input);
+        }
+        if !self.edits.is_empty() && !discard_edits {
+            if refresh.held.is_some() {
+                self.show_message(
+                    "The input changed: save the edits, or Ctrl + R to discard them and load it",
+                    Color::Yellow,
+                );
+            }
+            return Ok(());
+        }
+        let Some(input) = refresh.held.take() else {
+            return Ok(());
+        };
+        let documents = super::split_documents(&input)?;
+        if documents.is_empty() {
+            anyhow::bail!("No data found");
+        }
+        // Back to the input the pushed queries ran on.
+        let shown = self.document_position;
+        let levels = std::mem::take(&mut self.stack);
+        let mut pushed = Vec::new();
+        for level in levels.into_iter().rev() {
+            pushed.push((level.query, level.every_document, level.document_position));
+            self.documents = level.documents;
+            self.document_position = level.document_position;
+            self.suggest = level.suggest;
+        }
+        let start = Instant::now();
+        // Inputs followed while they grow (e.g. logs) only gain documents at the end,
+        // whose paths are added to the index instead of indexing every document again.
+        let kept = self.documents.len();
+        if kept < documents.len() && documents[..kept] == self.documents[..] {
+            super::extend_index(&mut self.suggest, &documents[kept..])?;
+        } else {
+            self.suggest = super::index(&documents)?;
+        }
+        self.index_time = start.elapsed();
+
+        // The ranking and the last query are kept for the original input,
+        // since a live input changes on every refresh.
+        self.document_position = self.document_position.min(documents.len() - 1);
+        self.documents = documents;
+        self.edits.clear();
+        self.pending_row = self.json_row;
+        self.loads += 1;
+
+        for (query, every_document, position) in pushed.into_iter().rev() {
+            self.document_position = position.min(self.documents.len() - 1);
+            self.push(&query, every_document)?;
+        }
+        self.document_position = shown.min(self.documents.len() - 1);
+        Ok(())
+    }
+
+    /// Handles the requests received on the control socket.
+    #[cfg(unix)]
+    pub fn handle_requests(&mut self) {
+        let requests = match &self.control {
+            Some(control) => control.pending(),
+            None => return,
+        };
+        for request in requests {
+            self.damage.mark_all();
+            let result = self.handle_request(&request.method, &request.params);
+            let _ = request.reply.send(result.map_err(|e| e.to_string()));
+        }
+    }
+
+    #[cfg(unix)]
+    fn handle_request(
+        &mut self,
+        method: &str,
+        params: &serde_json::Value,
+    ) -> anyhow::Result<serde_json::Value> {
+        if self.editor_mode.is_some() {
+            anyhow::bail!("jnv is waiting for an answer in the interface");
+        }
+        let param = |name: &str| {
+            params
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("The parameter '{}' is missing", name))
+        };
+        let editor = &mut self.query_editor_snapshot.after_mut().texteditor;
+        match method {
+            "set_query" => {
+                let query = param("query")?
+                    .as_str()
+                    .ok_or_else(|| anyhow::anyhow!("The query must be a string"))?;
+                editor.replace(query);
+                Ok(serde_json::Value::Null)
+            }
+            "get_query" => Ok(serde_json::Value::String(
+                editor.text_without_cursor().to_string(),
+            )),
+            "get_result" => {
+                let query = editor.text_without_cursor().to_string();
+                let results = super::run_jq(&query, &self.documents[self.document_position])?;
+                Ok(serde_json::Value::Array(
+                    results
+                        .iter()
+                        .map(|result| serde_json::from_str(result))
+                        .collect::<Result<_, _>>()?,
+                ))
+            }
+            "load" => {
+                let path = param("path")?
+                    .as_str()
+                    .ok_or_else(|| anyhow::anyhow!("The path must be a string"))?;
+                if !self.edits.is_empty() {
+                    anyhow::bail!("Loading a file would discard the edits");
+                }
+                // Loaded at once, for the client to know the outcome.
+                let path = Path::new(path);
+                formats::read_file(path)
+                    .and_then(|input| self.replace_input(&input, Some(path)))
+                    .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+                Ok(serde_json::Value::Null)
+            }
+            "scroll" => {
+                let row = param("row")?
+                    .as_u64()
+                    .ok_or_else(|| anyhow::anyhow!("The row must be a number"))?;
+                // Moved after the query is evaluated, which may reset the cursor.
+                self.pending_row = Some(row as usize);
+                Ok(serde_json::Value::Null)
+            }
+            _ => anyhow::bail!("Unknown method '{}'", method),
+        }
+    }
+
+    /// Shows the tab at the position, opening a new tab
+    /// (with the identity query) if it is past the last one.
+    pub fn switch_tab(&mut self, position: usize) {
+        if position == self.tab {
+            return;
+        }
+        let editor = &mut self.query_editor_snapshot.after_mut().texteditor;
+        self.tabs[self.tab] = Tab {
+            query: editor.text_without_cursor().to_string(),
+            row: self.json_row,
+        };
+        if position >= self.tabs.len() {
+            self.tabs.push(Tab {
+                query: ".".to_string(),
+                row: None,
+            });
+        }
+        self.tab = position.min(self.tabs.len() - 1);
+        editor.replace(&self.tabs[self.tab].query);
+        self.pending_row = self.tabs[self.tab].row;
+    }
+
+    /// Pins the query to compare the results with,
+    /// or unpins it if a query is already pinned.
+    pub fn toggle_pin(&mut self) -> anyhow::Result<()> {
+        if self.pinned.take().is_some() {
+            self.diff_snapshot.after_mut().listbox = Listbox::from_iter(Vec::<String>::new());
+            return Ok(());
+        }
+        let query = self
+            .query_editor_snapshot
+            .after()
+            .texteditor
+            .text_without_cursor()
+            .to_string();
+        let results = super::run_jq(&query, &self.documents[self.document_position]);
+        self.pinned = Some((query, parse_results(results.as_ref())?));
+        self.update_diff(&results);
+        Ok(())
+    }
+
+    /// Shows the differences between the results of the pinned query
+    /// and the results of the query, as evaluated.
+    pub fn update_diff(&mut self, results: &anyhow::Result<Vec<String>>) {
+        let Some((pinned_query, pinned)) = &self.pinned else {
+            return;
+        };
+        let lines = match parse_results(results.as_ref()) {
+            Ok(results) => diff::diff(pinned_query, pinned, &results),
+            Err(e) => vec![e.to_string()],
+        };
+        self.diff_snapshot.after_mut().listbox = Listbox::from_iter(lines);
+        self.damage.mark(PaneIndex::Result);
+    }
+
+    /// Moves the cursor of the JSON viewer to the row.
+    pub fn move_json_cursor(&mut self, row: usize) {
+        let bundle = &mut self.json_bundle_snapshot.after_mut().bundle;
+        bundle.move_to_head();
+        for _ in 0..row {
+            bundle.forward();
+        }
+        self.json_row = Some(row);
+        self.damage.mark(PaneIndex::Result);
+    }
+
+    /// Moves the cursor of the JSON viewer to the value at the path in the first result,
+    /// or to its closest ancestor shown if the value is folded.
+    pub fn move_json_cursor_to(&mut self, path: &JsonPath) {
+        if let Some(row) = (0..=path.len())
+            .rev()
+            .find_map(|len| self.row_of(&(0, path[..len].to_vec())))
+        {
+            self.move_json_cursor(row);
+        }
+    }
+
+    /// Updates the status line with the displayed document of the input stream
+    /// and the position of the cursor in the JSON viewer.
+    pub fn update_status(&mut self) {
+        let mut status = Vec::new();
+        if let Some(summary) = &self.result_summary {
+            status.push(summary.clone());
+        }
+        if self.documents.len() > 1 {
+            status.push(format!(
+                "document {}/{}",
+                self.document_position + 1,
+                self.documents.len()
+            ));
+        }
+        match (self.json_row, self.json_rows) {
+            (Some(row), Some(rows)) if rows > 0 => status.push(format!(
+                "row {}/{} ({}%)",
+                row + 1,
+                rows,
+                (row + 1) * 100 / rows
+            )),
+            (Some(row), _) => status.push(format!("row {}", row + 1)),
+            (None, Some(rows)) => status.push(format!("{} rows", rows)),
+            (None, None) => {}
+        }
+        if self.tabs.len() > 1 {
+            status.insert(0, format!("tab {}/{}", self.tab + 1, self.tabs.len()));
+        }
+        if !self.stack.is_empty() {
+            status.insert(0, format!("refining {}", self.pipeline("")));
+        }
+        if self.loading.is_some() {
+            status.push("loading…".to_string());
+        }
+        if let Some((path, _)) = &self.switching {
+            status.push(format!("loading '{}'…", path.display()));
+        }
+        if self.remaining.is_some() {
+            status.push("laying out the results…".to_string());
+        }
+        if let Some(refresh) = &self.refresh {
+            status.push(refresh.status());
+        }
+        if let Some(name) = self.macros.recording() {
+            status.push(format!("recording @{}", name));
+        }
+        if let (Some(anchor), Some(row)) = (self.visual_anchor, self.json_row) {
+            status.push(format!(
+                "selecting rows {}-{}",
+                anchor.min(row) + 1,
+                anchor.max(row) + 1
+            ));
+        }
+        if let Some(pattern) = &self.key_filter {
+            status.push(format!("keys matching '{}'", pattern));
+        }
+        let status = status.join("  ");
+        if self.status_snapshot.after().text != status {
+            self.status_snapshot.after_mut().text = status;
+            self.damage.mark(PaneIndex::Prompt);
+        }
+    }
+
+    /// Uses the query editor for the mode, replacing the query with the text.
+    pub fn enter_mode(&mut self, mode: EditorMode, text: &str) {
+        self.query_editor_snapshot
+            .after_mut()
+            .texteditor
+            .replace(text);
+        self.keymap.switch(mode.keymap());
+        self.editor_mode = Some(mode);
+    }
+
+    /// Gives the query editor back to the query shown before the mode,
+    /// returning the mode left, if any.
+    pub fn leave_mode(&mut self) -> Option<EditorMode> {
+        self.keymap.switch("default");
+        let mode = self.editor_mode.take()?;
+        let texteditor = &mut self.query_editor_snapshot.after_mut().texteditor;
+        texteditor.replace(mode.query());
+        if let EditorMode::Help { query, cursor } = &mode {
+            for _ in *cursor..query.chars().count() {
+                texteditor.backward();
+            }
+        }
+        Some(mode)
+    }
+
+    /// Shows the message in the hint pane until the query is evaluated next.
+    pub fn show_message<S: Into<String>>(&mut self, text: S, color: Color) {
+        let text = text.into();
+        report::log_message(&text);
+        self.hint_message_snapshot
+            .after_mut()
+            .replace(text::Renderer {
+                text,
+                style: StyleBuilder::new()
+                    .fgc(color)
+                    .attrs(Attributes::from(Attribute::Bold))
+                    .build(),
+            });
+        self.damage.mark(PaneIndex::Prompt);
+    }
+
+    /// Copies the text to the clipboard of the terminal, unless headless.
+    pub fn copy_to_clipboard(&self, text: &str) -> io::Result<()> {
+        if self.headless {
+            return Ok(());
+        }
+        yank::copy_to_clipboard(text)
+    }
+
+    /// Pages through the text, unless headless.
+    pub fn page(&self, text: String) -> anyhow::Result<()> {
+        if self.headless {
+            return Ok(());
+        }
+        pager::show(text)
+    }
+
+    /// Whether the pager and the exports are colored.
+    pub fn colored(&self) -> bool {
+        match self.color {
+            ColorChoice::Auto => std::env::var_os("NO_COLOR").is_none(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+
+    /// Fits the JSON viewer into the space left by the other panes.
+    ///
+    /// Panes are drawn from the top and the JSON viewer would otherwise
+    /// take up the rest of the terminal, pushing the panes below it
+    /// off the screen in the bottom layout.
+    pub fn fit_layout(&mut self) -> io::Result<()> {
+        if self.layout == Layout::Top {
+            return Ok(());
+        }
+        if self.fullscreen {
+            self.fit_json(self.result_lines);
+            return Ok(());
+        }
+        let (width, height) = match self.size {
+            Some(size) => size,
+            None => terminal::size()?,
+        };
+        let rows = |columns: usize| columns.div_ceil(width.max(1) as usize).max(1);
+
+        let editor = self.query_editor_snapshot.after();
+        let mut reserved = rows(
+            grapheme::width(&editor.prefix)
+                + grapheme::width(&editor.texteditor.text_without_cursor().to_string())
+                + 1,
+        )
+        .min(editor.lines.unwrap_or(usize::MAX));
+        if !self.status_snapshot.after().text.is_empty() {
+            reserved += 1;
+        }
+        if self.hud_visible {
+            reserved += 1;
+        }
+        let hint = &self.hint_message_snapshot.after().text;
+        if self.hint_visible && !hint.is_empty() {
+            reserved += rows(grapheme::width(hint)).min(self.hint_lines.unwrap_or(usize::MAX));
+        }
+        let suggest = self.suggest_snapshot.after();
+        if self.suggest_visible {
+            reserved += suggest
+                .listbox
+                .items()
+                .len()
+                .min(suggest.lines.unwrap_or(usize::MAX));
+            reserved += self.suggest_preview_snapshot.after().listbox.items().len();
+        }
+        let picker = self.picker_snapshot.after();
+        reserved += picker
+            .listbox
+            .items()
+            .len()
+            .min(picker.lines.unwrap_or(usize::MAX));
+        if self.settings_visible {
+            reserved += SETTINGS.len();
+        }
+        if self.theme_visible {
+            reserved += ELEMENTS.len();
+        }
+        let help = self.help_snapshot.after();
+        reserved += help
+            .listbox
+            .items()
+            .len()
+            .min(help.lines.unwrap_or(usize::MAX));
+        reserved += self.analysis_snapshot.after().listbox.items().len();
+        reserved += self.scratch_snapshot.after().listbox.items().len();
+        reserved += self.diff_snapshot.after().listbox.items().len();
+
+        let available = (height as usize).saturating_sub(reserved).max(1);
+        self.fit_json(Some(
+            self.result_lines
+                .map_or(available, |lines| lines.min(available)),
+        ));
+        Ok(())
+    }
+
+    fn fit_json(&mut self, lines: Option<usize>) {
+        if self.json_bundle_snapshot.after().theme.lines != lines {
+            self.json_bundle_snapshot.after_mut().theme.lines = lines;
+            self.damage.mark(PaneIndex::Result);
+        }
+    }
+
+    /// Scrolls the JSON viewer horizontally by `SCROLL_COLUMNS`,
+    /// keeping the end of the widest row shown in view.
+    pub fn scroll_horizontally(&mut self, forward: bool) {
+        if !forward {
+            self.column = self.column.saturating_sub(SCROLL_COLUMNS);
+            return;
+        }
+        let widest = self
+            .json_bundle_snapshot
+            .create_panes(u16::MAX)
+            .iter()
+            .flat_map(|pane| pane.extract(usize::MAX))
+            .map(|row| row.0.iter().map(|g| g.width()).sum::<usize>())
+            .max()
+            .unwrap_or(0);
+        self.column = (self.column + SCROLL_COLUMNS).min(widest.saturating_sub(1));
+    }
+
+    /// Drops the columns scrolled past from the rows of the JSON viewer.
+    fn scroll_columns(&self, panes: Vec<Pane>) -> Vec<Pane> {
+        if self.wrap || self.column == 0 {
+            return panes;
+        }
+        panes
+            .into_iter()
+            .map(|pane| {
+                let rows = pane
+                    .extract(usize::MAX)
+                    .into_iter()
+                    .map(|row| {
+                        let skipped =
+                            grapheme::columns_skipped(row.0.iter().map(|g| g.width()), self.column);
+                        row.0.into_iter().skip(skipped).collect()
+                    })
+                    .collect();
+                Pane::new(rows, 0, None)
+            })
+            .collect()
+    }
+}
+
+impl Renderer {
+    /// Numbers the rows of the JSON viewer, shown from the cursor on, in a gutter,
+    /// wrapping them within the rest of the width if long lines wrap.
+    /// The rows are left unnumbered while the row of the cursor is unknown.
+    fn number_rows(&self, panes: Vec<Pane>, width: u16) -> Vec<Pane> {
+        let rows = panes
+            .into_iter()
+            .flat_map(|pane| pane.extract(usize::MAX))
+            .collect::<Vec<_>>();
+        let last = self
+            .json_rows
+            .unwrap_or(0)
+            .max(self.json_row.unwrap_or(0) + rows.len());
+        let digits = last.to_string().len();
+        let blank = " ".repeat(digits + 1);
+        let style = StyleBuilder::new().fgc(Color::DarkGrey).build();
+        let columns = (width as usize).saturating_sub(digits + 1);
+        let mut lines = Vec::new();
+        for (i, row) in rows.into_iter().enumerate() {
+            let number = match self.json_row {
+                Some(first) => format!("{:>digits$} ", first + i + 1, digits = digits),
+                None => blank.clone(),
+            };
+            let chunks = if self.wrap {
+                grapheme::wrap_columns(row.0.iter().map(|g| g.width()), columns)
+            } else {
+                vec![row.0.len()]
+            };
+            let mut graphemes = row.0.into_iter();
+            for (j, len) in chunks.into_iter().enumerate() {
+                let gutter = if j == 0 { &number } else { &blank };
+                let mut line = StyledGraphemes::from_str(gutter, style);
+                line.0.extend(graphemes.by_ref().take(len));
+                lines.push(line);
+            }
+        }
+        vec![Pane::new(lines, 0, None)]
+    }
+}
+
+/// Parses the results of a query, or describes why it failed.
+fn parse_results(results: Result<&Vec<String>, &anyhow::Error>) -> anyhow::Result<Vec<Value>> {
+    match results {
+        Ok(results) => results
+            .iter()
+            .map(|result| Ok(serde_json::from_str(result)?))
+            .collect(),
+        Err(e) => Err(anyhow::anyhow!("{}", e)),
+    }
+}
+
+/// The step of the pipeline for a pushed query, narrowed to the result refined
+/// when it ran on the document shown and produced several results,
+/// or leaving out null when it ran on every document.
+fn step(query: &str, every_document: bool, results: usize, position: usize) -> String {
+    if every_document {
+        format!("{} | values", query)
+    } else if results > 1 {
+        format!("[{}][{}]", query, position)
+    } else {
+        query.to_string()
+    }
+}
+
+/// Pipes the steps into one another and into the query, which is left out
+/// when it only passes the results of the steps through.
+fn compose(steps: impl IntoIterator<Item = String>, query: &str) -> String {
+    let pipeline = steps.into_iter().fold(String::new(), |pipeline, step| {
+        wizard::pipe(&pipeline, &step)
+    });
+    match query.trim() {
+        "" | "." if !pipeline.is_empty() => pipeline,
+        query => wizard::pipe(&pipeline, query),
+    }
+}
+
+impl Renderer {
+    /// Underlines the part of each suggestion matching the token,
+    /// which is the start of the suggestion.
+    fn highlight_matches(&self, panes: Vec<Pane>) -> Vec<Pane> {
+        let suggest = self.suggest_snapshot.after();
+        let token = self.suggest_token.to_lowercase();
+        let length = token.chars().count();
+        if length == 0 {
+            return panes;
+        }
+        let offset = suggest.cursor.chars().count();
+        let styled = |mut style: ContentStyle| {
+            style.attributes.set(Attribute::Underlined);
+            style.attributes.set(Attribute::Bold);
+            style
+        };
+        let active = styled(suggest.active_item_style);
+        let inactive = styled(suggest.inactive_item_style);
+        panes
+            .into_iter()
+            .map(|pane| {
+                let rows = pane
+                    .extract(usize::MAX)
+                    .into_iter()
+                    .map(|row| {
+                        let text = row.to_string();
+                        let item = text.chars().skip(offset).collect::<String>();
+                        if !item.to_lowercase().starts_with(&token) {
+                            return row;
+                        }
+                        let style = if text.starts_with(&suggest.cursor) {
+                            active
+                        } else {
+                            inactive
+                        };
+                        (offset..offset + length).fold(row, |row, i| row.apply_style_at(i, style))
+                    })
+                    .collect();
+                Pane::new(rows, 0, None)
+            })
+            .collect()
+    }
+}
+
+impl Renderer {
+    /// Lays out the query editor and the panes about it, in order from the top.
+    fn prompt_panes(&self, width: u16) -> Vec<Pane> {
+        let mut prompt = self.query_editor_snapshot.create_panes(width);
+        if !self.status_snapshot.after().text.is_empty() {
+            prompt.extend(self.status_snapshot.create_panes(width));
+        }
+        if self.hud_visible {
+            let hud = text::Renderer {
+                text: self.metrics.summary(self.suggest.len()),
+                style: StyleBuilder::new().fgc(Color::DarkYellow).build(),
+            };
+            prompt.extend(promkit::Renderer::create_panes(&hud, width));
+        }
+        if self.hint_visible {
+            let hint = self.hint_message_snapshot.create_panes(width);
+            match self.hint_lines {
+                Some(lines) => prompt.extend(
+                    hint.into_iter()
+                        .map(|pane| Pane::new(pane.extract(lines), 0, None)),
+                ),
+                None => prompt.extend(hint),
+            }
+        }
+        if self.suggest_visible {
+            prompt.extend(self.highlight_matches(self.suggest_snapshot.create_panes(width)));
+            prompt.extend(self.suggest_preview_snapshot.create_panes(width));
+        }
+        prompt.extend(self.picker_snapshot.create_panes(width));
+        if self.settings_visible {
+            prompt.extend(self.settings_snapshot.create_panes(width));
+        }
+        if self.theme_visible {
+            prompt.extend(self.theme_snapshot.create_panes(width));
+        }
+        prompt.extend(self.help_snapshot.create_panes(width));
+        prompt
+    }
+
+    /// Lays out the JSON viewer and the panes over it,
+    /// or the JSON viewer alone in fullscreen.
+    fn result_panes(&self, width: u16) -> Vec<Pane> {
+        // Without wrapping, rows are laid out as if the terminal were
+        // infinitely wide, and the terminal cuts them off at its edge.
+        let json_width = if self.wrap { width } else { u16::MAX };
+        if self.fullscreen && self.line_numbers {
+            let json = self.json_bundle_snapshot.create_panes(u16::MAX);
+            return self.number_rows(self.scroll_columns(json), width);
+        }
+        if self.fullscreen {
+            return self.scroll_columns(self.json_bundle_snapshot.create_panes(json_width));
+        }
+
+        let mut result = self.analysis_snapshot.create_panes(width);
+        result.extend(self.scratch_snapshot.create_panes(width));
+        result.extend(self.diff_snapshot.create_panes(width));
+        // The gutter is laid out with the rows, which are wrapped after it.
+        let json = self
+            .json_bundle_snapshot
+            .create_panes(if self.line_numbers {
+                u16::MAX
+            } else {
+                json_width
+            });
+        let json = match self.changes.as_ref().filter(|changes| changes.is_active()) {
+            Some(changes) => changes.highlight(json),
+            None => json,
+        };
+        let json = self.scroll_columns(json);
+        if self.line_numbers {
+            result.extend(self.number_rows(json, width));
+        } else {
+            result.extend(json);
+        }
+        result
+    }
+}
+
 impl promkit::Renderer for Renderer {
     fn create_panes(&self, width: u16) -> Vec<Pane> {
-        let mut panes = Vec::new();
-        panes.extend(self.query_editor_snapshot.create_panes(width));
-        panes.extend(self.hint_message_snapshot.create_panes(width));
-        panes.extend(self.suggest_snapshot.create_panes(width));
-        panes.extend(self.json_bundle_snapshot.create_panes(width));
-        panes
+        self.metrics.frame();
+        self.damage.drawn();
+        let result = self
+            .damage
+            .panes(PaneIndex::Result, width, || self.result_panes(width));
+        if self.fullscreen {
+            return result;
+        }
+        let mut prompt = self
+            .damage
+            .panes(PaneIndex::Prompt, width, || self.prompt_panes(width));
+
+        match self.layout {
+            Layout::Top => prompt.into_iter().chain(result).collect(),
+            Layout::Bottom => {
+                // Mirrored, so that the query editor is on the bottom line.
+                prompt.reverse();
+                result.into_iter().chain(prompt).collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_narrows_several_results_of_the_document_shown() {
+        assert_eq!(step(".[]", false, 3, 2), "[.[]][2]");
+        assert_eq!(step(".[]", false, 1, 0), ".[]");
+        assert_eq!(step(".data", true, 3, 2), ".data | values");
+    }
+
+    #[test]
+    fn compose_pipes_the_steps_into_the_query() {
+        let steps = || vec![".items".to_string(), "[.[]][1]".to_string()];
+        assert_eq!(compose(steps(), ".name"), ".items | [.[]][1] | .name");
+        assert_eq!(compose(steps(), " . "), ".items | [.[]][1]");
+        assert_eq!(compose(Vec::new(), "."), ".");
+        assert_eq!(compose(Vec::new(), ""), "");
     }
 }
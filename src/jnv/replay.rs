@@ -0,0 +1,95 @@
+use anyhow::{anyhow, Result};
+use promkit::{
+    crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    serde_json::{self, Value},
+};
+
+/// Parses a script of events, one JSON object per line:
+/// - `{"key": "Alt+k"}` presses the key with the modifiers
+///   (`Ctrl`, `Alt` or `Shift`, e.g. `Enter`, `Tab`, `Down` or `a`).
+/// - `{"text": ".items"}` types the text.
+/// - `{"paste": ".items[0]"}` pastes the text.
+/// - `{"resize": [120, 40]}` resizes the terminal.
+///
+/// Blank lines and lines starting with `#` are skipped.
+pub fn parse_events(script: &str) -> Result<Vec<Event>> {
+    let mut events = Vec::new();
+    for (i, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        parse_line(line, &mut events).map_err(|e| anyhow!("line {}: {}", i + 1, e))?;
+    }
+    Ok(events)
+}
+
+fn parse_line(line: &str, events: &mut Vec<Event>) -> Result<()> {
+    let value = serde_json::from_str::<Value>(line)?;
+    if let Some(key) = value.get("key").and_then(Value::as_str) {
+        events.push(Event::Key(parse_key(key)?));
+    } else if let Some(text) = value.get("text").and_then(Value::as_str) {
+        events.extend(
+            text.chars()
+                .map(|ch| Event::Key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE))),
+        );
+    } else if let Some(text) = value.get("paste").and_then(Value::as_str) {
+        events.push(Event::Paste(text.to_string()));
+    } else if let Some([width, height]) = value
+        .get("resize")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+    {
+        let size = |value: &Value| {
+            value
+                .as_u64()
+                .and_then(|n| u16::try_from(n).ok())
+                .ok_or_else(|| anyhow!("The size must be two numbers"))
+        };
+        events.push(Event::Resize(size(width)?, size(height)?));
+    } else {
+        return Err(anyhow!(
+            "Expected one of 'key', 'text', 'paste' or 'resize'"
+        ));
+    }
+    Ok(())
+}
+
+fn parse_key(key: &str) -> Result<KeyEvent> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut name = key;
+    while let Some((modifier, rest)) = name.split_once('+').filter(|(_, rest)| !rest.is_empty()) {
+        modifiers |= match modifier.to_lowercase().as_str() {
+            "ctrl" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return Err(anyhow!("Unknown modifier '{}'", modifier)),
+        };
+        name = rest;
+    }
+    let code = match name.to_lowercase().as_str() {
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "space" => KeyCode::Char(' '),
+        _ => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => KeyCode::Char(ch),
+                _ => return Err(anyhow!("Unknown key '{}'", name)),
+            }
+        }
+    };
+    Ok(KeyEvent::new(code, modifiers))
+}
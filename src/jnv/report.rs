@@ -0,0 +1,224 @@
+use std::{
+    collections::VecDeque,
+    fmt::Write as _,
+    fs,
+    io::IsTerminal,
+    path::Path,
+    sync::{Mutex, OnceLock},
+    time::Instant,
+};
+
+use anyhow::Result;
+use promkit::crossterm::terminal;
+
+/// The number of messages kept for the report, the last ones.
+const LOGGED_MESSAGES: usize = 50;
+
+/// What is known of the session, for the report of an unexpected error.
+struct Session {
+    started: Option<Instant>,
+    /// The statistics of the input, one per line (e.g. `documents: 3`).
+    shape: Vec<String>,
+    /// The input itself, only kept if it may be included.
+    data: Option<String>,
+    /// The last query evaluated.
+    query: String,
+    /// The last messages shown, with the time since the start.
+    log: VecDeque<String>,
+}
+
+static SESSION: Mutex<Session> = Mutex::new(Session {
+    started: None,
+    shape: Vec::new(),
+    data: None,
+    query: String::new(),
+    log: VecDeque::new(),
+});
+
+/// Whether the input is included in the reports, as opted in.
+static INCLUDE_DATA: OnceLock<bool> = OnceLock::new();
+
+/// Includes the input itself in the reports of unexpected errors, once for the process.
+pub fn include_data_in_reports() {
+    let _ = INCLUDE_DATA.set(true);
+}
+
+fn includes_data() -> bool {
+    INCLUDE_DATA.get().copied().unwrap_or(false)
+}
+
+/// Records the size and shape of the input, split into the documents,
+/// with the number of paths indexed in them.
+pub fn record_input(input: &str, documents: &[String], paths: usize) {
+    let largest = documents.iter().map(String::len).max().unwrap_or(0);
+    let shape = vec![
+        format!("bytes: {}", input.len()),
+        format!("documents: {}", documents.len()),
+        format!("largest document: {} bytes", largest),
+        format!("deepest nesting: {}", nesting(input)),
+        format!("paths: {}", paths),
+    ];
+    let mut session = SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    session.started = Some(Instant::now());
+    session.shape = shape;
+    session.data = includes_data().then(|| input.to_string());
+}
+
+pub fn record_query(query: &str) {
+    let mut session = SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    query.clone_into(&mut session.query);
+}
+
+/// Records a message shown to the user (e.g. a failed export),
+/// with the strings in it redacted unless the input may be included.
+pub fn log_message(message: &str) {
+    let mut session = SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    let elapsed = session.started.map(|started| started.elapsed());
+    let message = if includes_data() {
+        message.to_string()
+    } else {
+        redact(message)
+    };
+    if session.log.len() == LOGGED_MESSAGES {
+        session.log.pop_front();
+    }
+    session.log.push_back(match elapsed {
+        Some(elapsed) => format!("[{:>8.1?}] {}", elapsed, message),
+        None => message,
+    });
+}
+
+/// Returns the deepest nesting of objects and arrays in the JSON text,
+/// counting the brackets outside strings rather than parsing it again.
+fn nesting(input: &str) -> usize {
+    let (mut depth, mut deepest) = (0usize, 0);
+    let (mut in_string, mut escaped) = (false, false);
+    for byte in input.bytes() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => {
+                depth += 1;
+                deepest = deepest.max(depth);
+            }
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    deepest
+}
+
+/// Replaces the text between double or single quotes, and the numbers, with `…`,
+/// since they may be values of the input (e.g. `Copied "a@b.c"` or `row 1200`).
+fn redact(message: &str) -> String {
+    let chars = message.chars().collect::<Vec<_>>();
+    let mut ret = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        // Not an apostrophe (e.g. `can't`), nor a digit of a name (e.g. `utf8`).
+        let in_word = i > 0 && (chars[i - 1].is_alphanumeric() || chars[i - 1] == '_');
+        if c == '"' || (c == '\'' && !in_word) {
+            // Only the text followed by a closing quote is quoted.
+            if let Some(len) = chars[i + 1..].iter().position(|&close| close == c) {
+                ret.extend([c, '…', c]);
+                i += len + 2;
+                continue;
+            }
+        } else if c.is_ascii_digit() && !in_word {
+            while chars.get(i).is_some_and(|c| {
+                c.is_ascii_digit()
+                    || (*c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+            }) {
+                i += 1;
+            }
+            ret.push('…');
+            continue;
+        }
+        ret.push(c);
+        i += 1;
+    }
+    ret
+}
+
+/// Writes a Markdown report of the error to attach to a GitHub issue:
+/// the version, the terminal, the shape of the input, the last query
+/// and the last messages shown, and the input itself only if opted in.
+pub fn write_report(path: &Path, error: &anyhow::Error) -> Result<()> {
+    let session = SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    let mut report = String::from("# jnv diagnostic report\n\n");
+    writeln!(report, "- version: {}", env!("CARGO_PKG_VERSION"))?;
+    writeln!(
+        report,
+        "- platform: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )?;
+    for name in ["TERM", "TERM_PROGRAM", "COLORTERM"] {
+        if let Ok(value) = std::env::var(name) {
+            writeln!(report, "- {}: {}", name, value)?;
+        }
+    }
+    if let Ok((width, height)) = terminal::size() {
+        writeln!(report, "- terminal size: {}x{}", width, height)?;
+    }
+    writeln!(
+        report,
+        "- output redirected: {}",
+        !std::io::stdout().is_terminal()
+    )?;
+
+    writeln!(
+        report,
+        "\n## Error\n\n```\n{}\n```",
+        redact(&format!("{:?}", error))
+    )?;
+    writeln!(report, "\n## Query\n\n```jq\n{}\n```", session.query)?;
+    writeln!(report, "\n## Input\n")?;
+    for line in &session.shape {
+        writeln!(report, "- {}", line)?;
+    }
+    writeln!(report, "\n## Messages\n\n```")?;
+    for line in &session.log {
+        writeln!(report, "{}", line)?;
+    }
+    writeln!(report, "```")?;
+    match &session.data {
+        Some(data) => writeln!(report, "\n## Data\n\n```json\n{}\n```", data.trim_end())?,
+        None => writeln!(
+            report,
+            "\nThe input is left out. Run jnv with --report-data to include it."
+        )?,
+    }
+    fs::write(path, report)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_hides_quoted_values_and_numbers() {
+        assert_eq!(
+            redact(r#"Copied "a@b.c" to row 1200"#),
+            r#"Copied "…" to row …"#
+        );
+        assert_eq!(
+            redact("Failed to execute jq query '.price > 9.5' at line 3"),
+            "Failed to execute jq query '…' at line …"
+        );
+        assert_eq!(
+            redact(r#"can't decode utf8 in "unclosed"#),
+            r#"can't decode utf8 in "unclosed"#
+        );
+    }
+}
@@ -0,0 +1,519 @@
+use promkit::{
+    json::{JsonNode, JsonPath, JsonPathSegment},
+    serde_json::Value,
+};
+
+/// The display options listed in the settings overlay.
+#[derive(Clone, Copy)]
+pub enum Setting {
+    Wrap,
+    LineNumbers,
+    Hints,
+    Verbosity,
+    Suggestions,
+    Fullscreen,
+    SortKeys,
+    HideEmpty,
+    SampleArrays,
+    HighlightChanges,
+    RawOutput,
+    CompactOutput,
+    AsciiOutput,
+    OutputFormat,
+}
+
+pub const SETTINGS: [Setting; 14] = [
+    Setting::Wrap,
+    Setting::LineNumbers,
+    Setting::Hints,
+    Setting::Verbosity,
+    Setting::Suggestions,
+    Setting::Fullscreen,
+    Setting::SortKeys,
+    Setting::HideEmpty,
+    Setting::SampleArrays,
+    Setting::HighlightChanges,
+    Setting::RawOutput,
+    Setting::CompactOutput,
+    Setting::AsciiOutput,
+    Setting::OutputFormat,
+];
+
+impl Setting {
+    pub fn label(self) -> &'static str {
+        match self {
+            Setting::Wrap => "Wrap long lines in the JSON viewer",
+            Setting::LineNumbers => "Number the rows of the JSON viewer in a gutter",
+            Setting::Hints => "Show the hint pane",
+            Setting::Verbosity => "Hint verbosity (off, errors, normal or verbose)",
+            Setting::Suggestions => "Show the suggestion list",
+            Setting::Fullscreen => "Hide every pane but the JSON viewer",
+            Setting::SortKeys => "Sort the keys of objects in the JSON viewer",
+            Setting::HideEmpty => "Hide keys whose values are null or empty",
+            Setting::SampleArrays => "Show a sample of the items of arrays longer than 100 items",
+            Setting::HighlightChanges => "Highlight the rows changed by an edit of the filter",
+            Setting::RawOutput => "Output strings without quotes on accept",
+            Setting::CompactOutput => "Output each result on one line on accept",
+            Setting::AsciiOutput => "Escape non-ASCII characters on accept and in exports",
+            Setting::OutputFormat => {
+                "Format of the output on accept and in the pager (json, yaml, toml, gron or jsonl)"
+            }
+        }
+    }
+}
+
+/// How the values are laid out in the JSON viewer.
+#[derive(Clone, Default)]
+pub struct View {
+    /// The lowercase pattern the keys shown must contain, if any.
+    pub key_filter: Option<String>,
+    pub sample_arrays: bool,
+    pub hide_empty: bool,
+    pub sort_keys: bool,
+    /// The levels rendered, the objects and arrays below replaced with a note on their size.
+    pub max_depth: Option<usize>,
+    /// The levels unfolded at first.
+    pub expand_depth: Option<usize>,
+}
+
+/// The key of the entry standing for the entries hidden from an object.
+pub const HIDDEN_KEY: &str = "…";
+
+/// The number of items beyond which an array is sampled.
+const LONG_ARRAY: usize = 100;
+/// The number of items an array is sampled down to, about.
+const SAMPLED_ITEMS: usize = 20;
+
+/// Builds the nodes shown in the JSON viewer from the values, in one pass:
+/// keeping the keys matching the filter, sampling long arrays, hiding empty values,
+/// sorting the keys and stopping at the depth, so that the levels below are never built.
+pub fn nodes(values: &[Value], view: &View) -> Vec<JsonNode> {
+    let (nodes, _) = nodes_until(values, view, || false);
+    or_empty(nodes, view)
+}
+
+/// Builds the nodes of the values as `nodes` does, until `stop` returns true
+/// before a value, returning the nodes built and the number of values laid out.
+pub fn nodes_until(
+    values: &[Value],
+    view: &View,
+    stop: impl Fn() -> bool,
+) -> (Vec<JsonNode>, usize) {
+    let pattern = view.key_filter.as_deref();
+    let mut nodes = Vec::new();
+    for (i, value) in values.iter().enumerate() {
+        if stop() {
+            return (nodes, i);
+        }
+        if pattern.is_none_or(|pattern| has_match(value, pattern)) {
+            nodes.push(node(value, view, pattern, 0));
+        }
+    }
+    (nodes, values.len())
+}
+
+/// Returns the nodes, or an empty object when no key matches the filter.
+pub fn or_empty(nodes: Vec<JsonNode>, view: &View) -> Vec<JsonNode> {
+    if nodes.is_empty() && view.key_filter.is_some() {
+        return vec![JsonNode::Object {
+            children: Vec::new(),
+            children_visible: true,
+        }];
+    }
+    nodes
+}
+
+/// Builds the node of the value at the level, keeping only the entries
+/// whose keys contain the pattern, or lead to such keys, unfolded to show them.
+fn node(value: &Value, view: &View, pattern: Option<&str>, level: usize) -> JsonNode {
+    let children_visible = pattern.is_some() || view.expand_depth.is_none_or(|depth| level < depth);
+    match value {
+        Value::Object(map) if view.max_depth == Some(level) => {
+            JsonNode::Leaf(Value::String(format!("{{… {} keys}}", map.len())))
+        }
+        Value::Array(items) if view.max_depth == Some(level) => {
+            JsonNode::Leaf(Value::String(format!("[… {} items]", items.len())))
+        }
+        Value::Object(map) => {
+            let mut hidden = 0;
+            let mut children = Vec::new();
+            for (key, child) in map {
+                let pattern = pattern.filter(|pattern| !key.to_lowercase().contains(pattern));
+                if pattern.is_some_and(|pattern| !has_match(child, pattern)) {
+                    continue;
+                }
+                if view.hide_empty && is_empty(child) {
+                    hidden += 1;
+                    continue;
+                }
+                children.push((key.clone(), node(child, view, pattern, level + 1)));
+            }
+            if view.sort_keys {
+                children.sort_by(|(a, _), (b, _)| a.cmp(b));
+            }
+            // An entry with the number hidden in their place.
+            if hidden > 0 {
+                children.push((
+                    HIDDEN_KEY.to_string(),
+                    JsonNode::Leaf(Value::String(format!("{} empty hidden", hidden))),
+                ));
+            }
+            JsonNode::Object {
+                children,
+                children_visible,
+            }
+        }
+        Value::Array(items) => JsonNode::Array {
+            children: layout(items, view, pattern)
+                .into_iter()
+                .map(|slot| match slot {
+                    Slot::Item(index) => node(&items[index], view, pattern, level + 1),
+                    Slot::Skipped(skipped) => {
+                        JsonNode::Leaf(Value::String(format!("… skipped {} items …", skipped)))
+                    }
+                })
+                .collect(),
+            children_visible,
+        },
+        leaf => JsonNode::Leaf(leaf.clone()),
+    }
+}
+
+/// What a row of an array shown in the JSON viewer stands for.
+#[derive(Clone, Copy, PartialEq)]
+enum Slot {
+    /// The item at the index in the array.
+    Item(usize),
+    /// A note on the number of items skipped by the sampling.
+    Skipped(usize),
+}
+
+/// Lays out the items of the array matching the pattern, showing only every Nth
+/// of the arrays longer than 100 items so that about 20 items remain,
+/// each followed by a note on the items skipped after it (e.g. `… skipped 49 items …`).
+fn layout(items: &[Value], view: &View, pattern: Option<&str>) -> Vec<Slot> {
+    let kept = (0..items.len())
+        .filter(|index| pattern.is_none_or(|pattern| has_match(&items[*index], pattern)))
+        .collect::<Vec<_>>();
+    if !view.sample_arrays || kept.len() <= LONG_ARRAY {
+        return kept.into_iter().map(Slot::Item).collect();
+    }
+    let total = kept.len();
+    let step = total.div_ceil(SAMPLED_ITEMS);
+    let mut ret = Vec::new();
+    for (i, index) in kept.into_iter().enumerate().step_by(step) {
+        ret.push(Slot::Item(index));
+        let skipped = step.min(total - i) - 1;
+        if skipped > 0 {
+            ret.push(Slot::Skipped(skipped));
+        }
+    }
+    ret
+}
+
+/// Whether a key in the value contains the lowercase pattern, ignoring case.
+fn has_match(value: &Value, pattern: &str) -> bool {
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .any(|(key, child)| key.to_lowercase().contains(pattern) || has_match(child, pattern)),
+        Value::Array(items) => items.iter().any(|item| has_match(item, pattern)),
+        _ => false,
+    }
+}
+
+/// Whether the value is null, an empty string, an empty array or an empty object.
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => map.is_empty(),
+        Value::Array(items) => items.is_empty(),
+        Value::Null => true,
+        Value::String(s) => s.is_empty(),
+        _ => false,
+    }
+}
+
+/// Maps the result and the path within it shown in the JSON viewer back to the values,
+/// through the filtered results and the sampled arrays. Returns `None` for the notes
+/// standing for values (e.g. `… skipped 49 items …`) and for values not shown.
+pub fn source_location(
+    values: &[Value],
+    view: &View,
+    location: &(usize, JsonPath),
+) -> Option<(usize, JsonPath)> {
+    let (result, path) = location;
+    let mut pattern = view.key_filter.as_deref();
+    let result = match pattern {
+        Some(pattern) => {
+            values
+                .iter()
+                .enumerate()
+                .filter(|(_, value)| has_match(value, pattern))
+                .nth(*result)?
+                .0
+        }
+        None => *result,
+    };
+    let mut value = values.get(result)?;
+    let mut ret = Vec::new();
+    for segment in path {
+        match (value, segment) {
+            (Value::Object(map), JsonPathSegment::Key(key)) => {
+                let child = map.get(key)?;
+                // The entry standing for the hidden ones.
+                if view.hide_empty && is_empty(child) {
+                    return None;
+                }
+                pattern = pattern.filter(|pattern| !key.to_lowercase().contains(pattern));
+                ret.push(segment.clone());
+                value = child;
+            }
+            (Value::Array(items), JsonPathSegment::Index(index)) => {
+                match layout(items, view, pattern).get(*index)? {
+                    Slot::Item(index) => {
+                        ret.push(JsonPathSegment::Index(*index));
+                        value = &items[*index];
+                    }
+                    Slot::Skipped(_) => return None,
+                }
+            }
+            _ => return None,
+        }
+    }
+    Some((result, ret))
+}
+
+/// Maps the result and the path within it in the values to where the JSON viewer
+/// shows them, or to the note standing for them below the depth. Returns `None`
+/// for the values left out (e.g. skipped by the sampling or by the key filter).
+pub fn view_location(
+    values: &[Value],
+    view: &View,
+    location: &(usize, JsonPath),
+) -> Option<(usize, JsonPath)> {
+    let (result, path) = location;
+    let mut pattern = view.key_filter.as_deref();
+    let mut value = values.get(*result)?;
+    let result = match pattern {
+        Some(pattern) if !has_match(value, pattern) => return None,
+        Some(pattern) => values[..*result]
+            .iter()
+            .filter(|value| has_match(value, pattern))
+            .count(),
+        None => *result,
+    };
+    let mut ret = Vec::new();
+    for segment in path {
+        if view.max_depth == Some(ret.len()) {
+            break;
+        }
+        match (value, segment) {
+            (Value::Object(map), JsonPathSegment::Key(key)) => {
+                let child = map.get(key)?;
+                pattern = pattern.filter(|pattern| !key.to_lowercase().contains(pattern));
+                if pattern.is_some_and(|pattern| !has_match(child, pattern))
+                    || view.hide_empty && is_empty(child)
+                {
+                    return None;
+                }
+                ret.push(segment.clone());
+                value = child;
+            }
+            (Value::Array(items), JsonPathSegment::Index(index)) => {
+                let shown = layout(items, view, pattern)
+                    .iter()
+                    .position(|slot| *slot == Slot::Item(*index))?;
+                ret.push(JsonPathSegment::Index(shown));
+                value = &items[*index];
+            }
+            _ => return None,
+        }
+    }
+    Some((result, ret))
+}
+
+/// Returns the value at the result and the path within it.
+pub fn value_at<'a>(values: &'a [Value], location: &(usize, JsonPath)) -> Option<&'a Value> {
+    let (result, path) = location;
+    path.iter()
+        .try_fold(values.get(*result)?, |value, segment| {
+            match (value, segment) {
+                (Value::Object(map), JsonPathSegment::Key(key)) => map.get(key),
+                (Value::Array(items), JsonPathSegment::Index(index)) => items.get(*index),
+                _ => None,
+            }
+        })
+}
+
+/// Returns the number of levels of objects and arrays nested in the value.
+pub fn depth(value: &Value) -> usize {
+    match value {
+        Value::Object(map) => 1 + map.values().map(depth).max().unwrap_or(0),
+        Value::Array(items) => 1 + items.iter().map(depth).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use promkit::serde_json::json;
+
+    use super::*;
+
+    fn leaf(s: &str) -> JsonNode {
+        JsonNode::Leaf(Value::from(s))
+    }
+
+    #[test]
+    fn nodes_until_the_stop() {
+        let values = [json!(1), json!(2), json!(3)];
+        let laid_out = std::cell::Cell::new(0);
+        let stop = || {
+            laid_out.set(laid_out.get() + 1);
+            laid_out.get() > 2
+        };
+        let (nodes, done) = nodes_until(&values, &View::default(), stop);
+        assert_eq!(
+            (nodes, done),
+            (vec![JsonNode::Leaf(json!(1)), JsonNode::Leaf(json!(2))], 2)
+        );
+    }
+
+    #[test]
+    fn nodes_stop_at_the_depth() {
+        let view = View {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let nodes = nodes(&[json!({"a": {"b": 1}, "c": [1, 2], "d": 3})], &view);
+        assert_eq!(
+            nodes,
+            vec![JsonNode::Object {
+                children: vec![
+                    ("a".to_string(), leaf("{… 1 keys}")),
+                    ("c".to_string(), leaf("[… 2 items]")),
+                    ("d".to_string(), JsonNode::Leaf(json!(3))),
+                ],
+                children_visible: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn nodes_unfold_up_to_the_expand_depth() {
+        let view = View {
+            expand_depth: Some(1),
+            ..Default::default()
+        };
+        let nodes = nodes(&[json!({"a": {"b": 1}})], &view);
+        assert_eq!(
+            nodes,
+            vec![JsonNode::Object {
+                children: vec![(
+                    "a".to_string(),
+                    JsonNode::Object {
+                        children: vec![("b".to_string(), JsonNode::Leaf(json!(1)))],
+                        children_visible: false,
+                    }
+                )],
+                children_visible: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn nodes_hide_empty_values_and_sort_keys() {
+        let view = View {
+            hide_empty: true,
+            sort_keys: true,
+            ..Default::default()
+        };
+        let nodes = nodes(&[json!({"b": 1, "e": "", "a": null})], &view);
+        assert_eq!(
+            nodes,
+            vec![JsonNode::Object {
+                children: vec![
+                    ("b".to_string(), JsonNode::Leaf(json!(1))),
+                    (HIDDEN_KEY.to_string(), leaf("2 empty hidden")),
+                ],
+                children_visible: true,
+            }]
+        );
+    }
+
+    fn index(index: usize) -> JsonPath {
+        vec![JsonPathSegment::Index(index)]
+    }
+
+    fn key(key: &str) -> JsonPathSegment {
+        JsonPathSegment::Key(key.to_string())
+    }
+
+    #[test]
+    fn locations_map_through_sampled_arrays() {
+        let values = [Value::Array((0..200).map(Value::from).collect())];
+        let view = View {
+            sample_arrays: true,
+            ..Default::default()
+        };
+        // Every 10th item, each followed by a note on the 9 skipped.
+        assert_eq!(
+            source_location(&values, &view, &(0, index(2))),
+            Some((0, index(10)))
+        );
+        assert_eq!(source_location(&values, &view, &(0, index(1))), None);
+        assert_eq!(
+            view_location(&values, &view, &(0, index(10))),
+            Some((0, index(2)))
+        );
+        assert_eq!(view_location(&values, &view, &(0, index(11))), None);
+    }
+
+    #[test]
+    fn locations_map_through_the_key_filter() {
+        let values = [
+            json!({"other": 1}),
+            json!([{"x": 1}, {"name": "a"}, {"Name": "b"}]),
+        ];
+        let view = View {
+            key_filter: Some("name".to_string()),
+            ..Default::default()
+        };
+        let shown = (0, vec![JsonPathSegment::Index(1), key("Name")]);
+        let source = (1, vec![JsonPathSegment::Index(2), key("Name")]);
+        assert_eq!(
+            source_location(&values, &view, &shown),
+            Some(source.clone())
+        );
+        assert_eq!(view_location(&values, &view, &source), Some(shown));
+        assert_eq!(view_location(&values, &view, &(0, Vec::new())), None);
+    }
+
+    #[test]
+    fn locations_skip_the_notes() {
+        let values = [json!({"a": {"b": {"c": 1}}, "e": null})];
+        let view = View {
+            hide_empty: true,
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            source_location(&values, &view, &(0, vec![key(HIDDEN_KEY)])),
+            None
+        );
+        // The note on the size of the object stands for it.
+        let path = vec![key("a"), key("b")];
+        assert_eq!(
+            view_location(&values, &view, &(0, path)),
+            Some((0, vec![key("a")]))
+        );
+    }
+
+    #[test]
+    fn value_at_reads_past_the_depth() {
+        let values = [json!({"a": {"b": [1, 2]}})];
+        let path = vec![JsonPathSegment::Key("a".to_string())];
+        assert_eq!(value_at(&values, &(0, path)), Some(&json!({"b": [1, 2]})));
+        assert_eq!(value_at(&values, &(1, Vec::new())), None);
+    }
+}
@@ -0,0 +1,186 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use promkit::serde_json::{self, Map, Value};
+
+/// The number of entries kept in the history files, unless set otherwise.
+const HISTORY_SIZE: usize = 500;
+
+static MAX_ENTRIES: OnceLock<usize> = OnceLock::new();
+
+/// Limits the entries kept in the history files, once for the process.
+pub fn set_history_size(size: usize) {
+    let _ = MAX_ENTRIES.set(size);
+}
+
+/// Returns the number of entries kept in the history files
+/// (e.g. the accepted filters), the oldest being forgotten first.
+pub fn history_size() -> usize {
+    MAX_ENTRIES.get().copied().unwrap_or(HISTORY_SIZE)
+}
+
+/// The key of the time a value was saved by `Store::save`.
+const SAVED: &str = "saved";
+
+/// A file in the data directory keeping a value for each input,
+/// keyed by the canonical path of the input file or the fingerprint of the input.
+pub struct Store {
+    /// The file, if a data directory is available.
+    file: Option<PathBuf>,
+    key: String,
+}
+
+impl Store {
+    pub fn open(name: &str, key: &str) -> Self {
+        Self {
+            file: data_dir().map(|dir| dir.join(name)),
+            key: key.to_string(),
+        }
+    }
+
+    /// Loads the value kept for the input.
+    pub fn load(&self) -> Option<Value> {
+        let mut value = self
+            .file
+            .as_ref()
+            .and_then(|file| read(file).ok())
+            .and_then(|mut values| values.remove(&self.key))?;
+        match value.get(SAVED) {
+            Some(_) => value.get_mut("value").map(Value::take),
+            // Saved before the time was recorded.
+            None => Some(value),
+        }
+    }
+
+    /// Keeps the value for the input along with the time,
+    /// forgetting the inputs saved least recently past the history size.
+    pub fn save(&self, value: Value) -> Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        let mut values = read(file).unwrap_or_default();
+        let saved = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        values.insert(
+            self.key.clone(),
+            serde_json::json!({ SAVED: saved, "value": value }),
+        );
+        prune(&mut values, &self.key, history_size());
+        write(file, &serde_json::to_string(&values)?)
+    }
+}
+
+/// Forgets the values saved least recently until at most `max` are left,
+/// the value of the input just saved being the most recent.
+fn prune(values: &mut Map<String, Value>, saved_now: &str, max: usize) {
+    let Some(excess) = values.len().checked_sub(max).filter(|&excess| excess > 0) else {
+        return;
+    };
+    let mut oldest = values
+        .iter()
+        .map(|(key, value)| {
+            let saved = value.get(SAVED).and_then(Value::as_u64).unwrap_or_default();
+            (key == saved_now, saved, key.clone())
+        })
+        .collect::<Vec<_>>();
+    oldest.sort();
+    for (_, _, key) in oldest.into_iter().take(excess) {
+        values.remove(&key);
+    }
+}
+
+fn read(file: &Path) -> Result<Map<String, Value>> {
+    Ok(serde_json::from_str(&fs::read_to_string(file)?)?)
+}
+
+/// Writes the file through a temporary file renamed over it,
+/// so that the file is never left half written (e.g. by a crash or a full disk),
+/// nor mixed with the writes of another session.
+pub fn write(file: &Path, contents: &str) -> Result<()> {
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut temporary = file.as_os_str().to_owned();
+    temporary.push(format!(".{}.tmp", process::id()));
+    fs::write(&temporary, contents)?;
+    if let Err(e) = fs::rename(&temporary, file) {
+        let _ = fs::remove_file(&temporary);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Returns the directory where jnv keeps data across sessions
+/// (e.g. `~/.local/share/jnv`), if any.
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir).join("jnv"));
+    }
+    if cfg!(windows) {
+        return env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join("jnv"));
+    }
+    env::var_os("HOME").map(|dir| PathBuf::from(dir).join(".local/share/jnv"))
+}
+
+/// Identifies the input file across sessions by its canonical path,
+/// so that the values kept for it hold after it changes (e.g. a report updated daily).
+pub fn path_key(path: &Path) -> Option<String> {
+    fs::canonicalize(path)
+        .ok()
+        .map(|path| path.display().to_string())
+}
+
+/// Identifies the input across sessions with its FNV-1a hash,
+/// which, unlike the std hasher, is stable across Rust releases.
+pub fn fingerprint(input: &str) -> String {
+    let hash = input.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prune_forgets_the_least_recent_values() {
+        let mut values = serde_json::from_str::<Map<String, Value>>(
+            r#"{"a": {"saved": 30, "value": 1}, "b": {".x": 2}, "c": {"saved": 10, "value": 3}, "d": {"saved": 20, "value": 4}}"#,
+        )
+        .unwrap();
+        prune(&mut values, "c", 2);
+        assert_eq!(values.keys().collect::<Vec<_>>(), vec!["a", "c"]);
+        prune(&mut values, "c", 2);
+        assert_eq!(values.len(), 2);
+        prune(&mut values, "c", 0);
+        assert!(values.is_empty());
+    }
+
+    #[test]
+    fn files_are_replaced_whole() {
+        let dir = env::temp_dir().join(format!("jnv-store-{}", process::id()));
+        let file = dir.join("queries.json");
+        write(&file, "[1]").unwrap();
+        write(&file, "[2]").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "[2]");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        assert!(path_key(&dir.join("missing.json")).is_none());
+        assert_eq!(
+            path_key(
+                &dir.join("../")
+                    .join(dir.file_name().unwrap())
+                    .join("queries.json")
+            ),
+            path_key(&file)
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
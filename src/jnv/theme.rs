@@ -0,0 +1,221 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use promkit::{
+    crossterm::style::{Attribute, Attributes, Color},
+    json,
+    serde_json::{self, Map, Value},
+    style::StyleBuilder,
+};
+
+/// The parts of the JSON viewer colored by the theme.
+#[derive(Clone, Copy)]
+pub enum Element {
+    Keys,
+    Strings,
+    Numbers,
+    Booleans,
+    Null,
+    CurlyBrackets,
+    SquareBrackets,
+}
+
+pub const ELEMENTS: [Element; 7] = [
+    Element::Keys,
+    Element::Strings,
+    Element::Numbers,
+    Element::Booleans,
+    Element::Null,
+    Element::CurlyBrackets,
+    Element::SquareBrackets,
+];
+
+impl Element {
+    /// The key of the element in the theme file.
+    pub fn key(self) -> &'static str {
+        match self {
+            Element::Keys => "keys",
+            Element::Strings => "strings",
+            Element::Numbers => "numbers",
+            Element::Booleans => "booleans",
+            Element::Null => "null",
+            Element::CurlyBrackets => "curly_brackets",
+            Element::SquareBrackets => "square_brackets",
+        }
+    }
+}
+
+/// The colors to cycle through in the theme editor, by their names in the theme file,
+/// where `default` leaves the color of the terminal.
+const PALETTE: [(&str, Option<Color>); 17] = [
+    ("default", None),
+    ("black", Some(Color::Black)),
+    ("dark_grey", Some(Color::DarkGrey)),
+    ("grey", Some(Color::Grey)),
+    ("white", Some(Color::White)),
+    ("red", Some(Color::Red)),
+    ("dark_red", Some(Color::DarkRed)),
+    ("green", Some(Color::Green)),
+    ("dark_green", Some(Color::DarkGreen)),
+    ("yellow", Some(Color::Yellow)),
+    ("dark_yellow", Some(Color::DarkYellow)),
+    ("blue", Some(Color::Blue)),
+    ("dark_blue", Some(Color::DarkBlue)),
+    ("magenta", Some(Color::Magenta)),
+    ("dark_magenta", Some(Color::DarkMagenta)),
+    ("cyan", Some(Color::Cyan)),
+    ("dark_cyan", Some(Color::DarkCyan)),
+];
+
+/// The colors of the JSON viewer, read from a JSON file mapping the elements
+/// to color names or `#rrggbb` codes (e.g. `{"numbers": "yellow", "keys": "#88c0d0"}`).
+/// Elements missing from the file keep their default colors.
+pub struct Theme {
+    /// The file to save the theme to.
+    path: Option<PathBuf>,
+    colors: [Option<Color>; ELEMENTS.len()],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            path: None,
+            colors: [
+                Some(Color::Cyan),
+                Some(Color::Green),
+                None,
+                None,
+                Some(Color::Grey),
+                None,
+                None,
+            ],
+        }
+    }
+}
+
+impl Theme {
+    /// Reads the theme from the file, or returns the default theme
+    /// to be saved to the file if it does not exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut theme = Self {
+            path: Some(path.to_path_buf()),
+            ..Default::default()
+        };
+        if !path.exists() {
+            return Ok(theme);
+        }
+        let error = |e: anyhow::Error| anyhow!("{}: {}", path.display(), e);
+        let value = serde_json::from_str::<Value>(&fs::read_to_string(path)?)?;
+        let object = value
+            .as_object()
+            .ok_or_else(|| error(anyhow!("The theme must be an object")))?;
+        for (key, name) in object {
+            let position = ELEMENTS
+                .iter()
+                .position(|element| element.key() == key)
+                .ok_or_else(|| error(anyhow!("Unknown element '{}'", key)))?;
+            theme.colors[position] = name
+                .as_str()
+                .ok_or_else(|| error(anyhow!("The color of '{}' must be a string", key)))
+                .and_then(|name| parse_color(name).map_err(error))?;
+        }
+        Ok(theme)
+    }
+
+    /// Writes every color to the file, returning its path.
+    pub fn save(&self) -> Result<&Path> {
+        let path = self
+            .path
+            .as_deref()
+            .ok_or_else(|| anyhow!("No config directory to save the theme to"))?;
+        let object = ELEMENTS
+            .iter()
+            .zip(self.colors)
+            .map(|(element, color)| (element.key().to_string(), Value::from(color_name(color))))
+            .collect::<Map<_, _>>();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&object)? + "\n")?;
+        Ok(path)
+    }
+
+    pub fn color_name(&self, element: Element) -> String {
+        color_name(self.colors[element as usize])
+    }
+
+    /// Moves the color of the element to the next one in the palette,
+    /// or the previous one if not forward.
+    pub fn cycle(&mut self, element: Element, forward: bool) {
+        let color = &mut self.colors[element as usize];
+        let current = PALETTE
+            .iter()
+            .position(|(_, candidate)| candidate == color)
+            .unwrap_or(0);
+        let next = if forward {
+            (current + 1) % PALETTE.len()
+        } else {
+            (current + PALETTE.len() - 1) % PALETTE.len()
+        };
+        *color = PALETTE[next].1;
+    }
+
+    /// Applies the colors to the styles of the JSON viewer,
+    /// keeping the brackets bold.
+    pub fn apply(&self, theme: &mut json::Theme) {
+        let style = |element: Element| {
+            let mut builder = StyleBuilder::new();
+            if let Some(color) = self.colors[element as usize] {
+                builder = builder.fgc(color);
+            }
+            if matches!(element, Element::CurlyBrackets | Element::SquareBrackets) {
+                builder = builder.attrs(Attributes::from(Attribute::Bold));
+            }
+            builder.build()
+        };
+        theme.key_style = style(Element::Keys);
+        theme.string_value_style = style(Element::Strings);
+        theme.number_value_style = style(Element::Numbers);
+        theme.boolean_value_style = style(Element::Booleans);
+        theme.null_value_style = style(Element::Null);
+        theme.curly_brackets_style = style(Element::CurlyBrackets);
+        theme.square_brackets_style = style(Element::SquareBrackets);
+    }
+}
+
+fn parse_color(name: &str) -> Result<Option<Color>> {
+    if let Some(hex) = name.strip_prefix('#') {
+        let channel = |range| {
+            hex.get(range)
+                .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+                .ok_or_else(|| anyhow!("Invalid color code '{}'", name))
+        };
+        if hex.len() != 6 {
+            return Err(anyhow!("Invalid color code '{}'", name));
+        }
+        return Ok(Some(Color::Rgb {
+            r: channel(0..2)?,
+            g: channel(2..4)?,
+            b: channel(4..6)?,
+        }));
+    }
+    PALETTE
+        .iter()
+        .find(|(candidate, _)| *candidate == name)
+        .map(|(_, color)| *color)
+        .ok_or_else(|| anyhow!("Unknown color '{}'", name))
+}
+
+fn color_name(color: Option<Color>) -> String {
+    match color {
+        Some(Color::Rgb { r, g, b }) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        color => PALETTE
+            .iter()
+            .find(|(_, candidate)| *candidate == color)
+            .map_or("default", |(name, _)| name)
+            .to_string(),
+    }
+}
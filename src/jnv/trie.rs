@@ -1,22 +1,68 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
 use radix_trie::{Trie, TrieCommon};
 
-use promkit::json::JsonNode;
+use promkit::serde_json::Value;
+
+/// How long the results of a query are kept after it was last evaluated.
+const MAX_AGE: Duration = Duration::from_secs(10 * 60);
 
-#[derive(Default)]
-pub struct QueryTrie(Trie<String, Vec<JsonNode>>);
+/// How often the results kept past their age are dropped.
+const TRIM_INTERVAL: Duration = Duration::from_secs(60);
+
+pub struct QueryTrie {
+    trie: Trie<String, Vec<Value>>,
+    /// When each query was last evaluated.
+    evaluated: HashMap<String, Instant>,
+    trimmed: Instant,
+}
+
+impl Default for QueryTrie {
+    fn default() -> Self {
+        Self {
+            trie: Trie::new(),
+            evaluated: HashMap::new(),
+            trimmed: Instant::now(),
+        }
+    }
+}
 
 impl QueryTrie {
-    pub fn insert(&mut self, query: &str, json_nodes: Vec<JsonNode>) {
-        self.0.insert(query.to_string(), json_nodes);
+    pub fn insert(&mut self, query: &str, values: Vec<Value>) {
+        self.trie.insert(query.to_string(), values);
+        self.evaluated.insert(query.to_string(), Instant::now());
     }
 
-    pub fn prefix_search(&self, query: &str) -> Option<(&String, &Vec<JsonNode>)> {
-        self.0
+    pub fn prefix_search(&self, query: &str) -> Option<(&String, &Vec<Value>)> {
+        self.trie
             .get_ancestor(query)
             .and_then(|subtrie| Some((subtrie.key()?, subtrie.value()?)))
     }
 
-    pub fn prefix_search_value(&self, query: &str) -> Option<&Vec<JsonNode>> {
+    pub fn prefix_search_value(&self, query: &str) -> Option<&Vec<Value>> {
         self.prefix_search(query).map(|tup| tup.1)
     }
+
+    /// Drops the results of the queries not evaluated for a while, once in a while,
+    /// so that a session left open for hours doesn't keep every result it showed.
+    pub fn trim(&mut self) {
+        if self.trimmed.elapsed() < TRIM_INTERVAL {
+            return;
+        }
+        self.trimmed = Instant::now();
+        let stale = self
+            .evaluated
+            .iter()
+            .filter(|(_, evaluated)| evaluated.elapsed() > MAX_AGE)
+            .map(|(query, _)| query.clone())
+            .collect::<Vec<_>>();
+        for query in stale {
+            self.trie.remove(&query);
+            self.evaluated.remove(&query);
+        }
+        self.evaluated.shrink_to_fit();
+    }
 }
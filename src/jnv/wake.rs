@@ -0,0 +1,34 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether other threads can wake the interface up. Without, on Windows,
+/// what they queue is only handled with the next key, so the work shown
+/// as it completes (e.g. loading the input) is done before instead.
+pub const SUPPORTED: bool = cfg!(unix);
+
+/// Whether a wake-up was raised and not handled yet.
+static PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Wakes the interface up from waiting for terminal events, to handle
+/// what other threads queued for it (e.g. control requests),
+/// once for the wake-ups raised until it is handled.
+///
+/// The terminal events are read by crossterm, which only returns
+/// for input or SIGWINCH, so the wake-up arrives as a resize event.
+/// Every resize event is handled as a wake-up as well as a resize,
+/// so that neither a wake-up nor a resize of the terminal is lost.
+pub fn wake() {
+    if PENDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    #[cfg(unix)]
+    // SAFETY: raising a signal on the own thread has no preconditions.
+    unsafe {
+        libc::raise(libc::SIGWINCH);
+    }
+}
+
+/// Lets the next wake-up raise a signal again,
+/// once the resize event the pending ones arrived as is handled.
+pub fn handled() {
+    PENDING.store(false, Ordering::SeqCst);
+}
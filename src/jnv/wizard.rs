@@ -0,0 +1,206 @@
+use anyhow::{bail, Result};
+use promkit::serde_json;
+
+/// A guided action building a filter from answers to its questions.
+pub enum Wizard {
+    GroupBy(GroupBy),
+    Replace(Replace),
+}
+
+impl Wizard {
+    /// Returns the query at the start of the wizard, restored on cancel.
+    pub fn query(&self) -> &str {
+        match self {
+            Wizard::GroupBy(wizard) => &wizard.query,
+            Wizard::Replace(wizard) => &wizard.query,
+        }
+    }
+
+    /// Returns the question to be answered next.
+    pub fn question(&self) -> &'static str {
+        match self {
+            Wizard::GroupBy(wizard) => wizard.question(),
+            Wizard::Replace(wizard) => wizard.question(),
+        }
+    }
+
+    /// Returns the jq query that previews the effect of the answer being typed,
+    /// producing a string to show along with the question.
+    pub fn preview(&self, answer: &str) -> Option<String> {
+        match self {
+            Wizard::GroupBy(_) => None,
+            Wizard::Replace(wizard) => wizard.preview(answer),
+        }
+    }
+
+    /// Takes the answer to the current question,
+    /// returning the generated filter once every question is answered.
+    pub fn answer(&mut self, answer: &str) -> Result<Option<String>> {
+        match self {
+            Wizard::GroupBy(wizard) => wizard.answer(answer),
+            Wizard::Replace(wizard) => wizard.answer(answer),
+        }
+    }
+}
+
+/// Builds a `group_by(...) | map(...)` filter aggregating each group.
+pub struct GroupBy {
+    /// The query producing the array to group.
+    query: String,
+    /// The path to group the elements by, once answered.
+    key: Option<String>,
+}
+
+impl GroupBy {
+    pub fn new(query: &str) -> Self {
+        Self {
+            query: query.to_string(),
+            key: None,
+        }
+    }
+
+    fn question(&self) -> &'static str {
+        match self.key {
+            None => "Group by (path in each element, e.g. .status):",
+            Some(_) => "Aggregate ('count', 'sum .field' or 'unique .field'):",
+        }
+    }
+
+    fn answer(&mut self, answer: &str) -> Result<Option<String>> {
+        let answer = answer.trim();
+        let Some(key) = &self.key else {
+            if answer.is_empty() {
+                bail!("The path to group by is empty");
+            }
+            self.key = Some(path(answer));
+            return Ok(None);
+        };
+
+        let aggregation = match answer.split_once(char::is_whitespace) {
+            None if answer == "count" => "count: length".to_string(),
+            Some(("sum", field)) => format!("sum: (map({}) | add)", path(field.trim())),
+            Some(("unique", field)) => format!("values: (map({}) | unique)", path(field.trim())),
+            _ => bail!("Unknown aggregation '{}'", answer),
+        };
+        let grouping = format!(
+            "group_by({}) | map({{key: .[0]{}, {}}})",
+            key, key, aggregation
+        );
+        Ok(Some(pipe(&self.query, &grouping)))
+    }
+}
+
+/// Builds a `walk(...)` filter replacing the matches of a regex in every string,
+/// so that the transformation is reproducible outside jnv.
+pub struct Replace {
+    /// The query producing the JSON to transform.
+    query: String,
+    /// The regex to search strings for, once answered.
+    pattern: Option<String>,
+}
+
+impl Replace {
+    pub fn new(query: &str) -> Self {
+        Self {
+            query: query.to_string(),
+            pattern: None,
+        }
+    }
+
+    fn question(&self) -> &'static str {
+        match self.pattern {
+            None => "Search strings for (regex):",
+            Some(_) => "Replace with (\\(.name) refers to a named capture):",
+        }
+    }
+
+    /// Previews the first strings matching the regex being typed,
+    /// then how they are replaced with the replacement being typed.
+    fn preview(&self, answer: &str) -> Option<String> {
+        let (pattern, shown) = match &self.pattern {
+            None if answer.is_empty() => return None,
+            None => (serde_json::to_string(answer).ok()?, "tojson".to_string()),
+            Some(pattern) => (
+                pattern.clone(),
+                format!(r#"tojson + " → " + ({} | tojson)"#, gsub(pattern, answer)),
+            ),
+        };
+        let preview = format!(
+            r#"[.. | strings | select(test({}))] | "\(length) strings match" + (.[:{}] | map(", " + {}) | add // "")"#,
+            pattern, PREVIEWED, shown
+        );
+        Some(pipe(&self.query, &preview))
+    }
+
+    fn answer(&mut self, answer: &str) -> Result<Option<String>> {
+        let Some(pattern) = &self.pattern else {
+            if answer.is_empty() {
+                bail!("The regex to search for is empty");
+            }
+            self.pattern = Some(serde_json::to_string(answer)?);
+            return Ok(None);
+        };
+
+        let replacement = format!(
+            r#"walk(if type == "string" then {} else . end)"#,
+            gsub(pattern, answer)
+        );
+        Ok(Some(pipe(&self.query, &replacement)))
+    }
+}
+
+/// The number of matching strings previewed with their replacements.
+const PREVIEWED: usize = 3;
+
+/// Returns the call replacing the matches of the regex, given as a JSON string,
+/// with the replacement typed.
+fn gsub(pattern: &str, replacement: &str) -> String {
+    // Unlike the regex, backslashes are kept as typed
+    // so that the replacement can use jq's string interpolation.
+    format!(
+        r#"gsub({}; "{}")"#,
+        pattern,
+        replacement.replace('"', "\\\"")
+    )
+}
+
+/// Pipes the query into the filter, omitting the identity query.
+pub fn pipe(query: &str, filter: &str) -> String {
+    match query.trim() {
+        "" | "." => filter.to_string(),
+        query => format!("{} | {}", query, filter),
+    }
+}
+
+/// Completes a path typed without the leading dot (e.g. `status`).
+fn path(path: &str) -> String {
+    if path.starts_with('.') {
+        path.to_string()
+    } else {
+        format!(".{}", path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replace_previews_the_replacements_being_typed() {
+        let mut wizard = Replace::new(".users");
+        assert_eq!(wizard.preview(""), None);
+        assert_eq!(
+            wizard.preview("@x").unwrap(),
+            r#".users | [.. | strings | select(test("@x"))] | "\(length) strings match" + (.[:3] | map(", " + tojson) | add // "")"#
+        );
+        assert_eq!(wizard.answer("@x").unwrap(), None);
+        assert_eq!(
+            wizard.preview("@y").unwrap(),
+            r#".users | [.. | strings | select(test("@x"))] | "\(length) strings match" + (.[:3] | map(", " + tojson + " → " + (gsub("@x"; "@y") | tojson)) | add // "")"#
+        );
+        assert_eq!(
+            wizard.answer("\"\\(.a)").unwrap().unwrap(),
+            r#".users | walk(if type == "string" then gsub("@x"; "\"\(.a)") else . end)"#
+        );
+    }
+}
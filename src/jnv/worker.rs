@@ -0,0 +1,133 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        mpsc::{self, Receiver},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use promkit::serde_json::{self, json, Value};
+
+/// The environment variable making jnv serve as the worker
+/// evaluating the queries given a timeout.
+pub const ENV: &str = "JNV_JQ_WORKER";
+
+/// A jnv process evaluating the queries, killed when a query runs past the timeout
+/// since libjq can't be interrupted. It keeps the document, which is only sent
+/// again once another document is queried, and its output is thrown away,
+/// so that the errors libjq prints never reach the interface.
+struct Worker {
+    child: Child,
+    stdin: ChildStdin,
+    /// The lines the worker replied with, read on their own thread,
+    /// which ends with the worker.
+    replies: Receiver<String>,
+    /// The document the worker holds.
+    document: String,
+}
+
+static WORKER: Mutex<Option<Worker>> = Mutex::new(None);
+
+impl Worker {
+    fn spawn() -> Result<Self> {
+        let mut child = Command::new(std::env::current_exe()?)
+            .env(ENV, "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(anyhow!("Failed to talk to the jq worker"));
+        };
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            child,
+            stdin,
+            replies,
+            document: String::new(),
+        })
+    }
+
+    /// Runs the program against the JSON, or returns `None` past the timeout.
+    fn run(&mut self, program: &str, json: &str, timeout: Duration) -> Result<Option<Value>> {
+        if self.document != json {
+            writeln!(self.stdin, "{}", json!({ "document": json }))?;
+            json.clone_into(&mut self.document);
+        }
+        writeln!(self.stdin, "{}", json!({ "program": program }))?;
+        match self.replies.recv_timeout(timeout) {
+            Ok(reply) => Ok(Some(serde_json::from_str(&reply)?)),
+            Err(mpsc::RecvTimeoutError::Timeout) => Ok(None),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(anyhow!("The jq worker exited")),
+        }
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The outcome of a query run by the worker.
+pub enum Reply {
+    Results(Vec<String>),
+    /// libjq failed to run the query (e.g. a syntax error).
+    Failed,
+    TimedOut,
+}
+
+/// Runs the jq program against the JSON in the worker, killing it past the timeout.
+pub fn run(program: &str, json: &str, timeout: Duration) -> Result<Reply> {
+    let mut slot = WORKER.lock().unwrap_or_else(|e| e.into_inner());
+    let worker = match slot.as_mut() {
+        Some(worker) => worker,
+        None => slot.insert(Worker::spawn()?),
+    };
+    let reply = worker.run(program, json, timeout);
+    // A worker that timed out or failed is replaced on the next query.
+    if !matches!(reply, Ok(Some(_))) {
+        *slot = None;
+    }
+    let Some(reply) = reply? else {
+        return Ok(Reply::TimedOut);
+    };
+    Ok(match reply.get("results") {
+        Some(results) => Reply::Results(serde_json::from_value(results.clone())?),
+        None => Reply::Failed,
+    })
+}
+
+/// Serves as the worker: keeps the last document received on standard input
+/// and replies to each program with its results on standard output.
+pub fn serve() -> Result<()> {
+    let mut document = String::new();
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        let request = serde_json::from_str::<Value>(&line?)?;
+        if let Some(json) = request.get("document").and_then(Value::as_str) {
+            json.clone_into(&mut document);
+        }
+        if let Some(program) = request.get("program").and_then(Value::as_str) {
+            let reply = match j9::run(program, &document) {
+                Ok(results) => json!({ "results": results }),
+                Err(_) => json!({ "error": true }),
+            };
+            writeln!(stdout, "{}", reply)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,225 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use anyhow::{anyhow, Result};
+use promkit::{
+    json::{JsonNode, JsonPath, JsonPathSegment},
+    serde_json::{Map, Value},
+};
+
+/// Converts the node back into the JSON value it was parsed from.
+pub fn to_value(node: &JsonNode) -> Value {
+    match node {
+        JsonNode::Object { children, .. } => Value::Object(
+            children
+                .iter()
+                .map(|(key, child)| (key.clone(), to_value(child)))
+                .collect::<Map<_, _>>(),
+        ),
+        JsonNode::Array { children, .. } => Value::Array(children.iter().map(to_value).collect()),
+        JsonNode::Leaf(value) => value.clone(),
+    }
+}
+
+/// A row of the JSON viewer, with the value it belongs to.
+pub struct Row {
+    pub text: String,
+    /// The result and the path within it of the value.
+    pub location: (usize, JsonPath),
+    /// Whether the row opens an object or an array, closed by a later row.
+    pub opens: bool,
+    /// Whether the row closes an object or an array.
+    pub closes: bool,
+}
+
+/// Formats the nodes with their initial folds into the rows of the JSON viewer.
+pub fn rows(nodes: &[JsonNode], indent: usize) -> Vec<Row> {
+    let mut ret = Vec::new();
+    for (i, node) in nodes.iter().enumerate() {
+        let mut path = Vec::new();
+        format_rows(node, None, (i, &mut path), true, indent, &mut ret);
+    }
+    ret
+}
+
+fn format_rows(
+    node: &JsonNode,
+    key: Option<&str>,
+    (result, path): (usize, &mut JsonPath),
+    is_last: bool,
+    indent: usize,
+    rows: &mut Vec<Row>,
+) {
+    let padding = " ".repeat(path.len() * indent);
+    let prefix = match key {
+        Some(key) => format!("{}{}: ", padding, Value::String(key.to_string())),
+        None => padding.clone(),
+    };
+    let comma = if is_last { "" } else { "," };
+    let row = |text: String, (opens, closes): (bool, bool), path: &JsonPath| Row {
+        text,
+        location: (result, path.clone()),
+        opens,
+        closes,
+    };
+    match node {
+        JsonNode::Object {
+            children,
+            children_visible: true,
+        } => {
+            rows.push(row(format!("{}{{", prefix), (true, false), path));
+            for (i, (key, child)) in children.iter().enumerate() {
+                let is_last = i + 1 == children.len();
+                path.push(JsonPathSegment::Key(key.clone()));
+                format_rows(child, Some(key), (result, path), is_last, indent, rows);
+                path.pop();
+            }
+            rows.push(row(format!("{}}}{}", padding, comma), (false, true), path));
+        }
+        JsonNode::Array {
+            children,
+            children_visible: true,
+        } => {
+            rows.push(row(format!("{}[", prefix), (true, false), path));
+            for (i, child) in children.iter().enumerate() {
+                let is_last = i + 1 == children.len();
+                path.push(JsonPathSegment::Index(i));
+                format_rows(child, None, (result, path), is_last, indent, rows);
+                path.pop();
+            }
+            rows.push(row(format!("{}]{}", padding, comma), (false, true), path));
+        }
+        JsonNode::Object { .. } => rows.push(row(
+            format!("{}{{…}}{}", prefix, comma),
+            (false, false),
+            path,
+        )),
+        JsonNode::Array { .. } => {
+            rows.push(row(format!("{}[…]{}", prefix, comma), (false, false), path))
+        }
+        JsonNode::Leaf(value) => rows.push(row(
+            format!("{}{}{}", prefix, value, comma),
+            (false, false),
+            path,
+        )),
+    }
+}
+
+/// Appends the text to the file as a line,
+/// so that several values can be pulled out in a session.
+pub fn append_to_file(path: &Path, text: &str) -> Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", text)?;
+    Ok(())
+}
+
+/// Copies the text to the system clipboard through the terminal
+/// with the OSC 52 escape sequence, which also works over SSH.
+pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    stdout.flush()
+}
+
+/// Reads the text in the system clipboard with the first available
+/// of the platform tools (e.g. `pbpaste`, `wl-paste` or `xclip`),
+/// since the terminal can't be asked for it.
+pub fn paste_from_clipboard() -> Result<String> {
+    let commands: &[&[&str]] = if cfg!(target_os = "macos") {
+        &[&["pbpaste"]]
+    } else if cfg!(windows) {
+        &[&["powershell", "-NoProfile", "-Command", "Get-Clipboard -Raw"]]
+    } else {
+        &[
+            &["wl-paste", "--no-newline"],
+            &["xclip", "-selection", "clipboard", "-o"],
+            &["xsel", "--clipboard", "--output"],
+        ]
+    };
+    for command in commands {
+        let Ok(output) = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+        else {
+            continue;
+        };
+        if output.status.success() {
+            return Ok(String::from_utf8(output.stdout)?);
+        }
+    }
+    Err(anyhow!(
+        "Failed to read the clipboard: none of {} is available",
+        commands
+            .iter()
+            .map(|command| command[0])
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut ret = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                ret.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                ret.push('=');
+            }
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use promkit::serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn rows_belong_to_their_values() {
+        let nodes = [JsonNode::Object {
+            children: vec![(
+                "a".to_string(),
+                JsonNode::Array {
+                    children: vec![JsonNode::Leaf(json!(1))],
+                    children_visible: true,
+                },
+            )],
+            children_visible: true,
+        }];
+        let rows = rows(&nodes, 2)
+            .into_iter()
+            .map(|row| (row.text, row.location.1.len(), row.opens, row.closes))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![
+                ("{".to_string(), 0, true, false),
+                ("  \"a\": [".to_string(), 1, true, false),
+                ("    1".to_string(), 2, false, false),
+                ("  ]".to_string(), 1, false, true),
+                ("}".to_string(), 0, false, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn base64_pads_the_last_chunk() {
+        assert_eq!(base64(b"jnv"), "am52");
+        assert_eq!(base64(b"jq"), "anE=");
+        assert_eq!(base64(b"j"), "ag==");
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    io::{self, BufRead, BufReader},
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::progress::Progress;
+
+/// How often the number of messages consumed is reported until the end of the topic.
+const TICK: Duration = Duration::from_millis(250);
+
+/// A topic followed by kcat in the background.
+struct Tail {
+    /// The messages consumed so far, one per line.
+    messages: Arc<Mutex<String>>,
+    /// Why kcat stopped, if it has.
+    stopped: Arc<Mutex<Option<String>>>,
+}
+
+static TAIL: OnceLock<Tail> = OnceLock::new();
+
+/// Returns the messages of the topic consumed so far, one per line.
+///
+/// The first call starts following the topic from the beginning with the kcat command,
+/// and waits until the end of the topic is reached; the later calls (e.g. on refresh)
+/// include the messages received meanwhile.
+pub fn consume(brokers: &str, topic: &str, progress: &mut Progress) -> Result<String> {
+    let tail = match TAIL.get() {
+        Some(tail) => tail,
+        None => {
+            let tail = follow(brokers, topic, progress)?;
+            TAIL.get_or_init(|| tail)
+        }
+    };
+    if let Some(reason) = &*tail.stopped.lock().unwrap_or_else(|e| e.into_inner()) {
+        return Err(anyhow!("Stopped consuming '{}': {}", topic, reason));
+    }
+    let messages = tail.messages.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(messages.clone())
+}
+
+/// Starts kcat on the topic, returning once it reports the end of the topic.
+fn follow(brokers: &str, topic: &str, progress: &mut Progress) -> Result<Tail> {
+    let mut child = Command::new("kcat")
+        .args(["-C", "-u", "-o", "beginning", "-b", brokers, "-t", topic])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => crate::not_installed("kcat"),
+            _ => anyhow!("Failed to run kcat: {}", e),
+        })?;
+    let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+        return Err(anyhow!("Failed to capture the output of kcat"));
+    };
+    let messages = Arc::new(Mutex::new(String::new()));
+    let consumed = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(Mutex::new(None));
+    let (reached_end, end) = mpsc::channel();
+
+    // kcat reports on standard error, the end of each partition among them.
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    {
+        let (errors, reached_end) = (Arc::clone(&errors), reached_end.clone());
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(|line| line.ok()) {
+                if line.contains("Reached end of topic") {
+                    let _ = reached_end.send(());
+                } else {
+                    errors.lock().unwrap_or_else(|e| e.into_inner()).push(line);
+                }
+            }
+        });
+    }
+    {
+        let (messages, stopped) = (Arc::clone(&messages), Arc::clone(&stopped));
+        let consumed = Arc::clone(&consumed);
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(|line| line.ok()) {
+                let mut messages = messages.lock().unwrap_or_else(|e| e.into_inner());
+                messages.push_str(&line);
+                messages.push('\n');
+                consumed.fetch_add(1, Ordering::Relaxed);
+            }
+            let status = child
+                .wait()
+                .map_or_else(|e| e.to_string(), |status| status.to_string());
+            let errors = errors.lock().unwrap_or_else(|e| e.into_inner());
+            *stopped.lock().unwrap_or_else(|e| e.into_inner()) = Some(match errors.last() {
+                Some(error) => format!("kcat exited with {}: {}", status, error),
+                None => format!("kcat exited with {}", status),
+            });
+            // Not to wait for the end of a topic that won't be reached.
+            let _ = reached_end.send(());
+        });
+    }
+
+    loop {
+        match end.recv_timeout(TICK) {
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {
+                let consumed = consumed.load(Ordering::Relaxed);
+                progress.step(&format!("Consuming {}: {} messages", topic, consumed))?;
+            }
+        }
+    }
+    Ok(Tail { messages, stopped })
+}
@@ -1,22 +1,49 @@
 use std::{
-    fs::File,
-    io::{self, Read},
-    path::PathBuf,
+    collections::HashSet,
+    env,
+    fs::{self, File},
+    io::{self, BufRead, IsTerminal, Read, Write},
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    process::{Command, ExitCode, Stdio},
+    sync::Arc,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::{anyhow, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
-use promkit::text_editor;
+use promkit::{
+    crossterm::{
+        cursor,
+        event::{DisableBracketedPaste, EnableBracketedPaste, Event},
+        execute,
+        terminal::{self, EnableLineWrap, EnterAlternateScreen, LeaveAlternateScreen},
+    },
+    serde_json, text_editor,
+};
 
 mod jnv;
-use jnv::Jnv;
+#[cfg(feature = "kafka")]
+mod kafka;
+use jnv::{
+    ansi, escape_non_ascii, include_data_in_reports, log_message, write_report, CaseSensitivity,
+    ColorChoice, Edits, Jnv, Layout, Outcome, OutputFormat, Theme, Verbosity,
+};
+mod merge;
+use merge::ArrayStrategy;
+mod plugins;
+use plugins::{config_dir, Plugin, Plugins};
+mod progress;
+use progress::Progress;
 
 /// JSON navigator and interactive filter leveraging jq
 #[derive(Parser)]
 #[command(
     name = "jnv",
     version,
+    subcommand_precedence_over_arg = true,
     help_template = "
 {about}
 
@@ -29,6 +56,18 @@ Examples:
 - Read from standard input:
         cat data.json | {bin}
 
+- Deep-merge a base file with an override file:
+        {bin} --merge base.json override.json
+
+- Print the screen for a filter, e.g. for golden tests:
+        {bin} render --width 120 --height 40 --filter '.items' data.json
+
+- Measure the time to load the input and evaluate a filter:
+        {bin} bench --filter '.[] | select(.x > 3)' data.json
+
+Commands:
+{subcommands}
+
 Arguments:
 {positionals}
 
@@ -37,10 +76,220 @@ Options:
 "
 )]
 pub struct Args {
-    /// Optional path to a JSON file.
+    /// Optional paths to JSON or YAML (.yaml, .yml) files,
+    /// or objects in S3 (s3://bucket/key) or GCS (gs://bucket/key).
     /// If not provided or if "-" is specified,
     /// reads from standard input.
-    pub input: Option<PathBuf>,
+    /// Multiple files are explored as a stream of documents
+    /// unless --merge is specified.
+    /// The JSON and YAML files in a directory can be picked with Alt+F.
+    /// A file named like a command (e.g. render) is given as ./render.
+    pub inputs: Vec<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+
+    #[arg(
+        long = "git-rev",
+        value_name = "REVISION",
+        help = "Read the files as of a git revision (e.g. 'HEAD~3').",
+        long_help = "
+        Reads the files given as inputs from the git repository
+        containing them as of the revision (e.g. HEAD~3, a tag or a branch),
+        without checking anything out.
+        "
+    )]
+    pub git_rev: Option<String>,
+
+    #[arg(
+        long = "sqlite",
+        value_name = "DATABASE",
+        requires = "sql",
+        help = "Read the input from a query to a SQLite database.",
+        long_help = "
+        Runs the query given by --sql against the SQLite database
+        and reads the rows as an array of objects, keyed by the column names,
+        in addition to the files if any, instead of standard input.
+        The database is opened read-only with the sqlite3 command (3.33 or later).
+        "
+    )]
+    pub sqlite: Option<PathBuf>,
+
+    #[arg(
+        long = "sql",
+        value_name = "QUERY",
+        requires = "sqlite",
+        help = "SQL query to run against the --sqlite database."
+    )]
+    pub sql: Option<String>,
+
+    #[cfg(feature = "kafka")]
+    #[arg(
+        long = "kafka",
+        value_name = "BROKERS",
+        requires = "topic",
+        help = "Follow the messages of a Kafka topic.",
+        long_help = "
+        Follows the messages of the topic given by --topic on the brokers
+        (e.g. broker:9092) with the kcat command, from the beginning,
+        and explores them as a stream of JSON documents,
+        in addition to the files if any, instead of standard input.
+        The interface starts once the end of the topic is reached,
+        and the messages arriving later are added on the --refresh interval
+        (every second by default).
+        Only available when jnv is built with the kafka feature.
+        "
+    )]
+    pub kafka: Option<String>,
+
+    #[cfg(feature = "kafka")]
+    #[arg(
+        long = "topic",
+        requires = "kafka",
+        help = "Kafka topic to follow with --kafka."
+    )]
+    pub topic: Option<String>,
+
+    #[arg(
+        long = "stdin-timeout",
+        value_name = "DURATION",
+        value_parser = duration_validator,
+        help = "Fail if no data arrives on standard input within this (e.g. '5s').",
+        long_help = "
+        Fails if no data arrives on standard input within the duration (e.g. '500ms' or '5s'),
+        so that a script launching jnv with no input connected fails fast instead of hanging.
+        Without it, jnv shows that it is waiting for standard input (e.g. a FIFO or a slow command)
+        after a second, and waits until data arrives or Ctrl+C is pressed.
+        "
+    )]
+    pub stdin_timeout: Option<Duration>,
+
+    #[arg(
+        long = "tee",
+        help = "Passes standard input through to standard output unmodified.",
+        long_help = "
+        Copies standard input unmodified to standard output as soon as it is read,
+        for the next stage of a pipeline (e.g. 'curl … | jnv --tee | jq …'),
+        while the interface is shown on the terminal. Standard input must be the only input.
+        The accepted filter is then printed on the terminal instead of its results.
+        "
+    )]
+    pub tee: bool,
+
+    #[arg(
+        long = "eval-timeout",
+        value_name = "DURATION",
+        value_parser = duration_validator,
+        help = "Give up on filters running longer than this (e.g. '5s').",
+        long_help = "
+        Abandons filters that run longer than the duration (e.g. '500ms' or '5s'),
+        such as an accidental '[range(1e9)]', keeping the previous results on screen
+        and warning that the filter timed out, so that a typo can't hang the interface.
+        The filters then run in a worker process, killed when a filter times out.
+        "
+    )]
+    pub eval_timeout: Option<Duration>,
+
+    #[arg(
+        long = "history-size",
+        value_name = "ENTRIES",
+        default_value = "500",
+        help = "Maximum number of entries kept in the history.",
+        long_help = "
+        Limits the accepted filters kept across sessions for completion,
+        the inputs whose accepted suggestions rank the completions,
+        and the inputs whose last accepted filter is offered again,
+        forgetting the oldest ones when a new one is added.
+        "
+    )]
+    pub history_size: usize,
+
+    #[arg(
+        long = "max-output",
+        value_name = "SIZE",
+        default_value = "64MB",
+        value_parser = byte_size_validator,
+        help = "Largest output of a filter to show (e.g. '64MB').",
+        long_help = "
+        Limits the total size of the results shown in the viewer
+        (e.g. '512KB', '64MB' or '1GB'), since viewing takes far more memory
+        than the text of the results. Filters producing more (e.g. cartesian products)
+        show the results up to the size, with a warning,
+        and jq stops evaluating them once the size is exceeded.
+        The output on accept is not limited.
+        "
+    )]
+    pub max_output: usize,
+
+    #[arg(
+        long = "refresh",
+        value_name = "INTERVAL",
+        value_parser = duration_validator,
+        help = "Read the input again on an interval (e.g. '5s').",
+        long_help = "
+        Reads the files, the command or the clipboard again on the interval
+        (e.g. '500ms', '5s' or '1m') and evaluates the filter over the new input,
+        keeping the cursor row where possible.
+        The input is read in the background, and a changed input is held back
+        while there are edits, until Ctrl + R discards them to load it.
+        Refreshing can be paused with Alt + X and forced with Ctrl + R.
+        Standard input can't be read again, so it can't be refreshed.
+        On Windows, the new input is shown with the next key pressed.
+        "
+    )]
+    pub refresh: Option<Duration>,
+
+    #[arg(
+        long = "clipboard",
+        help = "Read the input from the system clipboard.",
+        long_help = "
+        Reads the JSON in the system clipboard as the input,
+        in addition to the files if any, instead of standard input.
+        It relies on pbpaste on macOS, PowerShell on Windows,
+        and wl-paste, xclip or xsel elsewhere.
+        "
+    )]
+    pub clipboard: bool,
+
+    #[arg(
+        long = "exec",
+        value_name = "COMMAND",
+        help = "Read the input from the output of a shell command.",
+        long_help = "
+        Runs the command with the shell (sh, or cmd on Windows)
+        and reads its standard output as the input,
+        in addition to the files if any, instead of standard input
+        (e.g. --exec 'kubectl get pods -o json').
+        "
+    )]
+    pub exec: Option<String>,
+
+    #[arg(
+        short = 'm',
+        long = "merge",
+        help = "Deep-merge all input documents into one.",
+        long_help = "
+        Deep-merges all input documents, in the given order,
+        into a single document following JSON Merge Patch (RFC 7386):
+        objects are merged recursively and null removes the key.
+        Handy when configuration is split across base and override files.
+        "
+    )]
+    pub merge: bool,
+
+    #[arg(
+        long = "merge-arrays",
+        default_value = "replace",
+        value_parser = merge_arrays_validator,
+        help = "How --merge merges arrays ('replace' or 'concat').",
+        long_help = r#"
+        Specifies how arrays are merged with --merge.
+        Acceptable values are "replace" or "concat".
+        - "replace" replaces the earlier array with the later one, as in RFC 7386.
+        - "concat" appends the later array to the earlier one.
+        "#,
+    )]
+    pub merge_arrays: ArrayStrategy,
 
     #[arg(
         short = 'e',
@@ -72,14 +321,84 @@ pub struct Args {
     #[arg(
         short = 'n',
         long = "no-hint",
-        help = "Disables the display of hints.",
+        help = "Disables the display of hints (same as '--hint off').",
         long_help = "
         When this option is enabled, it prevents the display of
         hints that typically guide or offer suggestions to the user.
+        Same as '--hint off'.
         "
     )]
     pub no_hint: bool,
 
+    #[arg(
+        long = "hint",
+        default_value = "normal",
+        value_parser = verbosity_validator,
+        conflicts_with = "no_hint",
+        help = "How much the hints tell ('off', 'errors', 'normal' or 'verbose').",
+        long_help = r#"
+        Specifies what the hint pane shows about the evaluation of the filter.
+        It can also be changed in the settings overlay (F2).
+        - "off" shows no hints.
+        - "errors" shows only the failures to evaluate the filter.
+        - "normal" also warns of empty or null results and describes the value at a path.
+        - "verbose" also shows the time taken to evaluate the filter
+          and the number of paths indexed for suggestions.
+        "#,
+    )]
+    pub hint: Verbosity,
+
+    #[arg(
+        long = "highlight-changes",
+        help = "Highlights the rows changed by each edit of the filter.",
+        long_help = "
+        Highlights the rows of the JSON viewer that were not among the results
+        of the previous filter for a couple of seconds, showing the effect
+        of each edit on large outputs. Nothing is highlighted when most rows change.
+        It can also be toggled in the settings overlay (F2).
+        "
+    )]
+    pub highlight_changes: bool,
+
+    #[arg(
+        long = "hide-empty",
+        help = "Hides keys whose values are null or empty.",
+        long_help = "
+        Hides the keys of objects whose values are null, empty strings,
+        empty arrays or empty objects from the JSON viewer, noting how many
+        were hidden in each object. The filter and its results are unchanged.
+        It can also be toggled in the settings overlay (F2).
+        "
+    )]
+    pub hide_empty: bool,
+
+    #[arg(
+        long = "sample-arrays",
+        help = "Shows a sample of the items of arrays longer than 100 items.",
+        long_help = "
+        Shows only every Nth item of the arrays longer than 100 items in the JSON viewer,
+        about 20 items each followed by a note on the items skipped after it,
+        for an overview of huge arrays without rendering every item.
+        The filter and its results are unchanged.
+        It can also be toggled in the settings overlay (F2).
+        "
+    )]
+    pub sample_arrays: bool,
+
+    #[arg(
+        long = "max-depth",
+        value_name = "N",
+        help = "Shows only N levels of JSON nodes in the visualization.",
+        long_help = "
+        Shows only N levels of objects and arrays in the visualization,
+        noting the size of those below (e.g. '{… 3 keys}') instead of rendering them,
+        which makes the first paint of deeply nested data much faster.
+        Alt + '+' and Alt + '-' show a level more or less at any time.
+        The filter and its results are unchanged.
+        "
+    )]
+    pub max_depth: Option<usize>,
+
     #[arg(
         short = 'd',
         long = "expand-depth",
@@ -103,6 +422,307 @@ pub struct Args {
         "
     )]
     pub suggestion_list_length: usize,
+
+    #[arg(
+        long = "suggestion-case",
+        default_value = "smart",
+        value_parser = suggestion_case_validator,
+        help = "Case matching of suggestions ('sensitive', 'insensitive' or 'smart').",
+        long_help = r#"
+        Specifies how the letter case of the path being completed is matched.
+        Acceptable values are "sensitive", "insensitive" or "smart".
+        - "sensitive" matches the case exactly.
+        - "insensitive" ignores the case.
+        - "smart" ignores the case unless the path contains an uppercase letter.
+        "#,
+    )]
+    pub suggestion_case: CaseSensitivity,
+
+    #[arg(
+        long = "editor-lines",
+        help = "Maximum number of lines for the filter editor.",
+        long_help = "
+        Limits the height of the filter editor,
+        which wraps long filters over multiple lines.
+        "
+    )]
+    pub editor_lines: Option<usize>,
+
+    #[arg(
+        long = "result-lines",
+        help = "Maximum number of lines for the JSON viewer.",
+        long_help = "
+        Limits the height of the JSON viewer,
+        which otherwise takes up the rest of the terminal.
+        "
+    )]
+    pub result_lines: Option<usize>,
+
+    #[arg(
+        long = "hint-lines",
+        help = "Maximum number of lines for the hint pane.",
+        long_help = "
+        Limits the height of the hint pane,
+        which wraps long hints (e.g. errors) over multiple lines.
+        "
+    )]
+    pub hint_lines: Option<usize>,
+
+    #[arg(
+        long = "hide-hint",
+        help = "Hides the hint pane at start (Alt+I shows it).",
+        long_help = "
+        Starts with the hint pane hidden, leaving its space to the JSON viewer,
+        until it is shown with Alt+I or in the settings overlay (F2).
+        Unlike --hint off, the hints are still kept up to date meanwhile.
+        "
+    )]
+    pub hide_hint: bool,
+
+    #[arg(
+        long = "layout",
+        default_value = "top",
+        value_parser = layout_validator,
+        help = "Position of the filter editor ('top' or 'bottom').",
+        long_help = r#"
+        Specifies where the filter editor and suggestions are placed.
+        Acceptable values are "top" or "bottom".
+        - "top" places them above the JSON viewer.
+        - "bottom" places them below the JSON viewer, like fzf's default layout.
+        "#,
+    )]
+    pub layout: Layout,
+
+    #[arg(
+        long = "no-alt-screen",
+        help = "Render inline in the main screen instead of the alternate screen.",
+        long_help = "
+        Renders the interface inline in the main screen buffer
+        instead of the alternate screen, so that the final query
+        and result remain in the scrollback after exiting.
+        "
+    )]
+    pub no_alt_screen: bool,
+
+    #[arg(
+        long = "debug-hud",
+        help = "Shows the frame rate, query latency and memory usage.",
+        long_help = "
+        Shows a line with the frames drawn in the last second, the latency of
+        the last evaluation of the filter and its 95th percentile over the last
+        100 evaluations, the number of paths indexed for suggestions and
+        the memory used by jnv (on Linux), to check or report its performance.
+        It can also be toggled with F12.
+        "
+    )]
+    pub debug_hud: bool,
+
+    #[arg(
+        long = "report-data",
+        help = "Includes the input in the report of an unexpected error.",
+        long_help = "
+        On an unexpected error, jnv offers to write a diagnostic report
+        to attach to a GitHub issue: its version, the terminal, the size
+        and shape of the input, the last filter and the last messages shown,
+        with the quoted values in them redacted.
+        With this flag, the input itself and the messages as shown are included too.
+        "
+    )]
+    pub report_data: bool,
+
+    #[arg(
+        long = "yank-file",
+        help = "File to append yanked values to, instead of the clipboard.",
+        long_help = "
+        Specifies the file to which the values yanked with Alt + Y are appended
+        as JSON Lines. Without it, values are copied to the clipboard
+        through the terminal (OSC 52).
+        "
+    )]
+    pub yank_file: Option<PathBuf>,
+
+    #[arg(
+        long = "export",
+        value_name = "NAME",
+        help = "Convert the results with an exporter plugin.",
+        long_help = "
+        Writes the results of the accepted filter through the plugin
+        export-<NAME> (or the WebAssembly module export-<NAME>.wasm)
+        in ~/.config/jnv/plugins/ (or under $XDG_CONFIG_HOME),
+        which receives them as JSON Lines as its input.
+        "
+    )]
+    pub export: Option<String>,
+
+    #[arg(
+        long = "output-format",
+        value_name = "FORMAT",
+        default_value = "json",
+        value_parser = output_format_validator,
+        help = "Format of the results output on accept ('json', 'yaml', 'toml', 'gron' or 'jsonl').",
+        long_help = r#"
+        Specifies the format of the results of the accepted filter written to a redirected output,
+        also used by the pager (F4). It can also be changed in the settings overlay (F2).
+        - "json" writes each result as JSON, as jq does.
+        - "yaml" writes a YAML document for each result, separated by '---',
+          for tools consuming YAML (e.g. Kubernetes or Ansible).
+        - "toml" writes the single object produced by the filter as a TOML document,
+          for config file fragments, or fails listing the values TOML can't hold (null).
+        - "gron" writes an assignment for every value (e.g. 'json.items[0].name = "x";'),
+          like gron, to grep and diff the output.
+        - "jsonl" writes the items of an array, or each result of a stream,
+          as compact JSON on its own line (NDJSON), for log ingestion and bulk imports.
+        "#
+    )]
+    pub output_format: OutputFormat,
+
+    #[arg(
+        short = 'a',
+        long = "ascii-output",
+        help = "Escapes non-ASCII characters in the output, like jq -a.",
+        long_help = "
+        Escapes the characters beyond ASCII as '\\uXXXX' in the results output on accept,
+        in the pager and in exports, like jq's --ascii-output,
+        for systems that choke on raw UTF-8. The JSON viewer is unchanged.
+        It can also be toggled in the settings overlay (F2).
+        "
+    )]
+    pub ascii_output: bool,
+
+    #[arg(
+        long = "color",
+        value_name = "WHEN",
+        default_value = "auto",
+        value_parser = color_validator,
+        help = "When to color the output with ANSI escape codes ('auto', 'always' or 'never').",
+        long_help = r#"
+        Specifies when the output outside the interface is colored with ANSI escape codes,
+        with the colors of the JSON viewer.
+        Acceptable values are "auto", "always" or "never".
+        - "auto" colors the pager (F4) and the text exports (F5),
+          unless the NO_COLOR environment variable is set,
+          and leaves the results output on accept plain as they are piped.
+        - "always" also colors the JSON results output on accept,
+          to be read with 'less -R' or kept in CI logs.
+        - "never" colors nothing, including the rows printed by 'jnv render'.
+        "#
+    )]
+    pub color: ColorChoice,
+
+    #[arg(
+        long = "listen",
+        value_name = "SOCKET",
+        help = "Serve control requests on a Unix socket.",
+        long_help = r#"
+        Serves JSON-RPC 2.0 requests, one per line, on the Unix socket
+        so that other programs (e.g. editor plugins or tests) can drive jnv.
+        Methods: "set_query" {"query"}, "get_query", "get_result",
+        "load" {"path"} and "scroll" {"row"}.
+        A socket left by a session that ended is taken over,
+        but not one still served or a file of another kind.
+        "#
+    )]
+    pub listen: Option<PathBuf>,
+
+    #[arg(
+        long = "replay",
+        value_name = "SCRIPT",
+        help = "Replay scripted events without a terminal and print the screen.",
+        long_help = r#"
+        Feeds the events in the script, one JSON object per line,
+        to the interface without a terminal and prints the rows it shows at the end,
+        for end-to-end tests of key bindings and rendering.
+        Each line is one of {"key": "Alt+k"}, {"text": ".items"},
+        {"paste": ".items[0]"} or {"resize": [120, 40]}.
+        "#
+    )]
+    pub replay: Option<PathBuf>,
+
+    #[arg(
+        long = "replay-size",
+        value_name = "COLUMNSxROWS",
+        default_value = "80x24",
+        value_parser = size_validator,
+        help = "Size of the terminal for --replay."
+    )]
+    pub replay_size: (u16, u16),
+
+    #[arg(
+        long = "save-edits",
+        help = "File to save the edited values to on accept.",
+        long_help = "
+        Specifies the file to which the values edited in jnv are saved
+        when the filter is accepted. The format is set by --edits-format.
+        "
+    )]
+    pub save_edits: Option<PathBuf>,
+
+    #[arg(
+        long = "edits-format",
+        default_value = "document",
+        value_parser = edits_format_validator,
+        help = "Format of the saved edits ('document' or 'patch').",
+        long_help = r#"
+        Specifies the format of the edits saved with --save-edits.
+        Acceptable values are "document" or "patch".
+        - "document" saves the whole input with the edits applied.
+        - "patch" saves JSON Patches (RFC 6902) describing the edits, one for each document.
+        "#,
+    )]
+    pub edits_format: EditsFormat,
+}
+
+impl Args {
+    /// The brokers and the Kafka topic to follow, if any.
+    #[cfg(feature = "kafka")]
+    fn topic(&self) -> Option<(&str, &str)> {
+        self.kafka.as_deref().zip(self.topic.as_deref())
+    }
+
+    #[cfg(not(feature = "kafka"))]
+    fn topic(&self) -> Option<(&str, &str)> {
+        None
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    /// Print the screen for a filter without the interactive mode,
+    /// as styled text (e.g. for golden tests or screenshots).
+    Render {
+        /// Number of columns of the screen.
+        #[arg(long, default_value = "80")]
+        width: u16,
+        /// Number of rows of the screen.
+        #[arg(long, default_value = "24")]
+        height: u16,
+        /// Filter to evaluate.
+        #[arg(long, default_value = "")]
+        filter: String,
+        /// Print the text without styles.
+        #[arg(long)]
+        plain: bool,
+        /// Paths to JSON files, as for the interactive mode.
+        inputs: Vec<PathBuf>,
+    },
+    /// Measure the time to parse and index the input and to evaluate a filter.
+    Bench {
+        /// Filter to evaluate.
+        #[arg(long, default_value = ".")]
+        filter: String,
+        /// Number of times to evaluate the filter.
+        #[arg(long, default_value = "20")]
+        runs: usize,
+        /// Paths to JSON files, as for the interactive mode.
+        inputs: Vec<PathBuf>,
+    },
+}
+
+/// Format of the edits saved at the end of a session.
+#[derive(Clone)]
+pub enum EditsFormat {
+    Document,
+    Patch,
 }
 
 fn edit_mode_validator(val: &str) -> Result<text_editor::Mode> {
@@ -113,43 +733,930 @@ fn edit_mode_validator(val: &str) -> Result<text_editor::Mode> {
     }
 }
 
+fn suggestion_case_validator(val: &str) -> Result<CaseSensitivity> {
+    match val {
+        "smart" | "" => Ok(CaseSensitivity::Smart),
+        "sensitive" => Ok(CaseSensitivity::Sensitive),
+        "insensitive" => Ok(CaseSensitivity::Insensitive),
+        _ => Err(anyhow!(
+            "suggestion-case must be 'sensitive', 'insensitive' or 'smart'"
+        )),
+    }
+}
+
+fn verbosity_validator(val: &str) -> Result<Verbosity> {
+    match val {
+        "off" => Ok(Verbosity::Off),
+        "errors" => Ok(Verbosity::Errors),
+        "normal" | "" => Ok(Verbosity::Normal),
+        "verbose" => Ok(Verbosity::Verbose),
+        _ => Err(anyhow!(
+            "hint must be 'off', 'errors', 'normal' or 'verbose'"
+        )),
+    }
+}
+
+fn output_format_validator(val: &str) -> Result<OutputFormat> {
+    match val {
+        "json" | "" => Ok(OutputFormat::Json),
+        "yaml" => Ok(OutputFormat::Yaml),
+        "toml" => Ok(OutputFormat::Toml),
+        "gron" => Ok(OutputFormat::Gron),
+        "jsonl" => Ok(OutputFormat::JsonLines),
+        _ => Err(anyhow!(
+            "output-format must be 'json', 'yaml', 'toml', 'gron' or 'jsonl'"
+        )),
+    }
+}
+
+fn color_validator(val: &str) -> Result<ColorChoice> {
+    match val {
+        "auto" | "" => Ok(ColorChoice::Auto),
+        "always" => Ok(ColorChoice::Always),
+        "never" => Ok(ColorChoice::Never),
+        _ => Err(anyhow!("color must be 'auto', 'always' or 'never'")),
+    }
+}
+
+fn layout_validator(val: &str) -> Result<Layout> {
+    match val {
+        "top" | "" => Ok(Layout::Top),
+        "bottom" => Ok(Layout::Bottom),
+        _ => Err(anyhow!("layout must be 'top' or 'bottom'")),
+    }
+}
+
+fn size_validator(val: &str) -> Result<(u16, u16)> {
+    val.split_once('x')
+        .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+        .filter(|(width, height)| *width > 0 && *height > 0)
+        .ok_or_else(|| anyhow!("size must be columns and rows (e.g. '80x24')"))
+}
+
+fn duration_validator(val: &str) -> Result<Duration> {
+    let (number, unit) = val
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .map_or((val, "s"), |i| val.split_at(i));
+    let number = number
+        .parse::<f64>()
+        .map_err(|_| anyhow!("interval must be a number with a unit (e.g. '5s')"))?;
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "s" => number,
+        "m" => number * 60.0,
+        _ => return Err(anyhow!("interval unit must be 'ms', 's' or 'm'")),
+    };
+    Duration::try_from_secs_f64(seconds)
+        .ok()
+        .filter(|interval| !interval.is_zero())
+        .ok_or_else(|| anyhow!("interval must be positive and finite"))
+}
+
+fn byte_size_validator(val: &str) -> Result<usize> {
+    let (number, unit) = val
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or((val, ""), |i| val.split_at(i));
+    let number = number
+        .parse::<usize>()
+        .map_err(|_| anyhow!("size must be a number with a unit (e.g. '64MB')"))?;
+    let unit = match unit.to_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" => 1 << 10,
+        "MB" => 1 << 20,
+        "GB" => 1 << 30,
+        _ => return Err(anyhow!("size unit must be 'B', 'KB', 'MB' or 'GB'")),
+    };
+    number
+        .checked_mul(unit)
+        .filter(|size| *size > 0)
+        .ok_or_else(|| anyhow!("size must be positive"))
+}
+
+fn edits_format_validator(val: &str) -> Result<EditsFormat> {
+    match val {
+        "document" | "" => Ok(EditsFormat::Document),
+        "patch" => Ok(EditsFormat::Patch),
+        _ => Err(anyhow!("edits-format must be 'document' or 'patch'")),
+    }
+}
+
+/// Saves the edited input in the given format.
+fn save_edits(path: &PathBuf, format: &EditsFormat, edits: Edits) -> Result<()> {
+    let mut file = File::create(path)?;
+    match format {
+        EditsFormat::Document => {
+            for document in edits.documents {
+                writeln!(file, "{}", document)?;
+            }
+        }
+        EditsFormat::Patch => {
+            for patch in edits.patches {
+                writeln!(file, "{}", serde_json::to_string_pretty(&patch)?)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn merge_arrays_validator(val: &str) -> Result<ArrayStrategy> {
+    match val {
+        "replace" | "" => Ok(ArrayStrategy::Replace),
+        "concat" => Ok(ArrayStrategy::Concat),
+        _ => Err(anyhow!("merge-arrays must be 'replace' or 'concat'")),
+    }
+}
+
 /// Parses the input based on the provided arguments.
 ///
-/// This function reads input data from either a specified file or standard input.
-/// If the `input` argument is `None`, or if it is a path
-/// that equals "-", data is read from standard input.
+/// This function reads input data from either the specified files or standard input.
+/// If the `inputs` argument is empty, or for a path
+/// that equals "-", data is read from standard input,
+/// unless the clipboard, the output of a command, a SQLite query
+/// or a Kafka topic is read instead.
 /// Otherwise, the function attempts to open and
-/// read from the file specified in the `input` argument.
-fn parse_input(args: &Args) -> Result<String> {
-    let mut ret = String::new();
+/// read from the files specified in the `inputs` argument.
+/// Directories are left to the file picker,
+/// and the first file of the workspace is read if only directories are given.
+/// The progress of reading huge inputs is reported on standard error.
+fn parse_input(
+    args: &Args,
+    workspace: &[PathBuf],
+    plugins: &Plugins,
+    progress: &mut Progress,
+) -> Result<Vec<String>> {
+    let mut inputs = Vec::new();
 
-    match &args.input {
-        None => {
-            io::stdin().read_to_string(&mut ret)?;
-        }
-        Some(path) => {
-            if path == &PathBuf::from("-") {
-                io::stdin().read_to_string(&mut ret)?;
+    if args.clipboard {
+        inputs.push(jnv::paste_from_clipboard()?);
+    }
+    if let Some(command) = &args.exec {
+        inputs.push(run_command(command, progress)?);
+    }
+    if let (Some(database), Some(sql)) = (&args.sqlite, &args.sql) {
+        inputs.push(run_sql(database, sql, progress)?);
+    }
+    #[cfg(feature = "kafka")]
+    if let Some((brokers, topic)) = args.topic() {
+        inputs.push(kafka::consume(brokers, topic, progress)?);
+    }
+    if args.inputs.is_empty() && inputs.is_empty() {
+        inputs.push(progress.read_stdin(args.stdin_timeout)?);
+    }
+    let mut paths = args
+        .inputs
+        .iter()
+        .filter(|path| !path.is_dir())
+        .collect::<Vec<_>>();
+    if paths.is_empty() && !args.inputs.is_empty() && inputs.is_empty() {
+        let first = workspace
+            .first()
+            .ok_or_else(|| anyhow!("No JSON or YAML files found in the directories"))?;
+        paths.push(first);
+    }
+    for path in paths {
+        let url = path.to_string_lossy();
+        if path == &PathBuf::from("-") {
+            inputs.push(progress.read_stdin(args.stdin_timeout)?);
+        } else if url.starts_with("s3://") || url.starts_with("gs://") {
+            inputs.push(download_object(&url, progress)?);
+        } else if let Some(revision) = &args.git_rev {
+            inputs.push(show_revision(path, revision, progress)?);
+        } else if let Some(decoder) = plugins.decoder(path) {
+            inputs.push(match decoder {
+                Plugin::Executable(decoder) => read_output(
+                    &path.display().to_string(),
+                    Command::new(decoder),
+                    Stdio::from(File::open(path)?),
+                    progress,
+                )?,
+                #[cfg(feature = "wasm")]
+                module @ Plugin::Module(_) => String::from_utf8(module.run(fs::read(path)?)?)?,
+            });
+        } else {
+            let file = File::open(path)?;
+            let size = file.metadata()?.len();
+            let input = progress.read(&path.display().to_string(), file, Some(size))?;
+            inputs.push(if jnv::is_yaml(path) {
+                jnv::yaml_to_json(&input)?
             } else {
-                File::open(path)?.read_to_string(&mut ret)?;
+                input
+            });
+        }
+    }
+
+    Ok(inputs)
+}
+
+/// Returns the file the input is read from if it is the only input,
+/// which the accepted filter is kept for, or none for the other inputs
+/// (e.g. stdin or a command), whose filter is kept for their content.
+fn source_file(args: &Args) -> Option<PathBuf> {
+    let [path] = args.inputs.as_slice() else {
+        return None;
+    };
+    let only = !args.clipboard
+        && args.exec.is_none()
+        && args.sqlite.is_none()
+        && args.topic().is_none()
+        && args.git_rev.is_none();
+    (only && path.is_file()).then(|| path.clone())
+}
+
+/// Deep-merges the inputs into one document if specified,
+/// or else joins them to be explored as a stream of documents.
+fn join_inputs(args: &Args, inputs: &[String]) -> Result<String> {
+    if args.merge {
+        merge::merge_documents(inputs, &args.merge_arrays)
+    } else {
+        Ok(inputs.join("\n"))
+    }
+}
+
+/// Runs the command with the shell and reads its standard output.
+fn run_command(command: &str, progress: &mut Progress) -> Result<String> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut child = Command::new(shell);
+    child.args([flag, command]);
+    read_output(command, child, Stdio::null(), progress)
+}
+
+/// Runs the query against the SQLite database with the sqlite3 command,
+/// which prints the rows as a JSON array of objects.
+fn run_sql(database: &Path, sql: &str, progress: &mut Progress) -> Result<String> {
+    let mut command = Command::new("sqlite3");
+    command.args(["-readonly", "-json"]).arg(database).arg(sql);
+    let output = read_output(sql, command, Stdio::null(), progress)?;
+    // sqlite3 prints nothing, rather than an empty array, for no rows.
+    if output.trim().is_empty() {
+        return Ok("[]".to_string());
+    }
+    Ok(output)
+}
+
+/// Streams the object from S3 (`s3://bucket/key`) with the aws command
+/// or from GCS (`gs://bucket/key`) with the gcloud command,
+/// which use the credentials configured for them.
+#[cfg(feature = "cloud")]
+fn download_object(url: &str, progress: &mut Progress) -> Result<String> {
+    let command = if url.starts_with("s3://") {
+        let mut command = Command::new("aws");
+        command.args(["s3", "cp", "--quiet", url, "-"]);
+        command
+    } else {
+        let mut command = Command::new("gcloud");
+        command.args(["storage", "cat", url]);
+        command
+    };
+    read_output(url, command, Stdio::null(), progress)
+}
+
+#[cfg(not(feature = "cloud"))]
+fn download_object(url: &str, _: &mut Progress) -> Result<String> {
+    Err(anyhow!(
+        "Reading '{}' requires jnv built with the cloud feature",
+        url
+    ))
+}
+
+/// Reads the file as of the revision from the git repository containing it.
+fn show_revision(path: &Path, revision: &str, progress: &mut Progress) -> Result<String> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("'{}' is not a file", path.display()))?;
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(dir.unwrap_or(Path::new(".")))
+        .arg("show")
+        .arg(format!("{}:./{}", revision, name.to_string_lossy()));
+    read_output(
+        &format!("{}:{}", revision, path.display()),
+        command,
+        Stdio::null(),
+        progress,
+    )
+}
+
+/// The commands some inputs are read through, with where to install them from.
+const PROGRAMS: [(&str, &str); 4] = [
+    ("sqlite3", "https://sqlite.org/download.html"),
+    ("kcat", "https://github.com/edenhill/kcat"),
+    ("aws", "https://aws.amazon.com/cli/"),
+    ("gcloud", "https://cloud.google.com/sdk/docs/install"),
+];
+
+/// Returns the error for a command of `PROGRAMS` which can't be run.
+fn not_installed(program: &str) -> anyhow::Error {
+    let url = PROGRAMS
+        .iter()
+        .find(|(name, _)| *name == program)
+        .map_or("", |(_, url)| url);
+    anyhow!(
+        "'{}' is required but not found on PATH (install it from {})",
+        program,
+        url
+    )
+}
+
+/// Checks up front that the commands the inputs are read through are installed,
+/// rather than failing after the interface started (e.g. with `--sqlite` without sqlite3),
+/// given how to tell whether a command is on PATH.
+fn check_programs(args: &Args, installed: impl Fn(&str) -> bool) -> Result<()> {
+    let mut needed = Vec::new();
+    if args.sqlite.is_some() {
+        needed.push(("sqlite3", "--sqlite"));
+    }
+    if args.topic().is_some() {
+        needed.push(("kcat", "--kafka"));
+    }
+    if cfg!(feature = "cloud") {
+        for path in &args.inputs {
+            let url = path.to_string_lossy();
+            if url.starts_with("s3://") {
+                needed.push(("aws", "s3:// inputs"));
+            } else if url.starts_with("gs://") {
+                needed.push(("gcloud", "gs:// inputs"));
             }
         }
     }
+    match needed.into_iter().find(|(program, _)| !installed(program)) {
+        Some((program, input)) => Err(anyhow!("{}: {}", input, not_installed(program))),
+        None => Ok(()),
+    }
+}
+
+/// Returns whether the command is found in a directory of PATH.
+fn on_path(program: &str) -> bool {
+    env::var_os("PATH").is_some_and(|paths| {
+        env::split_paths(&paths).any(|dir| {
+            let path = dir.join(program);
+            path.is_file() || (cfg!(windows) && path.with_extension("exe").is_file())
+        })
+    })
+}
+
+/// Runs the command with the input and reads its standard output.
+///
+/// Its standard error is captured rather than left to the terminal,
+/// which the interface may be drawn on, and reported if it fails.
+fn read_output(
+    name: &str,
+    mut command: Command,
+    stdin: Stdio,
+    progress: &mut Progress,
+) -> Result<String> {
+    let program = command.get_program().to_string_lossy().to_string();
+    // The command may take a while before the first data (e.g. to authenticate).
+    progress.step(&format!("Waiting for {}…", name))?;
+    let mut child = command
+        .stdin(stdin)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => not_installed(&program),
+            _ => anyhow!("Failed to run {}: {}", program, e),
+        })?;
+    let (Some(stdout), Some(mut stderr)) = (child.stdout.take(), child.stderr.take()) else {
+        return Err(anyhow!("Failed to capture the output of '{}'", name));
+    };
+    // Read aside, so that the command is not blocked on a full pipe.
+    let errors = thread::spawn(move || {
+        let mut errors = String::new();
+        let _ = stderr.read_to_string(&mut errors);
+        errors
+    });
+    let output = progress.read(name, stdout, None);
+    let status = child.wait()?;
+    let errors = errors.join().unwrap_or_default();
+    if !status.success() {
+        return Err(match errors.trim() {
+            "" => anyhow!("'{}' failed with {}", name, status),
+            errors => anyhow!("'{}' failed with {}: {}", name, status, errors),
+        });
+    }
+    output
+}
+
+/// Collects the JSON and YAML files in the directories of the `inputs` argument,
+/// recursively and in order, skipping hidden entries.
+/// Directories reached again through symbolic links (e.g. a link to a parent)
+/// are walked only once.
+fn collect_workspace(args: &Args) -> Result<Vec<PathBuf>> {
+    fn walk(
+        dir: &Path,
+        visited: &mut HashSet<PathBuf>,
+        files: &mut Vec<PathBuf>,
+    ) -> io::Result<()> {
+        if !visited.insert(fs::canonicalize(dir)?) {
+            return Ok(());
+        }
+        let mut entries = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+        for path in entries {
+            let hidden = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            if hidden {
+                continue;
+            }
+            if path.is_dir() {
+                walk(&path, visited, files)?;
+            } else if path.extension().is_some_and(|ext| {
+                ["json", "jsonl", "ndjson", "yaml", "yml"].contains(&&*ext.to_string_lossy())
+            }) {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    let (mut visited, mut files) = (HashSet::new(), Vec::new());
+    for dir in args.inputs.iter().filter(|path| path.is_dir()) {
+        walk(dir, &mut visited, &mut files)?;
+    }
+    Ok(files)
+}
+
+/// Checks that key events can be read from the controlling terminal.
+///
+/// When the data is piped into jnv, standard input is left to the pipe
+/// (e.g. to keep reading a stream), and crossterm reads the key events
+/// from `/dev/tty`, opened as a separate handle, instead.
+#[cfg(unix)]
+fn check_tty() -> Result<()> {
+    open_tty()
+        .map(drop)
+        .map_err(|e| anyhow!("No terminal to read the keys from: {}", e))
+}
 
-    Ok(ret)
+/// Opens the terminal to read an answer from, regardless of standard input.
+#[cfg(unix)]
+fn open_tty() -> io::Result<File> {
+    File::open("/dev/tty")
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
-    let mut prompt = Jnv::try_new(
-        parse_input(&args)?,
-        args.expand_depth,
-        args.no_hint,
-        args.edit_mode,
-        args.indent,
-        args.suggestion_list_length,
-    )?
-    .prompt()?;
-    let _ = prompt.run()?;
+/// Opens the console input buffer to read an answer from,
+/// regardless of standard input.
+#[cfg(not(unix))]
+fn open_tty() -> io::Result<File> {
+    fs::OpenOptions::new().read(true).write(true).open("CONIN$")
+}
+
+/// On Windows, key events are read from the console input buffer
+/// (`CONIN$`), which is independent of a redirected standard input.
+#[cfg(not(unix))]
+fn check_tty() -> Result<()> {
     Ok(())
 }
+
+/// Detaches standard output from the interface.
+///
+/// When standard output is redirected, the interface is rendered
+/// to the terminal instead, and the original standard output is
+/// returned so that only the data is written to it.
+#[cfg(unix)]
+fn detach_stdout() -> Result<Box<dyn Write>> {
+    use std::{
+        fs::OpenOptions,
+        os::unix::io::{AsRawFd, FromRawFd},
+    };
+
+    // SAFETY: STDOUT_FILENO is open for the whole process.
+    let stdout = unsafe { libc::dup(libc::STDOUT_FILENO) };
+    if stdout < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    let tty = OpenOptions::new().write(true).open("/dev/tty")?;
+    // SAFETY: both file descriptors are valid for the duration of the call.
+    if unsafe { libc::dup2(tty.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    // SAFETY: the file descriptor was just duplicated and is owned by nobody else.
+    Ok(Box::new(unsafe { File::from_raw_fd(stdout) }))
+}
+
+#[cfg(not(unix))]
+fn detach_stdout() -> Result<Box<dyn Write>> {
+    Ok(Box::new(io::stdout()))
+}
+
+/// Formats the result of the filter for output, like jq's --raw-output
+/// and --compact-output.
+fn format_result(result: &str, raw: bool, compact: bool) -> Result<String> {
+    if !raw && !compact {
+        return Ok(result.to_string());
+    }
+    match serde_json::from_str::<serde_json::Value>(result)? {
+        serde_json::Value::String(s) if raw => Ok(s),
+        value if compact => Ok(serde_json::to_string(&value)?),
+        _ => Ok(result.to_string()),
+    }
+}
+
+/// How often the messages arriving on a followed topic are added without --refresh.
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Exit status on an unexpected error, the same as for the errors returned from `main`.
+const EXIT_ERROR: u8 = 1;
+/// Exit status when the session is aborted (e.g. with Esc or Ctrl+C),
+/// as nothing was accepted to be printed.
+const EXIT_ABORTED: u8 = 1;
+/// Exit status when the input cannot be parsed as JSON.
+const EXIT_INVALID_INPUT: u8 = 2;
+/// Exit status when the accepted query fails to be evaluated.
+const EXIT_QUERY_FAILED: u8 = 3;
+
+/// Returns the exit status for the error preparing the session,
+/// telling the input that failed to be parsed from the other errors.
+fn exit_status(e: &anyhow::Error) -> u8 {
+    if e.chain().any(|cause| cause.is::<serde_json::Error>()) {
+        EXIT_INVALID_INPUT
+    } else {
+        EXIT_ERROR
+    }
+}
+
+/// Asks whether to write a diagnostic report of the unexpected error
+/// to attach to a GitHub issue, and writes it to the current directory if so.
+fn offer_report(error: &anyhow::Error) -> Result<()> {
+    if !io::stderr().is_terminal() {
+        return Ok(());
+    }
+    // Asked on the terminal, as the input may have been piped into stdin.
+    let Ok(tty) = open_tty() else {
+        return Ok(());
+    };
+    // The error may have left the terminal in raw mode.
+    terminal::disable_raw_mode()?;
+    eprint!("jnv hit an unexpected error. Write a diagnostic report to attach to a GitHub issue? [y/N] ");
+    let mut answer = String::new();
+    io::BufReader::new(tty).read_line(&mut answer)?;
+    if !answer.trim().eq_ignore_ascii_case("y") {
+        return Ok(());
+    }
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let path = PathBuf::from(format!("jnv-report-{}.md", timestamp));
+    match write_report(&path, error) {
+        Ok(()) => eprintln!(
+            "Wrote {}. Please check it before attaching it to an issue at https://github.com/ynqa/jnv/issues.",
+            path.display()
+        ),
+        Err(e) => eprintln!("Failed to write the report: {}", e),
+    }
+    Ok(())
+}
+
+fn main() -> Result<ExitCode> {
+    // Spawned to evaluate the queries given --eval-timeout.
+    if std::env::var_os(jnv::JQ_WORKER_ENV).is_some() {
+        jnv::serve_jq_worker()?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    let mut args = Args::parse();
+    if let Some(Commands::Render { inputs, .. } | Commands::Bench { inputs, .. }) =
+        &mut args.command
+    {
+        args.inputs.append(inputs);
+    }
+    let args = Arc::new(args);
+    let reads_stdin = args.inputs.iter().any(|path| path == &PathBuf::from("-"))
+        || (args.inputs.is_empty()
+            && !args.clipboard
+            && args.exec.is_none()
+            && args.sqlite.is_none()
+            && args.topic().is_none());
+    if args.refresh.is_some() && reads_stdin {
+        return Err(anyhow!("Standard input can't be refreshed"));
+    }
+    if args.tee && (!reads_stdin || args.inputs.len() > 1) {
+        return Err(anyhow!(
+            "Standard input must be the only input to be passed through"
+        ));
+    }
+    check_programs(&args, on_path)?;
+    // Listed once for every use, including the inputs read again.
+    let plugins = Plugins::open();
+    let (definitions, skipped) = plugins.definitions()?;
+    for message in skipped {
+        eprintln!("Warning: {}", message);
+    }
+    jnv::define_jq_functions(definitions);
+    let hooks = plugins
+        .hooks()
+        .filter(|script| match jnv::check_hooks(script) {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("Warning: Skipped '{}': {}", script.display(), e);
+                false
+            }
+        });
+    if let Some(timeout) = args.eval_timeout {
+        jnv::set_eval_timeout(timeout);
+    }
+    jnv::set_history_size(args.history_size);
+    if args.report_data {
+        include_data_in_reports();
+    }
+    let theme = match config_dir() {
+        Some(dir) => Theme::load(&dir.join("theme.json"))?,
+        None => Theme::default(),
+    };
+    let mut progress = Progress::new();
+    let workspace = collect_workspace(&args)?;
+    // The interface starts at once, with the input read and parsed by a thread,
+    // unless the input is needed before (e.g. to be passed through)
+    // or the thread can't wake the interface up when it is done.
+    let loading =
+        jnv::WAKE_SUPPORTED && args.command.is_none() && args.replay.is_none() && !args.tee;
+    let input = if loading {
+        Ok(jnv::PLACEHOLDER.to_string())
+    } else {
+        let inputs = parse_input(&args, &workspace, &plugins, &mut progress)?;
+        if args.tee && !io::stdout().is_terminal() {
+            // Passed through before the interface starts, and closed,
+            // so that the next stage of the pipeline is not held up.
+            let mut stdout = detach_stdout()?;
+            stdout.write_all(inputs[0].as_bytes())?;
+            stdout.flush()?;
+        }
+        progress.message("Parsing the input…")?;
+        join_inputs(&args, &inputs)
+    };
+    if let Some(Commands::Bench { filter, runs, .. }) = &args.command {
+        progress.clear()?;
+        for line in jnv::bench(&input?, filter, *runs)? {
+            println!("{}", line);
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    let jnv = match input.and_then(|input| {
+        Jnv::try_new(
+            input,
+            args.expand_depth,
+            args.edit_mode.clone(),
+            args.indent,
+            args.suggestion_list_length,
+        )
+        .map(|jnv| {
+            jnv.editor_lines(args.editor_lines)
+                .result_lines(args.result_lines)
+                .hint_lines(args.hint_lines)
+                .hint_visible(!args.hide_hint)
+                .layout(args.layout)
+                .verbosity(if args.no_hint {
+                    Verbosity::Off
+                } else {
+                    args.hint
+                })
+                .suggestion_case(args.suggestion_case)
+                .yank_file(args.yank_file.clone())
+                .workspace(workspace.clone())
+                .source(source_file(&args))
+                .hooks(hooks)
+                .listen(args.listen.clone())
+                .theme(theme)
+                .highlight_changes(args.highlight_changes)
+                .hide_empty(args.hide_empty)
+                .sample_arrays(args.sample_arrays)
+                .debug_hud(args.debug_hud)
+                .max_depth(args.max_depth)
+                .max_output(args.max_output)
+                .output_format(args.output_format)
+                .ascii_output(args.ascii_output)
+                .color(args.color)
+        })
+        .map(|jnv| {
+            if !loading {
+                return jnv;
+            }
+            let (args, workspace, plugins) =
+                (Arc::clone(&args), workspace.clone(), plugins.clone());
+            jnv.loading(Box::new(move || {
+                let inputs = parse_input(&args, &workspace, &plugins, &mut Progress::silent())?;
+                join_inputs(&args, &inputs)
+            }))
+        })
+        // A topic is followed, with the messages arriving meanwhile added on the interval.
+        .map(
+            |jnv| match args.refresh.or(args.topic().map(|_| FOLLOW_INTERVAL)) {
+                Some(interval) => {
+                    let (args, workspace, plugins) =
+                        (Arc::clone(&args), workspace.clone(), plugins.clone());
+                    jnv.refresh(
+                        interval,
+                        Box::new(move || {
+                            let inputs =
+                                parse_input(&args, &workspace, &plugins, &mut Progress::silent())?;
+                            join_inputs(&args, &inputs)
+                        }),
+                    )
+                }
+                None => jnv,
+            },
+        )
+    }) {
+        Ok(jnv) => jnv,
+        Err(e) => {
+            progress.clear()?;
+            eprintln!("Error: {:?}", e);
+            return Ok(ExitCode::from(exit_status(&e)));
+        }
+    };
+    if let Some(Commands::Render {
+        width,
+        height,
+        filter,
+        plain,
+        ..
+    }) = &args.command
+    {
+        progress.clear()?;
+        let events = [Event::Paste(filter.clone())];
+        let mut stdout = io::stdout();
+        let styled = !plain && args.color != ColorChoice::Never;
+        for row in jnv.replay(&events, *width, *height, styled)? {
+            writeln!(stdout, "{}", row)?;
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    if let Some(script) = &args.replay {
+        progress.clear()?;
+        let events = jnv::parse_events(&fs::read_to_string(script)?)?;
+        let (width, height) = args.replay_size;
+        let mut stdout = io::stdout();
+        for row in jnv.replay(&events, width, height, false)? {
+            writeln!(stdout, "{}", row)?;
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    if !io::stdin().is_terminal() {
+        check_tty()?;
+    }
+    let redirected = !io::stdout().is_terminal();
+    let mut output = if redirected {
+        detach_stdout()?
+    } else {
+        Box::new(io::stdout())
+    };
+
+    let mut prompt = jnv.prompt()?;
+    progress.clear()?;
+    if !args.no_alt_screen {
+        execute!(io::stdout(), EnterAlternateScreen, cursor::MoveTo(0, 0))?;
+        jnv::use_alt_screen();
+    }
+    execute!(io::stdout(), EnableBracketedPaste)?;
+    // A panic is turned into an error, for the report to be offered.
+    panic::set_hook(Box::new(|info| log_message(&info.to_string())));
+    let ret = match panic::catch_unwind(AssertUnwindSafe(|| prompt.run())) {
+        Ok(ret) => ret.map_err(anyhow::Error::from),
+        Err(payload) => Err(anyhow!(
+            "jnv panicked: {}",
+            payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown cause")
+        )),
+    };
+    let _ = panic::take_hook();
+    // Line wrapping may have been disabled in the JSON viewer.
+    execute!(io::stdout(), DisableBracketedPaste, EnableLineWrap)?;
+    if !args.no_alt_screen {
+        execute!(io::stdout(), LeaveAlternateScreen)?;
+    }
+
+    let outcome = match ret {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            offer_report(&e)?;
+            return Ok(ExitCode::from(EXIT_ERROR));
+        }
+    };
+    match outcome {
+        Outcome::Accepted {
+            query,
+            results,
+            edits,
+            raw_output,
+            compact_output,
+            ascii_output,
+            output_format,
+            colors,
+        } => {
+            if let (Some(path), Some(edits)) = (&args.save_edits, edits) {
+                save_edits(path, &args.edits_format, edits)?;
+            }
+            match results {
+                Ok(results) => {
+                    if let Some(name) = &args.export {
+                        output.write_all(&plugins.export(name, &results)?)?;
+                    } else if redirected && output_format != OutputFormat::Json {
+                        write!(output, "{}", output_format.render(&results, ascii_output)?)?;
+                    } else if redirected || args.no_alt_screen {
+                        // Inline, the query is kept above the results in the scrollback.
+                        if !redirected {
+                            writeln!(output, "{}", query)?;
+                        }
+                        for result in results {
+                            let result = match (&colors, serde_json::from_str(&result)) {
+                                // Raw strings are left plain, as jq does.
+                                (Some(theme), Ok(value))
+                                    if !(raw_output
+                                        && matches!(value, serde_json::Value::String(_))) =>
+                                {
+                                    ansi(&value, theme, compact_output)
+                                }
+                                _ => format_result(&result, raw_output, compact_output)?,
+                            };
+                            if ascii_output {
+                                writeln!(output, "{}", escape_non_ascii(&result))?;
+                            } else {
+                                writeln!(output, "{}", result)?;
+                            }
+                        }
+                    } else {
+                        writeln!(output, "{}", query)?;
+                    }
+                    Ok(ExitCode::SUCCESS)
+                }
+                Err(e) => {
+                    eprintln!("Error: {:?}", e);
+                    Ok(ExitCode::from(EXIT_QUERY_FAILED))
+                }
+            }
+        }
+        Outcome::Aborted => Ok(ExitCode::from(EXIT_ABORTED)),
+        Outcome::Failed(e) => {
+            eprintln!("Error: {:?}", e);
+            Ok(ExitCode::from(exit_status(&e)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_follow_inputs() {
+        let args =
+            Args::try_parse_from(["jnv", "a.json", "render", "--width", "100", "b.json"]).unwrap();
+        assert_eq!(args.inputs, vec![PathBuf::from("a.json")]);
+        let Some(Commands::Render { width, inputs, .. }) = args.command else {
+            panic!("render is not parsed as a command");
+        };
+        assert_eq!((width, inputs), (100, vec![PathBuf::from("b.json")]));
+
+        let args = Args::try_parse_from(["jnv", "./render"]).unwrap();
+        assert_eq!(args.inputs, vec![PathBuf::from("./render")]);
+        assert!(args.command.is_none());
+    }
+
+    #[test]
+    fn hint_pane_is_configured() {
+        let args = Args::try_parse_from(["jnv", "--hint-lines", "2", "--hide-hint"]).unwrap();
+        assert_eq!((args.hint_lines, args.hide_hint), (Some(2), true));
+        let args = Args::try_parse_from(["jnv"]).unwrap();
+        assert_eq!((args.hint_lines, args.hide_hint), (None, false));
+    }
+
+    #[test]
+    fn alt_screen_is_the_default() {
+        assert!(!Args::try_parse_from(["jnv"]).unwrap().no_alt_screen);
+        assert!(
+            Args::try_parse_from(["jnv", "--no-alt-screen"])
+                .unwrap()
+                .no_alt_screen
+        );
+    }
+
+    #[test]
+    fn missing_programs_are_reported_up_front() {
+        let args =
+            Args::try_parse_from(["jnv", "--sqlite", "app.db", "--sql", "select 1"]).unwrap();
+        assert!(check_programs(&args, |_| true).is_ok());
+        let e = check_programs(&args, |program| program != "sqlite3").unwrap_err();
+        assert!(e.to_string().starts_with("--sqlite: 'sqlite3' is required"));
+        let args = Args::try_parse_from(["jnv", "a.json"]).unwrap();
+        assert!(check_programs(&args, |_| false).is_ok());
+    }
+
+    #[test]
+    fn only_parse_errors_are_invalid_input() {
+        let e = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        assert_eq!(exit_status(&anyhow::Error::from(e)), EXIT_INVALID_INPUT);
+        assert_eq!(exit_status(&anyhow!("No data found")), EXIT_ERROR);
+    }
+}
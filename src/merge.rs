@@ -0,0 +1,57 @@
+use anyhow::Result;
+use promkit::serde_json::{self, Map, Value};
+
+/// How arrays are merged when both sides of a merge hold an array.
+#[derive(Clone)]
+pub enum ArrayStrategy {
+    /// The later array replaces the earlier one, as in RFC 7386.
+    Replace,
+    /// The later array is appended to the earlier one.
+    Concat,
+}
+
+/// Merges the patch into the target following JSON Merge Patch (RFC 7386).
+///
+/// Objects are merged recursively and `null` in the patch removes the key,
+/// while any other value replaces the target (arrays depending on the strategy).
+fn merge(target: &mut Value, patch: Value, arrays: &ArrayStrategy) {
+    match patch {
+        Value::Object(patch) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            if let Value::Object(target) = target {
+                for (key, value) in patch {
+                    if value.is_null() {
+                        target.remove(&key);
+                    } else {
+                        merge(target.entry(key).or_insert(Value::Null), value, arrays);
+                    }
+                }
+            }
+        }
+        Value::Array(patch) => match (target, arrays) {
+            (Value::Array(target), ArrayStrategy::Concat) => target.extend(patch),
+            (target, _) => *target = Value::Array(patch),
+        },
+        patch => *target = patch,
+    }
+}
+
+/// Deep-merges every JSON document in the inputs, in order, into a single document.
+pub fn merge_documents(inputs: &[String], arrays: &ArrayStrategy) -> Result<String> {
+    let mut ret: Option<Value> = None;
+    for input in inputs {
+        for document in serde_json::Deserializer::from_str(input).into_iter::<Value>() {
+            let document = document?;
+            match ret.as_mut() {
+                Some(target) => merge(target, document, arrays),
+                None => ret = Some(document),
+            }
+        }
+    }
+    Ok(ret
+        .map(|merged| serde_json::to_string_pretty(&merged))
+        .transpose()?
+        .unwrap_or_default())
+}
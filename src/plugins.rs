@@ -0,0 +1,189 @@
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::jnv;
+
+#[cfg(feature = "wasm")]
+mod wasm;
+
+/// Plugins in the config directory (e.g. `~/.config/jnv/plugins/`),
+/// which extend jnv without being compiled into it, as jq files, executables
+/// in any language, or WebAssembly modules run sandboxed with the `wasm` feature:
+/// - `*.jq` files define jq functions available in every filter.
+/// - `functions-<name>.wasm` modules return jq function definitions
+///   available in every filter, given no input.
+/// - `decode-<extension>` executables or modules convert the input files
+///   with the extension, given as their input, into JSON (e.g. `decode-yaml`).
+/// - `export-<name>` executables or modules convert the results of the accepted filter,
+///   given as JSON Lines, for `--export <name>`.
+/// - The `hooks` executable, or with the `scripting` feature the `hooks.rhai` script,
+///   is called on events of the session.
+///
+/// The directory is listed once, when opened.
+#[derive(Clone, Default)]
+pub struct Plugins {
+    /// The files of the directory, in name order.
+    files: Vec<PathBuf>,
+}
+
+/// A plugin converting its input into its output.
+pub enum Plugin {
+    /// An executable given the input on standard input.
+    Executable(PathBuf),
+    /// A WebAssembly module run sandboxed.
+    #[cfg(feature = "wasm")]
+    Module(PathBuf),
+}
+
+impl Plugin {
+    /// Runs the plugin with the input, returning its output.
+    pub fn run(&self, input: Vec<u8>) -> Result<Vec<u8>> {
+        match self {
+            Self::Executable(path) => run_executable(path, input),
+            #[cfg(feature = "wasm")]
+            Self::Module(path) => wasm::run(path, &input),
+        }
+    }
+}
+
+impl Plugins {
+    pub fn open() -> Self {
+        let Some(dir) = config_dir().map(|dir| dir.join("plugins")) else {
+            return Self::default();
+        };
+        let mut files = fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.is_file())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        files.sort();
+        Self { files }
+    }
+
+    /// Returns the jq function definitions of the `.jq` files and the modules, in name order,
+    /// with a message for each plugin skipped since its definitions fail to compile.
+    pub fn definitions(&self) -> Result<(String, Vec<String>)> {
+        let (mut ret, mut skipped) = (String::new(), Vec::new());
+        for file in &self.files {
+            let definitions = match file.extension().and_then(|ext| ext.to_str()) {
+                Some("jq") => fs::read_to_string(file)?,
+                #[cfg(feature = "wasm")]
+                Some("wasm") if has_prefix(file, "functions-") => {
+                    match wasm::run(file, &[]).and_then(|output| Ok(String::from_utf8(output)?)) {
+                        Ok(definitions) => definitions,
+                        Err(e) => {
+                            skipped.push(format!("Skipped '{}': {}", file.display(), e));
+                            continue;
+                        }
+                    }
+                }
+                _ => continue,
+            };
+            // The definitions may use the ones of the files before.
+            let definitions = format!("{}{}\n", ret, definitions);
+            match jnv::check_jq_functions(&definitions) {
+                Ok(()) => ret = definitions,
+                Err(e) => skipped.push(format!("Skipped '{}': {}", file.display(), e)),
+            }
+        }
+        Ok((ret, skipped))
+    }
+
+    /// Returns the decoder for the file, if any.
+    pub fn decoder(&self, path: &Path) -> Option<Plugin> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        self.plugin(&format!("decode-{}", extension))
+    }
+
+    /// Runs the exporter with the results, returning its output.
+    pub fn export(&self, name: &str, results: &[String]) -> Result<Vec<u8>> {
+        let exporter = self
+            .plugin(&format!("export-{}", name))
+            .ok_or_else(|| anyhow!("No exporter plugin named 'export-{}'", name))?;
+        exporter.run((results.join("\n") + "\n").into_bytes())
+    }
+
+    /// Returns the hooks script, if any, preferring `hooks.rhai` with the `scripting` feature.
+    pub fn hooks(&self) -> Option<PathBuf> {
+        #[cfg(feature = "scripting")]
+        if let Some(script) = self.find("hooks.rhai") {
+            return Some(script);
+        }
+        self.executable("hooks")
+    }
+
+    /// Returns the executable or, with the `wasm` feature, the module of the name.
+    fn plugin(&self, name: &str) -> Option<Plugin> {
+        #[cfg(feature = "wasm")]
+        if let Some(module) = self.find(&format!("{}.wasm", name)) {
+            return Some(Plugin::Module(module));
+        }
+        self.executable(name).map(Plugin::Executable)
+    }
+
+    fn executable(&self, name: &str) -> Option<PathBuf> {
+        self.find(name)
+            .or_else(|| self.find(&format!("{}.exe", name)))
+    }
+
+    fn find(&self, name: &str) -> Option<PathBuf> {
+        self.files
+            .iter()
+            .find(|file| file.file_name().is_some_and(|file| file == name))
+            .cloned()
+    }
+}
+
+#[cfg(feature = "wasm")]
+fn has_prefix(file: &Path, prefix: &str) -> bool {
+    file.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with(prefix))
+}
+
+/// Runs the executable with the input on standard input, returning its output.
+fn run_executable(path: &Path, input: Vec<u8>) -> Result<Vec<u8>> {
+    let mut child = Command::new(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    // Write on another thread, not to block on the executable filling its output.
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("Failed to write to '{}'", path.display()))?;
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    writer
+        .join()
+        .map_err(|_| anyhow!("Failed to write to '{}'", path.display()))??;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "'{}' failed with {}",
+            path.display(),
+            output.status
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Returns the directory of the user's settings for jnv
+/// (e.g. `~/.config/jnv`), if any.
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir).join("jnv"));
+    }
+    if cfg!(windows) {
+        return env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join("jnv"));
+    }
+    env::var_os("HOME").map(|dir| PathBuf::from(dir).join(".config/jnv"))
+}
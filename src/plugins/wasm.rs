@@ -0,0 +1,102 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use wasmi::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+/// The instructions a module may run for one input, about a minute's worth,
+/// after which it is stopped as runaway.
+const FUEL: u64 = 50_000_000_000;
+/// The memory a module may grow to.
+const MAX_MEMORY: usize = 1 << 30;
+
+/// Runs the WebAssembly module with the input, returning its output.
+///
+/// The module is sandboxed: no function is provided for it to import,
+/// so it can only compute its output from its input, within the fuel and the memory.
+/// It exports its `memory`, an `alloc(len: i32) -> i32` function returning
+/// where to write the input, and a `run(ptr: i32, len: i32) -> i64` function
+/// returning where its output is, as the pointer in the upper 32 bits
+/// and the length in the lower 32 bits. It fails by trapping (e.g. `unreachable`).
+pub fn run(path: &Path, input: &[u8]) -> Result<Vec<u8>> {
+    run_module(&fs::read(path)?, input, FUEL)
+        .map_err(|e| anyhow!("WebAssembly plugin '{}' failed: {}", path.display(), e))
+}
+
+fn run_module(wasm: &[u8], input: &[u8], fuel: u64) -> Result<Vec<u8>> {
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, wasm)?;
+    let limits = StoreLimitsBuilder::new()
+        .memory_size(MAX_MEMORY)
+        .instances(1)
+        .build();
+    let mut store = Store::new(&engine, limits);
+    store.limiter(|limits: &mut StoreLimits| limits);
+    store.set_fuel(fuel).map_err(|e| anyhow!("{}", e))?;
+    let instance = Linker::<StoreLimits>::new(&engine)
+        .instantiate(&mut store, &module)?
+        .start(&mut store)?;
+    let memory = instance
+        .get_memory(&store, "memory")
+        .ok_or_else(|| anyhow!("no memory is exported"))?;
+    let alloc = instance.get_typed_func::<i32, i32>(&store, "alloc")?;
+    let run = instance.get_typed_func::<(i32, i32), i64>(&store, "run")?;
+
+    let len = i32::try_from(input.len()).map_err(|_| anyhow!("the input is too large"))?;
+    let ptr = alloc.call(&mut store, len)?;
+    memory
+        .write(&mut store, ptr as u32 as usize, input)
+        .map_err(|e| anyhow!("{}", e))?;
+    let packed = run.call(&mut store, (ptr, len))?;
+    let mut output = vec![0; packed as u32 as usize];
+    memory
+        .read(&store, (packed >> 32) as u32 as usize, &mut output)
+        .map_err(|e| anyhow!("{}", e))?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the input as is, from where `alloc` put it past the first page.
+    const ECHO: &str = r#"
+        (module
+          (memory (export "memory") 2)
+          (func (export "alloc") (param i32) (result i32) i32.const 65536)
+          (func (export "run") (param i32 i32) (result i64)
+            (i64.or
+              (i64.shl (i64.extend_i32_u (local.get 0)) (i64.const 32))
+              (i64.extend_i32_u (local.get 1)))))
+    "#;
+
+    #[test]
+    fn modules_run_in_the_sandbox() {
+        let echo = wat::parse_str(ECHO).unwrap();
+        assert_eq!(
+            run_module(&echo, b"{\"a\": 1}", FUEL).unwrap(),
+            b"{\"a\": 1}"
+        );
+
+        let spin = wat::parse_str(
+            r#"(module
+                 (memory (export "memory") 1)
+                 (func (export "alloc") (param i32) (result i32) i32.const 0)
+                 (func (export "run") (param i32 i32) (result i64)
+                   (loop $spin (br $spin))
+                   i64.const 0))"#,
+        )
+        .unwrap();
+        assert!(run_module(&spin, b"", 1_000_000).is_err());
+
+        let escape = wat::parse_str(
+            r#"(module
+                 (import "wasi_snapshot_preview1" "fd_write"
+                   (func (param i32 i32 i32 i32) (result i32)))
+                 (memory (export "memory") 1))"#,
+        )
+        .unwrap();
+        assert!(run_module(&escape, b"", FUEL).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+use std::{
+    io::{self, IsTerminal, Read, Write},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use promkit::crossterm::{
+    cursor, execute,
+    terminal::{Clear, ClearType},
+};
+
+/// Inputs smaller than this are loaded without reporting progress,
+/// since they are ready before the report could be read.
+const THRESHOLD: u64 = 32 * 1024 * 1024;
+/// The number of bytes read at a time.
+const CHUNK_SIZE: usize = 1024 * 1024;
+/// The width of the progress bar.
+const BAR_WIDTH: usize = 30;
+/// How long standard input stays silent before showing that jnv is waiting for it.
+const WAIT_THRESHOLD: Duration = Duration::from_secs(1);
+/// How often the time spent waiting for standard input is updated.
+const WAIT_TICK: Duration = Duration::from_millis(250);
+
+/// Reports the progress of loading huge inputs on standard error,
+/// until the interface takes over the terminal.
+pub struct Progress {
+    enabled: bool,
+    /// Whether a report is currently drawn.
+    active: bool,
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Self {
+            enabled: io::stderr().is_terminal(),
+            active: false,
+        }
+    }
+
+    /// Reports nothing, for inputs read again while the interface is running.
+    pub fn silent() -> Self {
+        Self {
+            enabled: false,
+            active: false,
+        }
+    }
+
+    /// Reads the reader to the end, reporting the bytes read
+    /// out of the total size, if known.
+    pub fn read<R: Read>(
+        &mut self,
+        name: &str,
+        mut reader: R,
+        total: Option<u64>,
+    ) -> Result<String> {
+        let mut ret = Vec::with_capacity(total.unwrap_or_default() as usize);
+        let mut chunk = vec![0; CHUNK_SIZE];
+        loop {
+            let n = match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            ret.extend_from_slice(&chunk[..n]);
+
+            let read = ret.len() as u64;
+            if read >= THRESHOLD {
+                let report = match total {
+                    Some(total) if total > 0 => {
+                        let ratio = read.min(total) as f64 / total as f64;
+                        let filled = (ratio * BAR_WIDTH as f64) as usize;
+                        format!(
+                            "Loading {} [{}{}] {:>3}% {}/{}",
+                            name,
+                            "#".repeat(filled),
+                            ".".repeat(BAR_WIDTH - filled),
+                            (ratio * 100.0) as usize,
+                            mebibytes(read),
+                            mebibytes(total),
+                        )
+                    }
+                    _ => format!("Loading {} {}", name, mebibytes(read)),
+                };
+                self.show(&report)?;
+            }
+        }
+        Ok(String::from_utf8(ret)?)
+    }
+
+    /// Reads standard input to the end, showing that jnv is waiting for it
+    /// until the first data arrives (e.g. from a FIFO or a slow command),
+    /// and failing if nothing arrives within the timeout, if given.
+    pub fn read_stdin(&mut self, timeout: Option<Duration>) -> Result<String> {
+        let (sender, receiver) = mpsc::channel();
+        // Reading blocks until data arrives, so it is left to a thread
+        // while the time spent waiting is checked.
+        thread::spawn(move || {
+            let mut stdin = io::stdin().lock();
+            let mut chunk = vec![0; CHUNK_SIZE];
+            loop {
+                let ret = match stdin.read(&mut chunk) {
+                    Ok(n) => Ok(chunk[..n].to_vec()),
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => Err(e),
+                };
+                let done = !matches!(&ret, Ok(data) if !data.is_empty());
+                if sender.send(ret).is_err() || done {
+                    break;
+                }
+            }
+        });
+        let start = Instant::now();
+        let first = loop {
+            match receiver.recv_timeout(WAIT_TICK) {
+                Ok(ret) => break ret?,
+                Err(RecvTimeoutError::Timeout) => {
+                    let waited = start.elapsed();
+                    if let Some(timeout) = timeout.filter(|timeout| waited >= *timeout) {
+                        self.clear()?;
+                        return Err(anyhow!(
+                            "No data arrived on standard input within {:?}",
+                            timeout
+                        ));
+                    }
+                    if waited >= WAIT_THRESHOLD {
+                        self.show(&format!(
+                            "Waiting for data on stdin… {}s (Ctrl+C to abort)",
+                            waited.as_secs()
+                        ))?;
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => break Vec::new(),
+            }
+        };
+        self.clear()?;
+        let chunks = Chunks {
+            receiver,
+            current: io::Cursor::new(first),
+        };
+        self.read("stdin", chunks, None)
+    }
+
+    /// Shows the step in progress, if the input is large enough
+    /// for its loading to be reported.
+    pub fn message(&mut self, text: &str) -> Result<()> {
+        if self.active {
+            self.show(text)?;
+        }
+        Ok(())
+    }
+
+    /// Shows the step in progress, whatever the size of the input
+    /// (e.g. waiting for a command to produce the first data).
+    pub fn step(&mut self, text: &str) -> Result<()> {
+        self.show(text)
+    }
+
+    /// Clears the report, if any.
+    pub fn clear(&mut self) -> Result<()> {
+        if self.active {
+            execute!(
+                io::stderr(),
+                cursor::MoveToColumn(0),
+                Clear(ClearType::CurrentLine)
+            )?;
+            self.active = false;
+        }
+        Ok(())
+    }
+
+    fn show(&mut self, text: &str) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let mut stderr = io::stderr();
+        execute!(
+            stderr,
+            cursor::MoveToColumn(0),
+            Clear(ClearType::CurrentLine)
+        )?;
+        write!(stderr, "{}", text)?;
+        stderr.flush()?;
+        self.active = true;
+        Ok(())
+    }
+}
+
+/// The chunks of standard input read by another thread, as a reader.
+struct Chunks {
+    receiver: Receiver<io::Result<Vec<u8>>>,
+    current: io::Cursor<Vec<u8>>,
+}
+
+impl Read for Chunks {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.current.read(buf)?;
+            if n > 0 || buf.is_empty() {
+                return Ok(n);
+            }
+            match self.receiver.recv() {
+                Ok(chunk) => self.current = io::Cursor::new(chunk?),
+                // The input has ended.
+                Err(_) => return Ok(0),
+            }
+        }
+    }
+}
+
+fn mebibytes(bytes: u64) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}